@@ -26,6 +26,9 @@ enum labrador_ldpc_code {
     LABRADOR_LDPC_CODE_TM5120   = 6,
     LABRADOR_LDPC_CODE_TM6144   = 7,
     LABRADOR_LDPC_CODE_TM8192   = 8,
+    LABRADOR_LDPC_CODE_TM20480  = 9,
+    LABRADOR_LDPC_CODE_TM24576  = 10,
+    LABRADOR_LDPC_CODE_TM32768  = 11,
 };
 
 /* Useful constants for each code, for statically allocating required memory.
@@ -51,6 +54,9 @@ enum labrador_ldpc_code {
 #define LABRADOR_LDPC_N_TM5120 (5120)
 #define LABRADOR_LDPC_N_TM6144 (6140)
 #define LABRADOR_LDPC_N_TM8192 (8192)
+#define LABRADOR_LDPC_N_TM20480 (20480)
+#define LABRADOR_LDPC_N_TM24576 (24576)
+#define LABRADOR_LDPC_N_TM32768 (32768)
 #define LABRADOR_LDPC_N_(CODE) LABRADOR_LDPC_N_##CODE
 #define LABRADOR_LDPC_N(CODE)  LABRADOR_LDPC_N_(CODE)
 
@@ -63,6 +69,9 @@ enum labrador_ldpc_code {
 #define LABRADOR_LDPC_K_TM5120 (4096)
 #define LABRADOR_LDPC_K_TM6144 (4096)
 #define LABRADOR_LDPC_K_TM8192 (4096)
+#define LABRADOR_LDPC_K_TM20480 (16384)
+#define LABRADOR_LDPC_K_TM24576 (16384)
+#define LABRADOR_LDPC_K_TM32768 (16384)
 #define LABRADOR_LDPC_K_(CODE) LABRADOR_LDPC_K_##CODE
 #define LABRADOR_LDPC_K(CODE)  LABRADOR_LDPC_K_(CODE)
 
@@ -75,6 +84,9 @@ enum labrador_ldpc_code {
 #define LABRADOR_LDPC_BF_WORKING_LEN_TM5120 (5632)
 #define LABRADOR_LDPC_BF_WORKING_LEN_TM6140 (7168)
 #define LABRADOR_LDPC_BF_WORKING_LEN_TM8192 (10240)
+#define LABRADOR_LDPC_BF_WORKING_LEN_TM20480 (22528)
+#define LABRADOR_LDPC_BF_WORKING_LEN_TM24576 (28672)
+#define LABRADOR_LDPC_BF_WORKING_LEN_TM32768 (40960)
 #define LABRADOR_LDPC_BF_WORKING_LEN_(CODE) LABRADOR_LDPC_BF_WORKING_LEN_##CODE
 #define LABRADOR_LDPC_BF_WORKING_LEN(CODE)  LABRADOR_LDPC_BF_WORKING_LEN_(CODE)
 
@@ -87,6 +99,9 @@ enum labrador_ldpc_code {
 #define LABRADOR_LDPC_MS_WORKING_LEN_TM5120 (48640)
 #define LABRADOR_LDPC_MS_WORKING_LEN_TM6140 (60416)
 #define LABRADOR_LDPC_MS_WORKING_LEN_TM8192 (83968)
+#define LABRADOR_LDPC_MS_WORKING_LEN_TM20480 (194560)
+#define LABRADOR_LDPC_MS_WORKING_LEN_TM24576 (241664)
+#define LABRADOR_LDPC_MS_WORKING_LEN_TM32768 (335872)
 #define LABRADOR_LDPC_MS_WORKING_LEN_(CODE) LABRADOR_LDPC_MS_WORKING_LEN_##CODE
 #define LABRADOR_LDPC_MS_WORKING_LEN(CODE)  LABRADOR_LDPC_MS_WORKING_LEN_(CODE)
 
@@ -99,6 +114,9 @@ enum labrador_ldpc_code {
 #define LABRADOR_LDPC_MS_WORKING_U8_LEN_TM5120 (192)
 #define LABRADOR_LDPC_MS_WORKING_U8_LEN_TM6140 (384)
 #define LABRADOR_LDPC_MS_WORKING_U8_LEN_TM8192 (768)
+#define LABRADOR_LDPC_MS_WORKING_U8_LEN_TM20480 (768)
+#define LABRADOR_LDPC_MS_WORKING_U8_LEN_TM24576 (1536)
+#define LABRADOR_LDPC_MS_WORKING_U8_LEN_TM32768 (3072)
 #define LABRADOR_LDPC_MS_WORKING_U8_LEN_(CODE) LABRADOR_LDPC_MS_WORKING_U8_LEN_##CODE
 #define LABRADOR_LDPC_MS_WORKING_U8_LEN(CODE)  LABRADOR_LDPC_MS_WORKING_U8_LEN_(CODE)
 
@@ -111,6 +129,9 @@ enum labrador_ldpc_code {
 #define LABRADOR_LDPC_OUTPUT_LEN_TM5120 (704)
 #define LABRADOR_LDPC_OUTPUT_LEN_TM6140 (896)
 #define LABRADOR_LDPC_OUTPUT_LEN_TM8192 (1280)
+#define LABRADOR_LDPC_OUTPUT_LEN_TM20480 (2816)
+#define LABRADOR_LDPC_OUTPUT_LEN_TM24576 (3584)
+#define LABRADOR_LDPC_OUTPUT_LEN_TM32768 (5120)
 #define LABRADOR_LDPC_OUTPUT_LEN_(CODE) LABRADOR_LDPC_OUTPUT_LEN_##CODE
 #define LABRADOR_LDPC_OUTPUT_LEN(CODE)  LABRADOR_LDPC_OUTPUT_LEN_(CODE)
 
@@ -139,16 +160,24 @@ size_t labrador_ldpc_output_len(enum labrador_ldpc_code code);
  *
  * If `codeword` is 4-byte aligned, encoding is performed 32 bits at a time,
  * which is usually faster than byte at a time.
+ *
+ * Returns true on success, or false without modifying `codeword` if no
+ * generator matrix is available for `code`. This is always the case for the
+ * k=16384 codes (TM20480, TM24576, TM32768), and for any code whose generator
+ * feature was disabled when building the library.
  */
-void labrador_ldpc_encode(enum labrador_ldpc_code code, uint8_t *codeword);
+bool labrador_ldpc_encode(enum labrador_ldpc_code code, uint8_t *codeword);
 
 /* Encode all of `data` (k/8 bytes long) into `codeword` (n/8 bytes long),
  * first copying `data` into `codeword`, using the `code` LDPC code.
  *
  * If `codeword` is 4-byte aligned, encoding is performed 32 bits at a time,
  * which is usually faster than byte at a time.
+ *
+ * Returns true on success, or false without modifying `codeword` if no
+ * generator matrix is available for `code`, as for `labrador_ldpc_encode`.
  */
-void labrador_ldpc_copy_encode(enum labrador_ldpc_code code,
+bool labrador_ldpc_copy_encode(enum labrador_ldpc_code code,
                                const uint8_t* data, uint8_t* codeword);
 
 /* Run the BF decoder:
@@ -23,7 +23,10 @@ pub extern fn labrador_ldpc_code_k(code: LDPCCode) -> usize {
 }
 
 #[no_mangle]
-pub extern fn labrador_ldpc_encode(code: LDPCCode, codeword: *mut u8) {
+pub extern fn labrador_ldpc_encode(code: LDPCCode, codeword: *mut u8) -> bool {
+    if code.compact_generator().is_empty() {
+        return false;
+    }
     if (codeword as usize) % 4 == 0 {
         let codeword: &mut[u32] = unsafe { slice::from_raw_parts_mut(codeword as *mut u32, code.n()/32) };
         code.encode(codeword);
@@ -31,10 +34,16 @@ pub extern fn labrador_ldpc_encode(code: LDPCCode, codeword: *mut u8) {
         let codeword: &mut[u8] = unsafe { slice::from_raw_parts_mut(codeword, code.n()/8) };
         code.encode(codeword);
     }
+    true
 }
 
 #[no_mangle]
-pub extern fn labrador_ldpc_copy_encode(code: LDPCCode, data: *const u8, codeword: *mut u8) {
+pub extern fn labrador_ldpc_copy_encode(code: LDPCCode, data: *const u8,
+                                        codeword: *mut u8) -> bool
+{
+    if code.compact_generator().is_empty() {
+        return false;
+    }
     let data: &[u8] = unsafe { slice::from_raw_parts(data, code.k()/8) };
     if (codeword as usize) % 4 == 0 {
         let codeword: &mut[u32] = unsafe { slice::from_raw_parts_mut(codeword as *mut u32, code.n()/32) };
@@ -43,6 +52,7 @@ pub extern fn labrador_ldpc_copy_encode(code: LDPCCode, data: *const u8, codewor
         let codeword: &mut[u8] = unsafe { slice::from_raw_parts_mut(codeword, code.n()/8) };
         code.copy_encode(data, codeword);
     }
+    true
 }
 
 #[no_mangle]
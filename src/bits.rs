@@ -0,0 +1,205 @@
+// Copyright 2017 Adam Greig
+// Licensed under the MIT license, see LICENSE for details.
+
+//! This module provides utilities for converting between individual bits and the packed bytes
+//! used by the encoders and decoders.
+//!
+//! Codewords are packed most significant bit first: bit `i` of a codeword is stored in byte
+//! `i/8`, at bit position `7 - (i%8)`. So the first bit of a codeword is the top bit of the
+//! first byte.
+//!
+//! ```
+//! # #[cfg(feature = "tc128")] {
+//! # use labrador_ldpc::LDPCCode;
+//! # use labrador_ldpc::bits::{pack_bits, unpack_bits};
+//! let code = LDPCCode::TC128;
+//!
+//! // Pack 64 bits of data, alternating 0 and 1
+//! let bits: Vec<u8> = (0..code.k()).map(|i| (i % 2) as u8).collect();
+//! let mut txdata = vec![0u8; code.k()/8];
+//! pack_bits(&bits, &mut txdata);
+//! assert_eq!(txdata[0], 0b0101_0101);
+//!
+//! // Encode, then unpack the codeword to see the data bits are sent first
+//! let mut txcode = vec![0u8; code.n()/8];
+//! code.copy_encode(&txdata, &mut txcode);
+//! let mut txbits = vec![0u8; code.n()];
+//! unpack_bits(&txcode, &mut txbits);
+//! assert_eq!(&txbits[..code.k()], &bits[..]);
+//! # }
+//! ```
+//!
+//! Other implementations may pack bits least significant bit first instead. The `_ordered`
+//! encoder and decoders, such as `LDPCCode::copy_encode_ordered`, take a `BitOrder` so that
+//! bytes can be exchanged with them directly, without reversing each byte by hand.
+
+/// Order in which bits are packed into each byte.
+#[derive(Copy,Clone,Debug,Default,Eq,PartialEq,Hash)]
+pub enum BitOrder {
+    /// The first bit is the most significant bit of each byte, as used throughout this crate.
+    #[default]
+    MsbFirst,
+
+    /// The first bit is the least significant bit of each byte.
+    LsbFirst,
+}
+
+impl BitOrder {
+    /// Convert `bytes` in place between this order and `BitOrder::MsbFirst`.
+    ///
+    /// Does nothing for `BitOrder::MsbFirst`, and reverses the bits of every byte for
+    /// `BitOrder::LsbFirst`. As this is its own inverse, the same call converts either way.
+    pub fn reorder(self, bytes: &mut [u8]) {
+        if self == BitOrder::LsbFirst {
+            for byte in bytes {
+                *byte = byte.reverse_bits();
+            }
+        }
+    }
+}
+
+/// Pack individual bits into bytes, most significant bit first.
+///
+/// Each entry of `bits` is one bit; any non-zero value is treated as a 1.
+///
+/// `bits` must be exactly `8 * out.len()` long.
+pub fn pack_bits(bits: &[u8], out: &mut [u8]) {
+    assert_eq!(bits.len(), out.len() * 8, "bits.len() != out.len() * 8");
+    for (byte, chunk) in out.iter_mut().zip(bits.chunks(8)) {
+        *byte = 0;
+        for (idx, bit) in chunk.iter().enumerate() {
+            if *bit != 0 {
+                *byte |= 1 << (7 - idx);
+            }
+        }
+    }
+}
+
+/// Unpack bytes into individual bits, most significant bit first.
+///
+/// Each entry of `out` is set to 0 or 1.
+///
+/// `out` must be exactly `8 * packed.len()` long.
+pub fn unpack_bits(packed: &[u8], out: &mut [u8]) {
+    assert_eq!(out.len(), packed.len() * 8, "out.len() != packed.len() * 8");
+    for (idx, bit) in out.iter_mut().enumerate() {
+        *bit = (packed[idx/8] >> (7 - (idx%8))) & 1;
+    }
+}
+
+/// Pack `bool`s into bytes, most significant bit first.
+///
+/// Each entry of `bits` is one bit, with `true` for a 1.
+///
+/// `bits` must be exactly `8 * out.len()` long.
+pub fn pack_bools(bits: &[bool], out: &mut [u8]) {
+    assert_eq!(bits.len(), out.len() * 8, "bits.len() != out.len() * 8");
+    for (byte, chunk) in out.iter_mut().zip(bits.chunks(8)) {
+        *byte = 0;
+        for (idx, bit) in chunk.iter().enumerate() {
+            if *bit {
+                *byte |= 1 << (7 - idx);
+            }
+        }
+    }
+}
+
+/// Unpack bytes into `bool`s, most significant bit first.
+///
+/// Each entry of `out` is set to `true` for a 1 bit.
+///
+/// `out` must be exactly `8 * packed.len()` long.
+pub fn unpack_bools(packed: &[u8], out: &mut [bool]) {
+    assert_eq!(out.len(), packed.len() * 8, "out.len() != packed.len() * 8");
+    for (idx, bit) in out.iter_mut().enumerate() {
+        *bit = (packed[idx/8] >> (7 - (idx%8))) & 1 == 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::v1::*;
+
+    use super::{pack_bits, unpack_bits, pack_bools, unpack_bools, BitOrder};
+
+    #[test]
+    fn test_pack_unpack() {
+        let packed: Vec<u8> = (0..=255).collect();
+        let mut bits = vec![0xAAu8; packed.len() * 8];
+        unpack_bits(&packed, &mut bits);
+        assert_eq!(&bits[8*0x81..8*0x82], &[1, 0, 0, 0, 0, 0, 0, 1]);
+        assert!(bits.iter().all(|b| *b <= 1));
+
+        let mut repacked = vec![0xAAu8; packed.len()];
+        pack_bits(&bits, &mut repacked);
+        assert_eq!(repacked, packed);
+
+        // Non-zero values are treated as 1
+        let mut byte = [0u8];
+        pack_bits(&[0, 7, 0, 0, 0, 0, 0, 255], &mut byte);
+        assert_eq!(byte[0], 0b0100_0001);
+    }
+
+    #[test]
+    fn test_pack_unpack_bools() {
+        let packed: Vec<u8> = (0..=255).collect();
+        let mut bools = vec![false; packed.len() * 8];
+        unpack_bools(&packed, &mut bools);
+        assert_eq!(&bools[8*0x81..8*0x82], &[true, false, false, false,
+                                             false, false, false, true]);
+        let mut repacked = vec![0u8; packed.len()];
+        pack_bools(&bools, &mut repacked);
+        assert_eq!(repacked, packed);
+    }
+
+    #[test]
+    fn test_bit_order() {
+        let mut bytes = [0b1000_0011, 0x0F];
+        BitOrder::default().reorder(&mut bytes);
+        assert_eq!(bytes, [0b1000_0011, 0x0F]);
+        BitOrder::LsbFirst.reorder(&mut bytes);
+        assert_eq!(bytes, [0b1100_0001, 0xF0]);
+    }
+
+    #[test]
+    #[cfg(feature = "tc256")]
+    fn test_bit_order_codec() {
+        use crate::codes::LDPCCode;
+
+        // The default order is identical to the plain encoder and decoders
+        let code = LDPCCode::TC256;
+        let data: Vec<u8> = (0..code.k()/8).map(|x| (x * 7) as u8).collect();
+        let mut msb = vec![0u8; code.n()/8];
+        code.copy_encode(&data, &mut msb);
+        let mut codeword = vec![0u8; code.n()/8];
+        code.copy_encode_ordered(&data, &mut codeword, BitOrder::MsbFirst);
+        assert_eq!(codeword, msb);
+
+        // LSB first data gives the LSB first packing of the same codeword
+        let mut lsb_data = data.clone();
+        BitOrder::LsbFirst.reorder(&mut lsb_data);
+        code.copy_encode_ordered(&lsb_data, &mut codeword, BitOrder::LsbFirst);
+        let mut lsb = msb.clone();
+        BitOrder::LsbFirst.reorder(&mut lsb);
+        assert_eq!(codeword, lsb);
+
+        // Both decoders take and give LSB first bytes
+        let mut working = vec![0u8; code.decode_bf_working_len()];
+        let mut output = vec![0u8; code.output_len()];
+        let mut rx = lsb.clone();
+        rx[3] ^= 0x01;
+        let (success, _) = code.decode_bf_ordered(&rx, &mut output, &mut working, 20,
+                                                  BitOrder::LsbFirst);
+        assert!(success);
+        assert_eq!(&output[..code.n()/8], &lsb[..]);
+
+        let mut llrs = vec![0i8; code.n()];
+        code.hard_to_llrs(&msb, &mut llrs);
+        let mut working = vec![0i8; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        let (success, _) = code.decode_ms_ordered(&llrs, &mut output, &mut working,
+                                                  &mut working_u8, 20, BitOrder::LsbFirst);
+        assert!(success);
+        assert_eq!(&output[..code.n()/8], &lsb[..]);
+    }
+}
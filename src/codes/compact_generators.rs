@@ -11,6 +11,7 @@
 // These literals won't be any more readable with extra underscores.
 #![allow(clippy::unreadable_literal)]
 
+#[cfg(feature = "tc128")]
 /// Compact generator matrix for the TC128 code
 ///
 /// P is 64x64, `circulant_size`=16, so we have 4 blocks of 1 u64 per row
@@ -18,6 +19,7 @@ pub static TC128_G: [u64; 4 * 1] = [
     0x0E69166BEF4C0BC2, 0x7766137EBB248418, 0xC480FEB9CD53A713, 0x4EAA22FA465EEA11,
 ];
 
+#[cfg(feature = "tc256")]
 /// Compact generator matrix for the TC256 code
 ///
 /// P is 128x128, `circulant_size`=32, so we have 4 blocks of 2 u64 per row
@@ -26,6 +28,7 @@ pub static TC256_G: [u64; 4 * 2] = [
     0x7DF83F76A5FF4C38, 0x8E6C0D4E025EB712, 0xBAA37B3260CB31C5, 0xD0F66A31FAF511BC,
 ];
 
+#[cfg(feature = "tc512")]
 /// Compact generator matrix for the TC512 code
 ///
 /// P is 256x256, `circulant_size`=64, so we have 4 blocks of 4 u64 per row
@@ -36,6 +39,7 @@ pub static TC512_G: [u64; 4 * 4] = [
     0x28706BD045300258, 0x2DAB85F05B9201D0, 0x8DFDEE2D9D84CA88, 0xB371FAE63A4EB07E,
 ];
 
+#[cfg(feature = "tm1280")]
 /// Compact generator matrix for the TM1280 code
 ///
 /// P is 1024x256, `circulant_size`=32, so we have 32 blocks of 4 u64 per row
@@ -74,6 +78,7 @@ pub static TM1280_G: [u64; 32 * 4] = [
     0x32FEFCAF70863B75, 0x3846F110C4E23DFF, 0x79D3F753064648FA, 0x830452F5B9ED8445
 ];
 
+#[cfg(feature = "tm1536")]
 /// Compact generator matrix for the TM1536 code
 ///
 /// P is 1024x512, `circulant_size`=64, so we have 16 blocks of 8 u64 per row
@@ -112,6 +117,7 @@ pub static TM1536_G: [u64; 16 * 8] = [
     0xC93CD9CA23658555, 0x7DDEC5E9451AD519, 0xB122C72A6177EE99, 0x1290B4C6B007D973
 ];
 
+#[cfg(feature = "tm2048")]
 /// Compact generator matrix for the TM2048 code
 ///
 /// P is 1024x1024, `circulant_size`=128, so we have 8 blocks of 16 u64 per row
@@ -150,6 +156,7 @@ pub static TM2048_G: [u64; 8 * 16] = [
     0xBCC64D21A65DB379, 0xABE2E4DC21F109FF, 0x2EC0CE7B5D40973D, 0x13ECF713B01C6F10
 ];
 
+#[cfg(feature = "tm5120")]
 /// Compact generator matrix for the TM5120 code
 ///
 /// P is 4096x1024, `circulant_size`=128, so we have 32 blocks of 16 u64 per row
@@ -284,6 +291,7 @@ pub static TM5120_G: [u64; 32 * 16] = [
     0xBCC64D21A65DB379, 0xABE2E4DC21F109FF, 0x2EC0CE7B5D40973D, 0x13ECF713B01C6F10,
 ];
 
+#[cfg(feature = "tm6144")]
 /// Compact generator matrix for the TM6144 code
 ///
 /// P is 4096x2048, `circulant_size`=256, so we have 16 blocks of 32 u64 per row
@@ -418,6 +426,7 @@ pub static TM6144_G: [u64; 16 * 32] = [
     0xF2DA86FE2BAA7E67, 0x5DFDED45499AF1B4, 0x0AE292B1DE6B7A7D, 0x4799C3B88177704D
 ];
 
+#[cfg(feature = "tm8192")]
 /// Compact generator matrix for the TM8192 code
 ///
 /// P is 4096x4096, `circulant_size`=512, so we have 8 blocks of 64 u64 per row
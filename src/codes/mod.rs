@@ -7,6 +7,10 @@
 // We have a bunch of expressions with +0 for clarity of where the 0 comes from
 #![allow(clippy::identity_op,clippy::erasing_op)]
 
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
+
 /// This module contains the constants representing the generator matrices.
 ///
 /// They are in a compact form: for each systematic generator matrix, we take just the
@@ -34,7 +38,11 @@ mod compact_parity_checks;
 ///
 /// For code parameters see the [`CodeParams`](struct.CodeParams.html) structs also in this module:
 /// [`TC128_PARAMS`](constant.TC128_PARAMS.html) etc.
+///
+/// This enum is `#[non_exhaustive]` so that further codes can be added without a breaking
+/// change; matches on it outside this crate need a wildcard arm.
 #[repr(C)]
+#[non_exhaustive]
 #[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
 pub enum LDPCCode {
     /// n=128 k=64 r=1/2
@@ -63,6 +71,48 @@ pub enum LDPCCode {
 
     /// n=8192 k=4096 r=1/2
     TM8192 = 8,
+
+    /// n=20480 k=16384 r=4/5
+    ///
+    /// No generator matrix is included for this code; see `compact_generator()`.
+    TM20480 = 9,
+
+    /// n=24576 k=16384 r=2/3
+    ///
+    /// No generator matrix is included for this code; see `compact_generator()`.
+    TM24576 = 10,
+
+    /// n=32768 k=16384 r=1/2
+    ///
+    /// No generator matrix is included for this code; see `compact_generator()`.
+    TM32768 = 11,
+}
+
+impl fmt::Display for LDPCCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when parsing an `LDPCCode` from a string which is not the name of a code.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub struct ParseCodeError;
+
+impl fmt::Display for ParseCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("unknown LDPC code name")
+    }
+}
+
+impl FromStr for LDPCCode {
+    type Err = ParseCodeError;
+
+    /// Parse a code from its name, such as `"TM2048"`, ignoring ASCII case.
+    fn from_str(s: &str) -> Result<LDPCCode, ParseCodeError> {
+        LDPCCode::all().iter().cloned()
+                       .find(|code| code.as_str().eq_ignore_ascii_case(s))
+                       .ok_or(ParseCodeError)
+    }
 }
 
 /// Parameters for a given LDPC code.
@@ -105,6 +155,35 @@ pub struct CodeParams {
     pub output_len: usize,
 }
 
+/// The family a code belongs to, from the CCSDS standard defining it.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub enum CodeFamily {
+    /// Telecommand codes (TC128, TC256, TC512), defined in CCSDS 231.1-O-1.
+    Telecommand,
+
+    /// Telemetry codes (TM1280 and larger), defined in CCSDS 131.0-B-2.
+    Telemetry,
+}
+
+/// Summary of a code's parameters, for presenting a choice of codes, see `LDPCCode::PARAMS`.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub struct CodeInfo {
+    /// Block length (number of bits transmitted/received, aka code length).
+    pub n: usize,
+
+    /// Data length (number of bits of user information, aka code dimension).
+    pub k: usize,
+
+    /// Code rate k/n as `(numerator, denominator)` in lowest terms, such as `(4, 5)`.
+    pub rate: (usize, usize),
+
+    /// Number of parity bits not transmitted.
+    pub punctured_bits: usize,
+
+    /// The family of this code.
+    pub family: CodeFamily,
+}
+
 /// Code parameters for the TC128 code
 pub const TC128_PARAMS: CodeParams = CodeParams {
     n: 128,
@@ -240,6 +319,132 @@ pub const TM8192_PARAMS: CodeParams = CodeParams {
     output_len: (8192 + 2048)/8,
 };
 
+/// Code parameters for the TM20480 code
+pub const TM20480_PARAMS: CodeParams = CodeParams {
+    n: 20480,
+    k: 16384,
+    punctured_bits: 2048,
+    submatrix_size: 2048,
+    circulant_size: 2048/4,
+    paritycheck_sum: 79872,
+
+    decode_bf_working_len: 20480 + 2048,
+    decode_ms_working_len: 2*79872 + 3*20480 + 3*2048 - 2*16384,
+    decode_ms_working_u8_len: (20480 + 2048 - 16384)/8,
+    output_len: (20480 + 2048)/8,
+};
+
+/// Code parameters for the TM24576 code
+pub const TM24576_PARAMS: CodeParams = CodeParams {
+    n: 24576,
+    k: 16384,
+    punctured_bits: 4096,
+    submatrix_size: 4096,
+    circulant_size: 4096/4,
+    paritycheck_sum: 94208,
+
+    decode_bf_working_len: 24576 + 4096,
+    decode_ms_working_len: 2*94208 + 3*24576 + 3*4096 - 2*16384,
+    decode_ms_working_u8_len: (24576 + 4096 - 16384)/8,
+    output_len: (24576 + 4096)/8,
+};
+
+/// Code parameters for the TM32768 code
+pub const TM32768_PARAMS: CodeParams = CodeParams {
+    n: 32768,
+    k: 16384,
+    punctured_bits: 8192,
+    submatrix_size: 8192,
+    circulant_size: 8192/4,
+    paritycheck_sum: 122880,
+
+    decode_bf_working_len: 32768 + 8192,
+    decode_ms_working_len: 2*122880 + 3*32768 + 3*8192 - 2*16384,
+    decode_ms_working_u8_len: (32768 + 8192 - 16384)/8,
+    output_len: (32768 + 8192)/8,
+};
+
+/// Compile-time description of an LDPC code.
+///
+/// Each variant of `LDPCCode` has a corresponding zero-sized type implementing this trait, such
+/// as [`Tc128`](struct.Tc128.html), whose associated constants give the code's parameters and
+/// required buffer lengths. These can be used to size arrays statically:
+///
+/// ```
+/// # use labrador_ldpc::codes::{Code, Tc128};
+/// let mut working = [0i8; Tc128::DECODE_MS_WORKING_LEN];
+/// let mut working_u8 = [0u8; Tc128::DECODE_MS_WORKING_U8_LEN];
+/// let mut output = [0u8; Tc128::OUTPUT_LEN];
+/// let llrs = [1i8; Tc128::N];
+/// let (success, _) = Tc128::CODE.decode_ms(&llrs, &mut output, &mut working,
+///                                          &mut working_u8, 10);
+/// assert!(success);
+/// ```
+///
+/// Use `LDPCCode` directly when the code is only known at runtime.
+pub trait Code {
+    /// The corresponding `LDPCCode` variant, on which all the encoder and decoder methods live.
+    const CODE: LDPCCode;
+
+    /// The parameters for this code.
+    const PARAMS: CodeParams;
+
+    /// Block length (number of bits transmitted/received).
+    const N: usize = Self::PARAMS.n;
+
+    /// Data length (number of bits of user information).
+    const K: usize = Self::PARAMS.k;
+
+    /// Number of parity bits not transmitted.
+    const PUNCTURED_BITS: usize = Self::PARAMS.punctured_bits;
+
+    /// Length of [u64] of the compact generator matrix; see `LDPCCode::compact_generator_len()`.
+    const COMPACT_GENERATOR_LEN: usize =
+        (Self::PARAMS.k / Self::PARAMS.circulant_size) * (Self::PARAMS.n - Self::PARAMS.k) / 64;
+
+    /// Length of [u8] required for the working area of `decode_bf`.
+    const DECODE_BF_WORKING_LEN: usize = Self::PARAMS.decode_bf_working_len;
+
+    /// Length of [T] required for the working area of `decode_ms`.
+    const DECODE_MS_WORKING_LEN: usize = Self::PARAMS.decode_ms_working_len;
+
+    /// Length of [u8] required for the working_u8 area of `decode_ms`.
+    const DECODE_MS_WORKING_U8_LEN: usize = Self::PARAMS.decode_ms_working_u8_len;
+
+    /// Length of [u8] required for the output of any decoder.
+    const OUTPUT_LEN: usize = Self::PARAMS.output_len;
+
+    /// Length of [u8] required for a syndrome; see `LDPCCode::syndrome_len()`.
+    const SYNDROME_LEN: usize =
+        (Self::PARAMS.n + Self::PARAMS.punctured_bits - Self::PARAMS.k) / 8;
+}
+
+macro_rules! typed_code {
+    ($name:ident, $code:ident, $params:ident) => {
+        #[doc = concat!("Zero-sized type for the `", stringify!($code), "` code, see `Code`.")]
+        #[derive(Copy,Clone,Debug,Default,Eq,PartialEq,Hash)]
+        pub struct $name;
+
+        impl Code for $name {
+            const CODE: LDPCCode = LDPCCode::$code;
+            const PARAMS: CodeParams = $params;
+        }
+    }
+}
+
+typed_code!(Tc128,   TC128,   TC128_PARAMS);
+typed_code!(Tc256,   TC256,   TC256_PARAMS);
+typed_code!(Tc512,   TC512,   TC512_PARAMS);
+typed_code!(Tm1280,  TM1280,  TM1280_PARAMS);
+typed_code!(Tm1536,  TM1536,  TM1536_PARAMS);
+typed_code!(Tm2048,  TM2048,  TM2048_PARAMS);
+typed_code!(Tm5120,  TM5120,  TM5120_PARAMS);
+typed_code!(Tm6144,  TM6144,  TM6144_PARAMS);
+typed_code!(Tm8192,  TM8192,  TM8192_PARAMS);
+typed_code!(Tm20480, TM20480, TM20480_PARAMS);
+typed_code!(Tm24576, TM24576, TM24576_PARAMS);
+typed_code!(Tm32768, TM32768, TM32768_PARAMS);
+
 /// Iterator over a code's parity check matrix.
 ///
 /// Iterating gives values `(check, variable)` which are the indices
@@ -362,9 +567,144 @@ impl Iterator for ParityIter {
     }
 }
 
+/// Iterator over a code's generator matrix.
+///
+/// Iterating gives values `(row, column)` which are the indices of each 1 in the systematic
+/// generator matrix, row by row, where `row` is a data bit in `0..k` and `column` is a
+/// codeword bit in `0..n`. Each row starts with its identity entry `(row, row)`.
+///
+/// `GeneratorIter` is obtained from `LDPCCode::iter_generator()`.
+pub struct GeneratorIter {
+    gc: &'static [u64],
+    k: usize,
+    r: usize,
+    b: usize,
+    row: usize,
+    col: usize,
+    identity_done: bool,
+}
+
+impl GeneratorIter {
+    /// Check whether parity column `col` of generator row `row` is set.
+    fn bit(&self, row: usize, col: usize) -> bool {
+        // Each row is the first row of its circulant rotated right by its offset in the
+        // circulant, separately within each block of b parity columns.
+        let (crow, offset) = (row / self.b, row % self.b);
+        let (block, pos) = (col / self.b, col % self.b);
+        let src = block * self.b + (pos + self.b - offset) % self.b;
+        let word = self.gc[crow * (self.r / 64) + src / 64];
+        (word >> (63 - (src % 64))) & 1 == 1
+    }
+}
+
+impl Iterator for GeneratorIter {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        while self.row < self.k {
+            if !self.identity_done {
+                self.identity_done = true;
+                return Some((self.row, self.row));
+            }
+            while self.col < self.r {
+                let col = self.col;
+                self.col += 1;
+                if self.bit(self.row, col) {
+                    return Some((self.row, self.k + col));
+                }
+            }
+            self.row += 1;
+            self.col = 0;
+            self.identity_done = false;
+        }
+        None
+    }
+}
+
+/// Every variant of `LDPCCode`, in order.
+static ALL_CODES: [LDPCCode; 12] = [
+    LDPCCode::TC128,   LDPCCode::TC256,   LDPCCode::TC512,
+    LDPCCode::TM1280,  LDPCCode::TM1536,  LDPCCode::TM2048,
+    LDPCCode::TM5120,  LDPCCode::TM6144,  LDPCCode::TM8192,
+    LDPCCode::TM20480, LDPCCode::TM24576, LDPCCode::TM32768,
+];
+
 impl LDPCCode {
+    /// Get every available code, in the same order as the enum.
+    ///
+    /// Note that this includes the k=16384 codes, which have no generator matrix, so check
+    /// `compact_generator()` is not empty before encoding with each code.
+    pub const fn all() -> &'static [LDPCCode] {
+        &ALL_CODES
+    }
+
+    /// Every available code with a summary of its parameters, in the same order as `all()`.
+    ///
+    /// This is convenient for configuration tools, or for presenting a choice of codes
+    /// grouped by `CodeFamily`.
+    pub const PARAMS: &'static [(LDPCCode, CodeInfo)] = &[
+        (LDPCCode::TC128,   LDPCCode::TC128.info()),
+        (LDPCCode::TC256,   LDPCCode::TC256.info()),
+        (LDPCCode::TC512,   LDPCCode::TC512.info()),
+        (LDPCCode::TM1280,  LDPCCode::TM1280.info()),
+        (LDPCCode::TM1536,  LDPCCode::TM1536.info()),
+        (LDPCCode::TM2048,  LDPCCode::TM2048.info()),
+        (LDPCCode::TM5120,  LDPCCode::TM5120.info()),
+        (LDPCCode::TM6144,  LDPCCode::TM6144.info()),
+        (LDPCCode::TM8192,  LDPCCode::TM8192.info()),
+        (LDPCCode::TM20480, LDPCCode::TM20480.info()),
+        (LDPCCode::TM24576, LDPCCode::TM24576.info()),
+        (LDPCCode::TM32768, LDPCCode::TM32768.info()),
+    ];
+
+    /// Get the name of this code, such as `"TM2048"`.
+    ///
+    /// This is the same as the enum variant name, and can be parsed back with `str::parse`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            LDPCCode::TC128   => "TC128",
+            LDPCCode::TC256   => "TC256",
+            LDPCCode::TC512   => "TC512",
+            LDPCCode::TM1280  => "TM1280",
+            LDPCCode::TM1536  => "TM1536",
+            LDPCCode::TM2048  => "TM2048",
+            LDPCCode::TM5120  => "TM5120",
+            LDPCCode::TM6144  => "TM6144",
+            LDPCCode::TM8192  => "TM8192",
+            LDPCCode::TM20480 => "TM20480",
+            LDPCCode::TM24576 => "TM24576",
+            LDPCCode::TM32768 => "TM32768",
+        }
+    }
+
+    /// Get the family of this code.
+    pub const fn family(self) -> CodeFamily {
+        match self {
+            LDPCCode::TC128 | LDPCCode::TC256 | LDPCCode::TC512 => CodeFamily::Telecommand,
+            _ => CodeFamily::Telemetry,
+        }
+    }
+
+    /// Get a summary of this code's parameters.
+    pub const fn info(self) -> CodeInfo {
+        // Reduce k/n to lowest terms
+        let (mut a, mut b) = (self.n(), self.k());
+        while b != 0 {
+            let t = a % b;
+            a = b;
+            b = t;
+        }
+        CodeInfo {
+            n: self.n(),
+            k: self.k(),
+            rate: (self.k() / a, self.n() / a),
+            punctured_bits: self.punctured_bits(),
+            family: self.family(),
+        }
+    }
+
     /// Get the code parameters for a specific LDPC code
-    pub fn params(self) -> CodeParams {
+    pub const fn params(self) -> CodeParams {
         match self {
             LDPCCode::TC128  => TC128_PARAMS,
             LDPCCode::TC256  => TC256_PARAMS,
@@ -375,51 +715,131 @@ impl LDPCCode {
             LDPCCode::TM5120 => TM5120_PARAMS,
             LDPCCode::TM6144 => TM6144_PARAMS,
             LDPCCode::TM8192 => TM8192_PARAMS,
+            LDPCCode::TM20480 => TM20480_PARAMS,
+            LDPCCode::TM24576 => TM24576_PARAMS,
+            LDPCCode::TM32768 => TM32768_PARAMS,
         }
     }
 
     /// Get the code length (number of codeword bits)
-    pub fn n(self) -> usize {
+    pub const fn n(self) -> usize {
         self.params().n
     }
 
     /// Get the code dimension (number of information bits)
-    pub fn k(self) -> usize {
+    pub const fn k(self) -> usize {
         self.params().k
     }
 
     /// Get the number of punctured bits (parity bits not transmitted)
-    pub fn punctured_bits(self) -> usize {
+    pub const fn punctured_bits(self) -> usize {
         self.params().punctured_bits
     }
 
+    /// Get the positions in the codeword which carry the data bits.
+    ///
+    /// All the codes are systematic, so this is always the first k bits, and the data is
+    /// transmitted unchanged in them.
+    pub const fn info_bit_range(self) -> Range<usize> {
+        0 .. self.k()
+    }
+
+    /// Get the positions in the codeword of the transmitted parity bits.
+    ///
+    /// This is always the final n-k bits of the transmitted codeword. For punctured codes the
+    /// remaining parity bits are never transmitted; their positions in the full codeword output
+    /// by the decoders are given by `puncture_pattern`, which immediately follows this range.
+    pub const fn parity_bit_range(self) -> Range<usize> {
+        self.k() .. self.n()
+    }
+
+    /// Get the positions in the full codeword which are punctured (never transmitted).
+    ///
+    /// The full codeword, as output by the decoders, is n + punctured_bits long, and the
+    /// punctured bits are always the final punctured_bits parity bits. The range is empty
+    /// for codes without puncturing.
+    pub const fn puncture_pattern(self) -> Range<usize> {
+        self.n() .. self.codeword_len_depunctured()
+    }
+
+    /// Get the length in bits of the full codeword including punctured bits.
+    ///
+    /// Equal to n + punctured_bits. This is the number of variables in the parity check
+    /// matrix, and the number of bits in the output of every decoder.
+    pub const fn codeword_len_depunctured(self) -> usize {
+        self.n() + self.punctured_bits()
+    }
+
     /// Get the size of the sub-matrices used to define the parity check matrix
-    pub fn submatrix_size(self) -> usize {
+    pub const fn submatrix_size(self) -> usize {
         self.params().submatrix_size
     }
 
     /// Get the size of the sub-matrices used to define the generator matrix
-    pub fn circulant_size(self) -> usize {
+    pub const fn circulant_size(self) -> usize {
         self.params().circulant_size
     }
 
     /// Get the sum of the parity check matrix (total number of parity check edges)
-    pub fn paritycheck_sum(self) -> u32 {
+    ///
+    /// Each decoder iteration takes time proportional to this, so it is the per-code figure
+    /// needed, along with the iteration limit, to bound worst-case decode time. See
+    /// `LDPCCode::DEFAULT_MAX_ITERS`.
+    pub const fn paritycheck_sum(self) -> u32 {
         self.params().paritycheck_sum
     }
 
     /// Get the reference to the compact generator matrix for this code
+    ///
+    /// The k=16384 codes (TM20480, TM24576, and TM32768) do not have generator matrices
+    /// included, and return an empty slice. They can still be decoded, and can be encoded
+    /// using `encode_from_parity`, or with a generator computed by
+    /// `init_generator_from_parity`.
+    ///
+    /// Each generator is only included when the cargo feature named after its code (such as
+    /// `tc128` or `tm2048`) is enabled, as it is by default. Disabling the features for codes
+    /// you never encode saves their generators' flash; those codes then return an empty slice
+    /// here, like the k=16384 codes, but can still be decoded.
+    ///
+    /// Each code has its own generator matrix, even where several codes share the same k, as
+    /// each rate has a different number of parity bits. They are never expanded into RAM, so
+    /// there is no memory to be saved by sharing them between codes.
     pub fn compact_generator(self) -> &'static [u64] {
         match self {
+            #[cfg(feature = "tc128")]
             LDPCCode::TC128  => &compact_generators::TC128_G,
+            #[cfg(feature = "tc256")]
             LDPCCode::TC256  => &compact_generators::TC256_G,
+            #[cfg(feature = "tc512")]
             LDPCCode::TC512  => &compact_generators::TC512_G,
+            #[cfg(feature = "tm1280")]
             LDPCCode::TM1280 => &compact_generators::TM1280_G,
+            #[cfg(feature = "tm1536")]
             LDPCCode::TM1536 => &compact_generators::TM1536_G,
+            #[cfg(feature = "tm2048")]
             LDPCCode::TM2048 => &compact_generators::TM2048_G,
+            #[cfg(feature = "tm5120")]
             LDPCCode::TM5120 => &compact_generators::TM5120_G,
+            #[cfg(feature = "tm6144")]
             LDPCCode::TM6144 => &compact_generators::TM6144_G,
+            #[cfg(feature = "tm8192")]
             LDPCCode::TM8192 => &compact_generators::TM8192_G,
+            // The k=16384 codes, and any code whose feature is disabled
+            _ => &[],
+        }
+    }
+
+    /// Get an iterator over all the 1 entries of the generator matrix for this code.
+    ///
+    /// The generator matrix is never stored expanded; each entry is computed from the compact
+    /// generator on demand, checking every one of the k*n positions, so this is intended for
+    /// analysis and debugging rather than encoding. For codes without an included generator
+    /// (the k=16384 codes) the iterator is empty.
+    pub fn iter_generator(self) -> GeneratorIter {
+        let gc = self.compact_generator();
+        GeneratorIter {
+            gc, k: if gc.is_empty() { 0 } else { self.k() }, r: self.n() - self.k(),
+            b: self.circulant_size(), row: 0, col: 0, identity_done: false,
         }
     }
 
@@ -432,16 +852,231 @@ impl LDPCCode {
     ///
     /// The iterator yields (check, variable) pairs, corresponding to the index of a
     /// row and column in the parity check matrix which contains a 1.
+    ///
+    /// This is a convenient starting point for custom decoders or analysis of the codes, as
+    /// it never requires the parity check matrix to be stored in memory.
     pub fn iter_paritychecks(self) -> ParityIter {
+        self.iter_paritychecks_from(0)
+    }
+
+    /// Get the degree of parity check `check`: the number of variables it involves.
+    ///
+    /// `check` must be less than n + punctured_bits - k.
+    pub fn check_degree(self, check: usize) -> usize {
+        assert!(check < self.n() + self.punctured_bits() - self.k(), "check out of range");
+        self.iter_paritychecks_row(check / self.submatrix_size())
+            .filter(|&(c, _)| c == check).count()
+    }
+
+    /// Get the degree of variable `var`: the number of parity checks it takes part in.
+    ///
+    /// Variables include the punctured bits, so `var` must be less than n + punctured_bits.
+    /// This walks the whole parity check matrix, so takes time proportional to
+    /// `paritycheck_sum()`.
+    pub fn variable_degree(self, var: usize) -> usize {
+        assert!(var < self.n() + self.punctured_bits(), "var out of range");
+        self.iter_paritychecks().filter(|&(_, v)| v == var).count()
+    }
+
+    /// Get the largest degree of any parity check.
+    pub fn max_check_degree(self) -> usize {
+        // Each sub-matrix is a sum of permutation matrices, so every check in a block row
+        // has the same degree.
+        let m = self.submatrix_size();
+        let rows = (self.n() + self.punctured_bits() - self.k()) / m;
+        (0..rows).map(|row| self.check_degree(row * m)).max().unwrap_or(0)
+    }
+
+    /// Get the largest degree of any variable, including the punctured bits.
+    pub fn max_variable_degree(self) -> usize {
+        // As for checks, every variable in a block column has the same degree.
+        let m = self.submatrix_size();
+        let cols = (self.n() + self.punctured_bits()) / m;
+        (0..cols).map(|col| self.variable_degree(col * m)).max().unwrap_or(0)
+    }
+
+    /// Count how many variables and parity checks have each degree.
+    ///
+    /// On return, `var_counts[d]` is the number of variables (including punctured bits) which
+    /// take part in exactly `d` parity checks, and `check_counts[d]` is the number of parity
+    /// checks involving exactly `d` variables. Together these describe the irregular ensemble
+    /// the code belongs to, and are useful for predicting its decoding threshold.
+    ///
+    /// * `var_counts` must be longer than `max_variable_degree()`; 8 is always sufficient
+    /// * `check_counts` must be longer than `max_check_degree()`; 19 is always sufficient
+    ///
+    /// Any further entries are set to zero.
+    pub fn degree_distribution(self, var_counts: &mut [usize], check_counts: &mut [usize]) {
+        // Every variable in a block column, and every check in a block row, has the same
+        // degree, so only the first of each needs to be examined.
+        let m = self.submatrix_size();
+        for x in &mut var_counts[..] { *x = 0 }
+        for x in &mut check_counts[..] { *x = 0 }
+        for col in 0..(self.n() + self.punctured_bits()) / m {
+            let degree = self.variable_degree(col * m);
+            assert!(degree < var_counts.len(), "var_counts too short");
+            var_counts[degree] += m;
+        }
+        for row in 0..(self.n() + self.punctured_bits() - self.k()) / m {
+            let degree = self.check_degree(row * m);
+            assert!(degree < check_counts.len(), "check_counts too short");
+            check_counts[degree] += m;
+        }
+    }
+
+    /// List the information bits from best to worst protected, for unequal error protection.
+    ///
+    /// The codes are irregular, so some information bits take part in more parity checks
+    /// than others and are more reliably recovered by the iterative decoders. This fills
+    /// `order` with the indices of all k information bits (their positions in the data and
+    /// codeword), sorted by decreasing variable degree, and by increasing index among bits of
+    /// the same degree. Placing the most important data, such as the header of an image, in
+    /// the first positions listed makes best use of the code's structure.
+    ///
+    /// The TC codes' information bits all have the same degree, giving `0..k` in order.
+    ///
+    /// `order` must be k long.
+    pub fn protection_ordering(self, order: &mut [u16]) {
+        assert_eq!(order.len(), self.k(), "order.len() != k");
+
+        // As for degree_distribution, every variable in a block column has the same degree,
+        // so whole block columns are listed at a time.
+        let m = self.submatrix_size();
+        let mut idx = 0;
+        for degree in (0..=self.max_variable_degree()).rev() {
+            for col in (0..self.k() / m).filter(|&col| self.variable_degree(col * m) == degree) {
+                for var in col*m .. (col+1)*m {
+                    order[idx] = var as u16;
+                    idx += 1;
+                }
+            }
+        }
+    }
+
+    /// Get the length of [u8] required for `init_paritycheck_packed`.
+    ///
+    /// Equal to (n + punctured_bits - k) rows of (n + punctured_bits)/8 bytes.
+    pub const fn paritycheck_packed_len(self) -> usize {
+        let cols = self.n() + self.punctured_bits();
+        (cols - self.k()) * (cols / 8)
+    }
+
+    /// Write the full parity check matrix H into `h`, one bit per entry.
+    ///
+    /// `h` must be `paritycheck_packed_len()` long. H has one row per parity check and one
+    /// column per variable including the punctured bits, so (n + punctured_bits - k) rows of
+    /// (n + punctured_bits) columns, and is stored row-major with each row taking
+    /// (n + punctured_bits)/8 consecutive bytes. Within a row the bits are packed most
+    /// significant bit first, as for codewords: H[check][var] is bit `7 - (var%8)` of byte
+    /// `check * (n + punctured_bits)/8 + var/8`. Every column count is a multiple of 8, so no
+    /// row contains padding.
+    ///
+    /// For example, numpy can read it with
+    /// `np.unpackbits(np.frombuffer(h, np.uint8)).reshape(rows, cols)`.
+    ///
+    /// This takes an eighth of the memory of storing one byte per entry, but is still large
+    /// for the bigger codes (around 8MB for TM8192 and 125MB for TM32768), so is intended for
+    /// exporting H to analysis tools; on small targets use `iter_paritychecks` instead.
+    pub fn init_paritycheck_packed(self, h: &mut [u8]) {
+        assert_eq!(h.len(), self.paritycheck_packed_len(), "h.len() incorrect");
+        let stride = (self.n() + self.punctured_bits()) / 8;
+        for x in &mut h[..] { *x = 0 }
+        for (check, var) in self.iter_paritychecks() {
+            h[check * stride + var/8] |= 1 << (7 - (var%8));
+        }
+    }
+
+    /// Get a fingerprint identifying this code and the exact constants that define it.
+    ///
+    /// This is a 32-bit FNV-1a hash of the code parameters and every parity check edge. Store
+    /// it alongside anything derived from the constants and persisted outside the crate, such
+    /// as a lookup table from `init_ms_lookup`, and compare it when loading to reject data
+    /// built for a different code or a different version of the constants. Codes never change
+    /// once added, so in practice this catches data loaded for the wrong code.
+    ///
+    /// The generator matrix is not included, so the fingerprint does not depend on which code
+    /// features are enabled. A persisted generator can instead be checked completely with
+    /// `verify_generator`.
+    ///
+    /// Computing it walks every parity check edge, so it is best done once at startup.
+    pub fn fingerprint(self) -> u32 {
+        fn hash(mut state: u32, bytes: &[u8]) -> u32 {
+            for &b in bytes {
+                state ^= u32::from(b);
+                state = state.wrapping_mul(0x0100_0193);
+            }
+            state
+        }
+
+        let mut state = 0x811C_9DC5;
+        for x in &[self.n(), self.k(), self.punctured_bits(), self.submatrix_size()] {
+            state = hash(state, &(*x as u32).to_le_bytes());
+        }
+        for (check, var) in self.iter_paritychecks() {
+            state = hash(state, &(check as u32).to_le_bytes());
+            state = hash(state, &(var as u32).to_le_bytes());
+        }
+        state
+    }
+
+    /// Get the minimum distance of this code, if it is known.
+    ///
+    /// The minimum distance is the smallest number of bits in which any two codewords differ.
+    /// For the TC codes it is known exactly: 14, 26, and 40 for TC128, TC256, and TC512
+    /// respectively, as given in CCSDS 231.1-O-1 (Short Block Length LDPC Codes for TC
+    /// Synchronization and Channel Coding) alongside the codes' definitions.
+    ///
+    /// For the TM codes CCSDS 131.0-B-2 does not give the minimum distance, and the codes are
+    /// too long for it to be found by exhaustive search. No value or bound is included here, so
+    /// it is treated as unknown and `None` is returned.
+    ///
+    /// Note that the iterative decoders in this crate are not maximum likelihood decoders, so
+    /// they may fail to correct some error patterns smaller than this bound, and will often
+    /// correct many larger ones.
+    pub const fn min_distance(self) -> Option<usize> {
+        match self {
+            LDPCCode::TC128 => Some(14),
+            LDPCCode::TC256 => Some(26),
+            LDPCCode::TC512 => Some(40),
+            _ => None,
+        }
+    }
+
+    /// Get the number of bit errors this code is guaranteed to be able to correct, if known.
+    ///
+    /// Equal to (min_distance - 1)/2. See `min_distance` for caveats.
+    pub const fn correctable_errors(self) -> Option<usize> {
+        match self.min_distance() {
+            Some(d) => Some((d - 1) / 2),
+            None => None,
+        }
+    }
+
+    /// Get an iterator over the parity check matrix edges for one block row of checks.
+    ///
+    /// Yields exactly the edges `iter_paritychecks()` yields for checks in
+    /// `row*submatrix_size()..(row+1)*submatrix_size()`, in the same order, without
+    /// computing any of the edges for earlier rows.
+    pub(crate) fn iter_paritychecks_row(self, row: usize)
+        -> impl Iterator<Item=(usize, usize)>
+    {
+        let end = (row + 1) * self.submatrix_size();
+        self.iter_paritychecks_from(row).take_while(move |&(check, _)| check < end)
+    }
+
+    /// Set up a ParityIter starting from block row `row` of the prototype
+    fn iter_paritychecks_from(self, row: usize) -> ParityIter {
         match self {
-            LDPCCode::TC128  | LDPCCode::TC256  | LDPCCode::TC512 => self.iter_paritychecks_tc(),
+            LDPCCode::TC128  | LDPCCode::TC256  | LDPCCode::TC512 => self.iter_paritychecks_tc(row),
             LDPCCode::TM1280 | LDPCCode::TM1536 | LDPCCode::TM2048 |
-            LDPCCode::TM5120 | LDPCCode::TM6144 | LDPCCode::TM8192 => self.iter_paritychecks_tm(),
+            LDPCCode::TM5120 | LDPCCode::TM6144 | LDPCCode::TM8192 |
+            LDPCCode::TM20480 | LDPCCode::TM24576 | LDPCCode::TM32768 =>
+                self.iter_paritychecks_tm(row),
         }
     }
 
     /// Set up a ParityIter for a TC code
-    fn iter_paritychecks_tc(self) -> ParityIter {
+    fn iter_paritychecks_tc(self, row: usize) -> ParityIter {
         let prototype = match self {
             LDPCCode::TC128 => &compact_parity_checks::TC128_H,
             LDPCCode::TC256 => &compact_parity_checks::TC256_H,
@@ -450,7 +1085,7 @@ impl LDPCCode {
             _               => unreachable!(),
         };
 
-        let subm = prototype[0][0][0];
+        let subm = prototype[0][row][0];
 
         let m = self.submatrix_size();
 
@@ -459,12 +1094,12 @@ impl LDPCCode {
 
         ParityIter {
             phi, prototype, m, logmd4: (m/4).trailing_zeros() as usize, modm: m-1, modmd4: (m/4)-1,
-            rowidx: 0, colidx: 0, sub_mat_idx: 0, sub_mat: subm, sub_mat_val: (subm & 0x3F) as usize, check: 0,
+            rowidx: row, colidx: 0, sub_mat_idx: 0, sub_mat: subm, sub_mat_val: (subm & 0x3F) as usize, check: 0,
         }
     }
 
     /// Set up a ParityIter for a TM code
-    fn iter_paritychecks_tm(self) -> ParityIter {
+    fn iter_paritychecks_tm(self, row: usize) -> ParityIter {
         let m = self.submatrix_size();
         let phi = match m {
             128  => &self::compact_parity_checks::PHI_J_K_M128,
@@ -485,20 +1120,28 @@ impl LDPCCode {
             _  => unreachable!(),
         };
 
-        let subm = prototype[0][0][0];
+        let subm = prototype[0][row][0];
 
         ParityIter {
             phi, prototype, m, logmd4: (m/4).trailing_zeros() as usize, modm: m-1, modmd4: (m/4)-1,
-            rowidx: 0, colidx: 0, sub_mat_idx: 0, check: 0, sub_mat: subm, sub_mat_val: (subm & 0x3F) as usize,
+            rowidx: row, colidx: 0, sub_mat_idx: 0, check: 0, sub_mat: subm, sub_mat_val: (subm & 0x3F) as usize,
         }
     }
 }
 
+/// The codes from `codes` whose generator matrix is included in this build.
+///
+/// Tests which encode use this so that they still pass with only some code features enabled.
+#[cfg(test)]
+pub(crate) fn encodable(codes: &[LDPCCode]) -> std::vec::Vec<LDPCCode> {
+    codes.iter().cloned().filter(|code| !code.compact_generator().is_empty()).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use std::prelude::v1::*;
 
-    use super::{LDPCCode};
+    use super::{encodable, CodeFamily, LDPCCode};
 
     const CODES: [LDPCCode;  9] = [LDPCCode::TC128,   LDPCCode::TC256,   LDPCCode::TC512,
                                    LDPCCode::TM1280,  LDPCCode::TM1536,  LDPCCode::TM2048,
@@ -533,4 +1176,266 @@ mod tests {
             assert_eq!(crc, crc_results[idx]);
         }
     }
+
+    #[test]
+    fn test_iter_parity_k16384() {
+        for code in &[LDPCCode::TM20480, LDPCCode::TM24576, LDPCCode::TM32768] {
+            let mut count = 0;
+            for (check, var) in code.iter_paritychecks() {
+                count += 1;
+                assert!(check < code.n() + code.punctured_bits() - code.k());
+                assert!(var < code.n() + code.punctured_bits());
+            }
+            assert_eq!(count, code.paritycheck_sum() as usize);
+        }
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn check<T: Send + Sync>() {}
+        check::<LDPCCode>();
+        check::<crate::decoder::MsDecoder<f32>>();
+        check::<crate::encoder::Encoder>();
+    }
+
+    #[test]
+    fn test_all_codes() {
+        let all = LDPCCode::all();
+        assert_eq!(all.len(), 12);
+        for (idx, code) in all.iter().enumerate() {
+            assert_eq!(*code as usize, idx);
+        }
+    }
+
+    #[test]
+    fn test_code_info() {
+        assert_eq!(LDPCCode::PARAMS.len(), LDPCCode::all().len());
+        for ((code, info), all) in LDPCCode::PARAMS.iter().zip(LDPCCode::all()) {
+            assert_eq!(code, all);
+            assert_eq!((info.n, info.k, info.punctured_bits),
+                       (code.n(), code.k(), code.punctured_bits()));
+            assert_eq!(info.rate.0 * code.n(), info.rate.1 * code.k());
+            let family = if code.as_str().starts_with("TC") {
+                CodeFamily::Telecommand
+            } else {
+                CodeFamily::Telemetry
+            };
+            assert_eq!(info.family, family);
+        }
+        assert_eq!(LDPCCode::TC128.info().rate, (1, 2));
+        assert_eq!(LDPCCode::TM1536.info().rate, (2, 3));
+        assert_eq!(LDPCCode::TM20480.info().rate, (4, 5));
+    }
+
+    #[test]
+    fn test_min_distance() {
+        assert_eq!(LDPCCode::TC128.correctable_errors(), Some(6));
+        assert_eq!(LDPCCode::TC256.correctable_errors(), Some(12));
+        assert_eq!(LDPCCode::TC512.correctable_errors(), Some(19));
+        assert_eq!(LDPCCode::TM8192.min_distance(), None);
+        assert_eq!(LDPCCode::TM8192.correctable_errors(), None);
+    }
+
+    #[test]
+    fn test_const_params() {
+        const CODE: LDPCCode = LDPCCode::TM1280;
+        const N: usize = CODE.n();
+        static WORKING: [i8; CODE.decode_ms_working_len()] = [0; CODE.decode_ms_working_len()];
+        let output = [0u8; CODE.output_len()];
+        assert_eq!(N, 1280);
+        assert_eq!(WORKING.len(), CODE.decode_ms_working_len());
+        assert_eq!(output.len(), CODE.codeword_len_depunctured() / 8);
+    }
+
+    #[test]
+    fn test_code_names() {
+        use super::ParseCodeError;
+        for code in LDPCCode::all() {
+            assert_eq!(code.as_str(), format!("{:?}", code));
+            assert_eq!(code.to_string().parse::<LDPCCode>(), Ok(*code));
+        }
+        assert_eq!("tm2048".parse::<LDPCCode>(), Ok(LDPCCode::TM2048));
+        assert_eq!("TM2049".parse::<LDPCCode>(), Err(ParseCodeError));
+        assert_eq!("".parse::<LDPCCode>(), Err(ParseCodeError));
+    }
+
+    #[test]
+    fn test_bit_ranges() {
+        for code in LDPCCode::all() {
+            assert_eq!(code.info_bit_range().len(), code.k());
+            assert_eq!(code.parity_bit_range().start, code.info_bit_range().end);
+            assert_eq!(code.parity_bit_range().end, code.n());
+            assert_eq!(code.puncture_pattern().start, code.parity_bit_range().end);
+        }
+        assert_eq!(LDPCCode::TM1280.parity_bit_range(), 1024..1280);
+        assert_eq!(LDPCCode::TM1280.puncture_pattern(), 1280..1408);
+    }
+
+    #[test]
+    fn test_typed_codes() {
+        use super::*;
+
+        fn check<C: Code>() {
+            let code = C::CODE;
+            assert_eq!(C::N, code.n());
+            assert_eq!(C::K, code.k());
+            assert_eq!(C::PUNCTURED_BITS, code.punctured_bits());
+            assert_eq!(C::COMPACT_GENERATOR_LEN, code.compact_generator_len());
+            assert_eq!(C::DECODE_BF_WORKING_LEN, code.decode_bf_working_len());
+            assert_eq!(C::DECODE_MS_WORKING_LEN, code.decode_ms_working_len());
+            assert_eq!(C::DECODE_MS_WORKING_U8_LEN, code.decode_ms_working_u8_len());
+            assert_eq!(C::OUTPUT_LEN, code.output_len());
+            assert_eq!(C::SYNDROME_LEN, code.syndrome_len());
+            assert_eq!(code.codeword_len_depunctured(), code.output_len() * 8);
+        }
+
+        check::<Tc128>();
+        check::<Tc256>();
+        check::<Tc512>();
+        check::<Tm1280>();
+        check::<Tm1536>();
+        check::<Tm2048>();
+        check::<Tm5120>();
+        check::<Tm6144>();
+        check::<Tm8192>();
+        check::<Tm20480>();
+        check::<Tm24576>();
+        check::<Tm32768>();
+    }
+
+    #[test]
+    fn test_iter_parity_row() {
+        for code in CODES.iter().chain(&[LDPCCode::TM20480]) {
+            let rows = (code.n() + code.punctured_bits() - code.k()) / code.submatrix_size();
+            let mut all = code.iter_paritychecks();
+            for row in 0..rows {
+                for edge in code.iter_paritychecks_row(row) {
+                    assert_eq!(Some(edge), all.next());
+                }
+            }
+            assert_eq!(all.next(), None);
+        }
+    }
+
+    #[test]
+    fn test_degrees() {
+        for code in &[LDPCCode::TC128, LDPCCode::TM1280, LDPCCode::TM2048] {
+            let mut check_degrees = vec![0; code.n() + code.punctured_bits() - code.k()];
+            let mut var_degrees = vec![0; code.n() + code.punctured_bits()];
+            for (check, var) in code.iter_paritychecks() {
+                check_degrees[check] += 1;
+                var_degrees[var] += 1;
+            }
+            for (check, degree) in check_degrees.iter().enumerate() {
+                assert_eq!(code.check_degree(check), *degree);
+            }
+            for var in (0..var_degrees.len()).step_by(61) {
+                assert_eq!(code.variable_degree(var), var_degrees[var]);
+            }
+            assert_eq!(code.max_check_degree(), *check_degrees.iter().max().unwrap());
+            assert_eq!(code.max_variable_degree(), *var_degrees.iter().max().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_protection_ordering() {
+        for code in LDPCCode::all() {
+            let mut order = vec![0u16; code.k()];
+            code.protection_ordering(&mut order);
+
+            // Every information bit is listed once, with degrees never increasing
+            let mut sorted = order.clone();
+            sorted.sort();
+            assert!(sorted.iter().enumerate().all(|(i, &x)| x as usize == i));
+            let m = code.submatrix_size();
+            let degrees: Vec<usize> = order.iter().step_by(m)
+                                           .map(|&x| code.variable_degree(x as usize)).collect();
+            assert!(degrees.windows(2).all(|w| w[0] >= w[1]));
+        }
+
+        let mut order = vec![0u16; LDPCCode::TC128.k()];
+        LDPCCode::TC128.protection_ordering(&mut order);
+        assert!(order.iter().enumerate().all(|(i, &x)| x as usize == i));
+    }
+
+    #[test]
+    fn test_degree_distribution() {
+        for code in LDPCCode::all() {
+            let mut var_counts = [0usize; 8];
+            let mut check_counts = [0usize; 19];
+            code.degree_distribution(&mut var_counts, &mut check_counts);
+
+            // Every variable and check is counted, and every edge is accounted for
+            let vars = code.n() + code.punctured_bits();
+            assert_eq!(var_counts.iter().sum::<usize>(), vars);
+            assert_eq!(check_counts.iter().sum::<usize>(), vars - code.k());
+            let edges = code.paritycheck_sum() as usize;
+            assert_eq!(var_counts.iter().enumerate().map(|(d, c)| d * c).sum::<usize>(), edges);
+            assert_eq!(check_counts.iter().enumerate().map(|(d, c)| d * c).sum::<usize>(), edges);
+        }
+
+        let mut var_counts = [0usize; 8];
+        let mut check_counts = [0usize; 19];
+        LDPCCode::TC128.degree_distribution(&mut var_counts, &mut check_counts);
+        assert_eq!(var_counts, [0, 0, 0, 64, 0, 64, 0, 0]);
+        assert_eq!(check_counts[8], 64);
+        LDPCCode::TM2048.degree_distribution(&mut var_counts, &mut check_counts);
+        assert_eq!(var_counts, [0, 512, 512, 1024, 0, 0, 512, 0]);
+        assert_eq!((check_counts[3], check_counts[6]), (512, 1024));
+    }
+
+    #[test]
+    fn test_paritycheck_packed() {
+        for code in &[LDPCCode::TC128, LDPCCode::TM1280, LDPCCode::TM2048] {
+            let mut h = vec![0xFFu8; code.paritycheck_packed_len()];
+            code.init_paritycheck_packed(&mut h);
+
+            // Every edge is set, and nothing else
+            let cols = code.n() + code.punctured_bits();
+            for (check, var) in code.iter_paritychecks() {
+                assert_eq!((h[check * cols/8 + var/8] >> (7 - (var%8))) & 1, 1);
+            }
+            let ones: u32 = h.iter().map(|x| x.count_ones()).sum();
+            assert_eq!(ones, code.paritycheck_sum());
+
+            // Each row holds one check
+            for (check, row) in h.chunks(cols/8).enumerate().step_by(37) {
+                let degree: u32 = row.iter().map(|x| x.count_ones()).sum();
+                assert_eq!(degree as usize, code.check_degree(check));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let fingerprints: Vec<u32> = LDPCCode::all().iter().map(|c| c.fingerprint()).collect();
+        for (idx, a) in fingerprints.iter().enumerate() {
+            assert!(fingerprints[idx+1..].iter().all(|b| a != b));
+        }
+        // The constants never change, so neither may their fingerprints
+        assert_eq!(LDPCCode::TC128.fingerprint(), 0x9E4E_13B5);
+    }
+
+    #[test]
+    fn test_iter_generator() {
+        let codes = [LDPCCode::TC128, LDPCCode::TC512, LDPCCode::TM1280, LDPCCode::TM2048];
+        for code in &encodable(&codes) {
+            let mut gen = code.iter_generator().peekable();
+            for row in 0..code.k() {
+                // Encoding a single data bit gives that row of the generator
+                let mut data = vec![0u8; code.k()/8];
+                data[row/8] = 1 << (7 - (row%8));
+                let mut codeword = vec![0u8; code.n()/8];
+                code.copy_encode(&data, &mut codeword);
+                for col in 0..code.n() {
+                    if (codeword[col/8] >> (7 - (col%8))) & 1 == 1 {
+                        assert_eq!(gen.next(), Some((row, col)));
+                    }
+                }
+                assert_ne!(gen.peek().map(|&(r, _)| r), Some(row));
+            }
+            assert_eq!(gen.next(), None);
+        }
+        assert_eq!(LDPCCode::TM20480.iter_generator().next(), None);
+    }
 }
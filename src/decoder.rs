@@ -6,16 +6,20 @@
 //! Please refer to the `decode_ms` and `decode_bf` methods on
 //! [`LDPCCode`](../codes/enum.LDPCCode.html) for more details.
 
-use core::i8;
-use core::i16;
-use core::i32;
 use core::f32;
 use core::f64;
+use core::fmt;
+use core::mem;
+use core::slice;
 
 use core::ops::{Add,AddAssign,Neg,Sub};
 
+use crate::bits::{pack_bools, BitOrder};
 use crate::codes::LDPCCode;
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 // Ugh gross yuck.
 //
 // No `f32::abs()` available with `no_std`, and it's not worth bringing in some
@@ -36,7 +40,9 @@ fn fabsf(f: f32) -> f32 {
 
 /// Trait for types that the min-sum decoder can operate with.
 ///
-/// Implemented for `i8`, `i16`, `i32`, `f32`, and `f64`.
+/// Implemented for `i8`, `i16`, `i32`, `f32`, and `f64`. It may also be implemented for other
+/// types, such as a custom fixed-point representation; the working area is always sized in
+/// units of `T`.
 pub trait DecodeFrom:
     Sized + Clone + Copy + PartialEq + PartialOrd
     + Add + AddAssign + Neg<Output=Self> + Sub<Output=Self>
@@ -50,7 +56,16 @@ pub trait DecodeFrom:
     /// Absolute value of self
     fn abs(&self)       -> Self;
     /// Saturating add
+    ///
+    /// For integer types this saturates symmetrically to `-maxval()..=maxval()`, so that
+    /// the result can always be safely negated or have its absolute value taken.
     fn saturating_add(&self, other: Self) -> Self;
+    /// Saturating subtract, saturating symmetrically like `saturating_add`
+    fn saturating_sub(&self, other: Self) -> Self;
+    /// Multiply by `factor`, rounding towards zero for integer types
+    fn scale(&self, factor: f32) -> Self;
+    /// Whether self is finite, which is always true for integer types
+    #[inline] fn is_finite(&self) -> bool { true }
 }
 
 impl DecodeFrom for i8 {
@@ -58,21 +73,39 @@ impl DecodeFrom for i8 {
     #[inline] fn zero()     -> i8 { 0 }
     #[inline] fn maxval()   -> i8 { i8::MAX }
     #[inline] fn abs(&self) -> i8 { i8::abs(*self) }
-    #[inline] fn saturating_add(&self, other: Self) -> Self { i8::saturating_add(*self, other) }
+    #[inline] fn saturating_add(&self, other: Self) -> Self {
+        i8::max(i8::saturating_add(*self, other), -i8::MAX)
+    }
+    #[inline] fn saturating_sub(&self, other: Self) -> Self {
+        i8::max(i8::saturating_sub(*self, other), -i8::MAX)
+    }
+    #[inline] fn scale(&self, factor: f32) -> Self { (*self as f32 * factor) as i8 }
 }
 impl DecodeFrom for i16 {
     #[inline] fn one()      -> i16 { 1 }
     #[inline] fn zero()     -> i16 { 0 }
     #[inline] fn maxval()   -> i16 { i16::MAX }
     #[inline] fn abs(&self) -> i16 { i16::abs(*self) }
-    #[inline] fn saturating_add(&self, other: Self) -> Self { i16::saturating_add(*self, other) }
+    #[inline] fn saturating_add(&self, other: Self) -> Self {
+        i16::max(i16::saturating_add(*self, other), -i16::MAX)
+    }
+    #[inline] fn saturating_sub(&self, other: Self) -> Self {
+        i16::max(i16::saturating_sub(*self, other), -i16::MAX)
+    }
+    #[inline] fn scale(&self, factor: f32) -> Self { (*self as f32 * factor) as i16 }
 }
 impl DecodeFrom for i32 {
     #[inline] fn one()      -> i32 { 1 }
     #[inline] fn zero()     -> i32 { 0 }
     #[inline] fn maxval()   -> i32 { i32::MAX }
     #[inline] fn abs(&self) -> i32 { i32::abs(*self) }
-    #[inline] fn saturating_add(&self, other: Self) -> Self { i32::saturating_add(*self, other) }
+    #[inline] fn saturating_add(&self, other: Self) -> Self {
+        i32::max(i32::saturating_add(*self, other), -i32::MAX)
+    }
+    #[inline] fn saturating_sub(&self, other: Self) -> Self {
+        i32::max(i32::saturating_sub(*self, other), -i32::MAX)
+    }
+    #[inline] fn scale(&self, factor: f32) -> Self { (*self as f32 * factor) as i32 }
 }
 impl DecodeFrom for f32 {
     #[inline] fn one()      -> f32 { 1.0 }
@@ -80,6 +113,9 @@ impl DecodeFrom for f32 {
     #[inline] fn maxval()   -> f32 { f32::MAX }
     #[inline] fn abs(&self) -> f32 { fabsf(*self) }
     #[inline] fn saturating_add(&self, other: Self) -> Self { *self + other }
+    #[inline] fn saturating_sub(&self, other: Self) -> Self { *self - other }
+    #[inline] fn scale(&self, factor: f32) -> Self { *self * factor }
+    #[inline] fn is_finite(&self) -> bool { f32::is_finite(*self) }
 }
 impl DecodeFrom for f64 {
     #[inline] fn one()      -> f64 { 1.0 }
@@ -87,42 +123,899 @@ impl DecodeFrom for f64 {
     #[inline] fn maxval()   -> f64 { f64::MAX }
     #[inline] fn abs(&self) -> f64 { fabs(*self) }
     #[inline] fn saturating_add(&self, other: Self) -> Self { *self + other }
+    #[inline] fn saturating_sub(&self, other: Self) -> Self { *self - other }
+    #[inline] fn scale(&self, factor: f32) -> Self { *self * f64::from(factor) }
+    #[inline] fn is_finite(&self) -> bool { f64::is_finite(*self) }
+}
+
+/// Trait for floating point types that the sum-product decoder can operate with.
+///
+/// Implemented for `f32` and `f64`. Only available with the `std` feature.
+#[cfg(feature = "std")]
+pub trait DecodeFromFloat: DecodeFrom {
+    /// Convert self to f64
+    fn to_f64(self) -> f64;
+    /// Convert an f64 to T
+    fn from_f64(x: f64) -> Self;
+}
+
+#[cfg(feature = "std")]
+impl DecodeFromFloat for f32 {
+    #[inline] fn to_f64(self) -> f64 { f64::from(self) }
+    #[inline] fn from_f64(x: f64) -> f32 { x as f32 }
+}
+#[cfg(feature = "std")]
+impl DecodeFromFloat for f64 {
+    #[inline] fn to_f64(self) -> f64 { self }
+    #[inline] fn from_f64(x: f64) -> f64 { x }
+}
+
+/// The sum-product check update function, phi(x) = -ln(tanh(x/2)) = ln((e^x + 1)/(e^x - 1)).
+///
+/// phi is its own inverse on x > 0. The input is clamped to keep the output finite.
+#[cfg(feature = "std")]
+#[inline]
+fn phi(x: f64) -> f64 {
+    let x = x.clamp(1e-12, 40.0);
+    let ex = x.exp();
+    ((ex + 1.0) / (ex - 1.0)).ln()
+}
+
+/// Correction applied to the magnitude of each check-to-variable message in the min-sum decoder.
+#[derive(Copy,Clone,Debug)]
+enum MsCorrection<T> {
+    /// Plain min-sum, messages are unmodified.
+    None,
+    /// Normalised min-sum, messages are multiplied by the given factor.
+    Normalized(f32),
+    /// Offset min-sum, messages have the given offset subtracted, saturating at zero.
+    Offset(T),
+    /// Clamped min-sum, messages and marginals are limited to the given magnitude.
+    Clamp(T),
+    /// Damped min-sum, messages are blended with the previous message by the given factor.
+    Damped(f32),
+}
+
+impl<T: DecodeFrom> MsCorrection<T> {
+    /// Apply this correction to the (non-negative) message magnitude `x`.
+    #[inline]
+    fn apply(self, x: T) -> T {
+        match self {
+            MsCorrection::None => x,
+            MsCorrection::Normalized(alpha) => x.scale(alpha),
+            MsCorrection::Offset(beta) => if x > beta { x - beta } else { T::zero() },
+            MsCorrection::Clamp(max) => if x > max { max } else { x },
+            MsCorrection::Damped(_) => x,
+        }
+    }
+}
+
+/// Bound `x` to `-T::maxval()..=T::maxval()`, which integer types already are, so that float
+/// sums of very large LLRs cannot overflow to infinity.
+///
+/// Shared by the flooding and layered min-sum decoders.
+#[inline]
+fn bound<T: DecodeFrom>(x: T) -> T {
+    if x > T::maxval() { T::maxval() } else if x < -T::maxval() { -T::maxval() } else { x }
+}
+
+/// Fold the magnitude `abs` of one incoming message into the two smallest magnitudes seen so
+/// far at a check, `min1` and then `min2`, which both start from `T::maxval()`.
+///
+/// Shared by `check_node_update` and the min-sum decoders.
+#[inline]
+fn accumulate_mins<T: DecodeFrom>(abs: T, min1: &mut T, min2: &mut T) {
+    if abs < *min1 {
+        *min2 = *min1;
+        *min1 = abs;
+    } else if abs < *min2 {
+        *min2 = abs;
+    }
+}
+
+/// The magnitude of the reply to an incoming message of magnitude `abs`, the smallest of the
+/// *other* messages at its check: `min2` if this message was the smallest, otherwise `min1`.
+#[inline]
+fn reply_magnitude<T: DecodeFrom>(abs: T, min1: T, min2: T) -> T {
+    if abs == min1 { min2 } else { min1 }
+}
+
+/// Min-sum check node update, for building custom decoders.
+///
+/// Given the `incoming` variable-to-check messages of one parity check, sets each entry of
+/// `out` to the check-to-variable message back to that variable: the smallest magnitude of
+/// all the *other* incoming messages, multiplied by `alpha`, with the sign that makes the
+/// product of the signs of all the other messages and itself positive. A zero message counts
+/// as positive. This is the same computation, tracking the two smallest magnitudes and the
+/// product of the signs, that `decode_ms` and `decode_ms_layered` make for every check, so
+/// it may be used to assemble other schedules, such as informed dynamic scheduling, from the
+/// same core.
+///
+/// `alpha` is applied as in `decode_ms_normalized`, rounding towards zero for integer `T`;
+/// an `alpha` of 1.0 gives plain min-sum. With a single incoming message, its reply has no
+/// other messages to draw on and is `T::maxval()`.
+///
+/// `incoming` and `out` must be the same length, and `alpha` must be in (0, 1].
+pub fn check_node_update<T: DecodeFrom>(incoming: &[T], out: &mut [T], alpha: f32) {
+    assert_eq!(incoming.len(), out.len(), "out.len() != incoming.len()");
+    assert!(alpha > 0.0 && alpha <= 1.0, "alpha must be in (0, 1]");
+
+    let correction = if alpha == 1.0 {
+        MsCorrection::None
+    } else {
+        MsCorrection::Normalized(alpha)
+    };
+
+    // Accumulate two minimums and the product of the signs
+    let mut min1 = T::maxval();
+    let mut min2 = T::maxval();
+    let mut negative = false;
+    for x in incoming {
+        accumulate_mins(x.abs(), &mut min1, &mut min2);
+        if *x < T::zero() {
+            negative = !negative;
+        }
+    }
+
+    // Each reply uses the other minimum if this message was the minimum, and removes its own
+    // sign from the product
+    for (x, o) in incoming.iter().zip(out.iter_mut()) {
+        let mut msg = correction.apply(reply_magnitude(x.abs(), min1, min2));
+        if negative != (*x < T::zero()) {
+            msg = -msg;
+        }
+        *o = msg;
+    }
+}
+
+/// Which bits `decode_bf_with_strategy` flips when several are tied for the most failed checks.
+#[derive(Copy,Clone,Debug,Default,Eq,PartialEq,Hash)]
+pub enum BfStrategy {
+    /// Flip every tied bit. This is the default, and what `decode_bf` does.
+    #[default]
+    FlipAll,
+
+    /// Flip only the tied bit with the lowest index.
+    FlipLowest,
+
+    /// Flip only the tied bit with the highest index.
+    FlipHighest,
+}
+
+/// The decoders available, for looking up their buffer requirements with
+/// `LDPCCode::decoder_buffers`.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub enum DecoderKind {
+    /// `decode_bf` and its variants.
+    BitFlipping,
+
+    /// `decode_gallager_b`.
+    GallagerB,
+
+    /// `decode_wbf`.
+    WeightedBitFlipping,
+
+    /// `decode_gdbf`.
+    GradientDescentBitFlipping,
+
+    /// `decode_ms` and its variants.
+    MinSum,
+
+    /// `decode_sp`, which is only available with the `std` feature.
+    SumProduct,
+}
+
+/// Coarse confidence in a decode, as returned by `LDPCCode::decode_ms_confidence`.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub enum Confidence {
+    /// The decoder converged quickly to a codeword with every bit reliably decided.
+    High,
+
+    /// The decoder converged, but only just: it took many iterations or some bits are barely
+    /// decided. The output is a valid codeword but is more likely than usual to be wrong.
+    Marginal,
+
+    /// The decoder did not converge.
+    Failed,
+}
+
+/// Thresholds used by `LDPCCode::decode_ms_confidence` to classify a converged decode.
+#[derive(Copy,Clone,Debug,PartialEq)]
+pub struct ConfidenceThresholds<T: DecodeFrom> {
+    /// Smallest final a-posteriori LLR magnitude allowed for every codeword bit.
+    pub min_llr: T,
+
+    /// Largest number of iterations allowed.
+    pub max_iters: usize,
+}
+
+/// Received information given to `LDPCCode::decode`.
+#[derive(Copy,Clone,Debug)]
+pub enum DecodeInput<'a> {
+    /// Hard information, n/8 bytes, as for `decode_bf`.
+    Hard(&'a [u8]),
+
+    /// Soft information, n LLRs, as for `decode_ms`.
+    Soft(&'a [f32]),
+}
+
+/// Lengths of the buffers required by a decoder, as returned by `LDPCCode::decoder_buffers`.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub struct DecoderBuffers {
+    /// Length of [u8] of the decoder's output, always `output_len()`.
+    pub output: usize,
+
+    /// Length of [u8] of the decoder's byte working area: `working` for the bit flipping and
+    /// Gallager-B decoders, or `working_u8` for the min-sum and sum-product decoders.
+    pub working_u8: usize,
+
+    /// Length of the decoder's working area of soft values, or 0 if it has none. This is in
+    /// units of the decoder's soft type: `T` for the min-sum and sum-product decoders, `i16`
+    /// for `decode_wbf`, and `f32` for `decode_gdbf`.
+    pub working_soft: usize,
+}
+
+/// Every buffer a decoder reads or writes, as returned by `LDPCCode::buffer_plan`.
+///
+/// Allocate each buffer with the listed length, using 0 to mean the decoder does not take
+/// that buffer. There are no parity check structures to allocate, as every decoder generates
+/// the parity check matrix on the fly from compact constants.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub struct BufferPlan {
+    /// Length of [u8] of the received hard information `input`, n/8 for the bit flipping,
+    /// Gallager-B and weighted bit flipping decoders.
+    pub input_u8: usize,
+
+    /// Length of the received soft information, n for every decoder taking it: the `llrs`
+    /// for the min-sum, sum-product and gradient descent decoders, or the `reliability` for
+    /// `decode_wbf`.
+    pub input_soft: usize,
+
+    /// The output and working areas, as returned by `LDPCCode::decoder_buffers`.
+    pub buffers: DecoderBuffers,
+}
+
+/// Statistics describing the outcome of a decode.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub struct DecodeStats {
+    /// Number of iterations run, with the same meaning as the iteration count returned
+    /// by the corresponding decoder method.
+    pub iterations: usize,
+
+    /// Whether the decoder stopped because every parity check was satisfied.
+    pub converged: bool,
+}
+
+impl From<(bool, usize)> for DecodeStats {
+    fn from((converged, iterations): (bool, usize)) -> DecodeStats {
+        DecodeStats { iterations, converged }
+    }
+}
+
+/// Errors which can be returned by the checked decoding interfaces.
+#[non_exhaustive]
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub enum DecodeError {
+    /// A buffer passed in was not the length required by the code.
+    BadBufferLen {
+        /// Name of the offending buffer, such as `"working"`.
+        which: &'static str,
+        /// Length required by the code.
+        expected: usize,
+        /// Length actually provided.
+        got: usize,
+    },
+
+    /// A byte buffer passed in was not suitably aligned for the type it is used as.
+    BadAlignment {
+        /// Name of the offending buffer, such as `"working"`.
+        which: &'static str,
+        /// Alignment required, in bytes.
+        required: usize,
+    },
+
+    /// An input LLR was NaN or infinite.
+    NonFiniteInput {
+        /// Index of the first non-finite LLR.
+        index: usize,
+    },
+
+    /// The decoder cannot be run by `LDPCCode::decode` with the input given.
+    Unsupported {
+        /// The decoder requested.
+        kind: DecoderKind,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::BadBufferLen { which, expected, got } =>
+                write!(f, "{} has length {} but must be length {}", which, got, expected),
+            DecodeError::BadAlignment { which, required } =>
+                write!(f, "{} must be aligned to {} bytes", which, required),
+            DecodeError::NonFiniteInput { index } =>
+                write!(f, "input LLR {} is not finite", index),
+            DecodeError::Unsupported { kind } =>
+                write!(f, "{:?} decoder cannot be used with this input", kind),
+        }
+    }
+}
+
+/// Return `DecodeError::BadBufferLen` if `got` is not equal to `expected`.
+fn check_len(which: &'static str, expected: usize, got: usize) -> Result<(), DecodeError> {
+    if expected == got {
+        Ok(())
+    } else {
+        Err(DecodeError::BadBufferLen { which, expected, got })
+    }
+}
+
+/// Return `DecodeError::NonFiniteInput` if any of `llrs` is NaN or infinite.
+fn check_finite<T: DecodeFrom>(llrs: &[T]) -> Result<(), DecodeError> {
+    match llrs.iter().position(|llr| !llr.is_finite()) {
+        Some(index) => Err(DecodeError::NonFiniteInput { index }),
+        None => Ok(()),
+    }
+}
+
+/// Reusable context for the min-sum decoder.
+///
+/// Holds a code and the two working areas required by `decode_ms`, validating their lengths
+/// once at construction so they cannot later be mismatched. No memory is allocated: the
+/// working areas are borrowed from the caller for the lifetime of the decoder.
+///
+/// ```
+/// # use labrador_ldpc::LDPCCode;
+/// # use labrador_ldpc::decoder::MsDecoder;
+/// let code = LDPCCode::TC128;
+/// let mut working = vec![0i8; code.decode_ms_working_len()];
+/// let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+/// let mut decoder = MsDecoder::new(code, &mut working, &mut working_u8).unwrap();
+///
+/// let llrs = vec![1i8; code.n()];
+/// let mut output = vec![0u8; code.output_len()];
+/// let stats = decoder.decode(&llrs, &mut output, LDPCCode::DEFAULT_MAX_ITERS).unwrap();
+/// assert!(stats.converged);
+/// ```
+pub struct MsDecoder<'a, T: DecodeFrom> {
+    code: LDPCCode,
+    working: &'a mut [T],
+    working_u8: &'a mut [u8],
+}
+
+impl<'a, T: DecodeFrom> MsDecoder<'a, T> {
+    /// Create a new min-sum decoder context for `code`.
+    ///
+    /// * `working` must have `code.decode_ms_working_len()` elements.
+    /// * `working_u8` must have `code.decode_ms_working_u8_len()` elements.
+    ///
+    /// Returns `DecodeError::BadBufferLen` if either working area is the wrong length.
+    pub fn new(code: LDPCCode, working: &'a mut [T], working_u8: &'a mut [u8])
+        -> Result<MsDecoder<'a, T>, DecodeError>
+    {
+        check_len("working", code.decode_ms_working_len(), working.len())?;
+        check_len("working_u8", code.decode_ms_working_u8_len(), working_u8.len())?;
+        Ok(MsDecoder { code, working, working_u8 })
+    }
+
+    /// Get the code this decoder was created for.
+    pub fn code(&self) -> LDPCCode {
+        self.code
+    }
+
+    /// Decode `llrs` into `output`, running for at most `maxiters` iterations.
+    ///
+    /// * `llrs` must be `n` long.
+    /// * `output` must be `code.output_len()` long.
+    ///
+    /// See `LDPCCode::decode_ms` for full details of the decoder.
+    ///
+    /// Returns `DecodeError::BadBufferLen` if `llrs` or `output` are the wrong length, or
+    /// `DecodeError::NonFiniteInput` if any of `llrs` is NaN or infinite, otherwise returns
+    /// the statistics of the decode.
+    pub fn decode(&mut self, llrs: &[T], output: &mut [u8], maxiters: usize)
+        -> Result<DecodeStats, DecodeError>
+    {
+        check_len("llrs", self.code.n(), llrs.len())?;
+        check_len("output", self.code.output_len(), output.len())?;
+        check_finite(llrs)?;
+        Ok(self.decode_unchecked(llrs, output, maxiters))
+    }
+
+    /// Decode `llrs` into `output` without checking any lengths or values.
+    fn decode_unchecked(&mut self, llrs: &[T], output: &mut [u8], maxiters: usize)
+        -> DecodeStats
+    {
+        let code = self.code;
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        let result = code.decode_ms_unchecked(|| code.iter_paritychecks(), init_llrs, output,
+                                              self.working, self.working_u8, maxiters,
+                                              MsCorrection::None, |_| true, false, |_, _| ());
+        DecodeStats::from(result)
+    }
+
+    /// Decode many codewords back to back, running for at most `maxiters` iterations each.
+    ///
+    /// * `llrs_blocks` holds one `n`-long slice of LLRs per codeword.
+    /// * `outputs` must have one `code.output_len()`-long slice per entry of `llrs_blocks`.
+    /// * `stats` must have one entry per entry of `llrs_blocks`, and is filled with the
+    ///   statistics of each decode.
+    ///
+    /// Every length is checked before any decoding starts, so on error no output is written.
+    /// Otherwise this is equivalent to calling `decode` on each block in turn.
+    pub fn decode_batch(&mut self, llrs_blocks: &[&[T]], outputs: &mut [&mut [u8]],
+                        stats: &mut [DecodeStats], maxiters: usize)
+        -> Result<(), DecodeError>
+    {
+        check_len("outputs", llrs_blocks.len(), outputs.len())?;
+        check_len("stats", llrs_blocks.len(), stats.len())?;
+        for llrs in llrs_blocks {
+            check_len("llrs", self.code.n(), llrs.len())?;
+            check_finite(llrs)?;
+        }
+        for output in outputs.iter() {
+            check_len("output", self.code.output_len(), output.len())?;
+        }
+
+        let code = self.code;
+        for ((llrs, output), stat) in llrs_blocks.iter().zip(outputs.iter_mut())
+                                                 .zip(stats.iter_mut())
+        {
+            let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+            let result = code.decode_ms_unchecked(|| code.iter_paritychecks(), init_llrs,
+                                                  output, self.working, self.working_u8,
+                                                  maxiters, MsCorrection::None,
+                                                  |_| true, false, |_, _| ());
+            *stat = DecodeStats::from(result);
+        }
+        Ok(())
+    }
+}
+
+/// Decoder for a stream of received codewords, the counterpart to `encoder::StreamEncoder`.
+///
+/// LLRs may be written in pieces of any length, for example as they arrive from a receiver.
+/// Each time a full codeword of n LLRs has been received it is decoded with an `MsDecoder`,
+/// and its k/8 bytes of decoded data are passed to a callback. If the length of the original
+/// data is given with `set_length`, the zero padding added by `StreamEncoder::finish` is
+/// removed from the final block. No allocation is required: the caller provides the buffers.
+///
+/// ```
+/// # #[cfg(feature = "tc128")] {
+/// # use labrador_ldpc::LDPCCode;
+/// # use labrador_ldpc::decoder::{MsDecoder, StreamDecoder};
+/// let code = LDPCCode::TC128;
+/// let mut codeword = [0u8; 16];
+/// code.copy_encode(b"Hello, w", &mut codeword);
+/// let mut llrs = vec![0i8; code.n()];
+/// code.hard_to_llrs(&codeword, &mut llrs);
+///
+/// let mut working = vec![0i8; code.decode_ms_working_len()];
+/// let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+/// let mut buf = vec![0i8; code.n()];
+/// let mut output = vec![0u8; code.output_len()];
+/// let decoder = MsDecoder::new(code, &mut working, &mut working_u8).unwrap();
+/// let maxiters = LDPCCode::DEFAULT_MAX_ITERS;
+/// let mut stream = StreamDecoder::new(decoder, &mut buf, &mut output, maxiters).unwrap();
+///
+/// // Only the first 5 bytes were real data
+/// stream.set_length(5);
+/// let mut data = Vec::new();
+/// stream.write(&llrs[..100], |d, _| data.extend_from_slice(d));
+/// stream.write(&llrs[100..], |d, _| data.extend_from_slice(d));
+/// assert_eq!(data, b"Hello");
+/// # }
+/// ```
+pub struct StreamDecoder<'a, T: DecodeFrom> {
+    decoder: MsDecoder<'a, T>,
+    llrs: &'a mut [T],
+    output: &'a mut [u8],
+    fill: usize,
+    maxiters: usize,
+    remaining: Option<usize>,
+}
+
+impl<'a, T: DecodeFrom> StreamDecoder<'a, T> {
+    /// Create a new stream decoder, decoding each codeword with `decoder` for at most
+    /// `maxiters` iterations.
+    ///
+    /// * `llrs` must be n long, and buffers each codeword until it is complete.
+    /// * `output` must be `code.output_len()` long.
+    ///
+    /// Returns `DecodeError::BadBufferLen` if either buffer is the wrong length.
+    pub fn new(decoder: MsDecoder<'a, T>, llrs: &'a mut [T], output: &'a mut [u8],
+               maxiters: usize)
+        -> Result<StreamDecoder<'a, T>, DecodeError>
+    {
+        check_len("llrs", decoder.code().n(), llrs.len())?;
+        check_len("output", decoder.code().output_len(), output.len())?;
+        Ok(StreamDecoder { decoder, llrs, output, fill: 0, maxiters, remaining: None })
+    }
+
+    /// Get the code this decoder was created for.
+    pub fn code(&self) -> LDPCCode {
+        self.decoder.code()
+    }
+
+    /// Get the number of LLRs received but not yet decoded, always less than n.
+    pub fn buffered(&self) -> usize {
+        self.fill
+    }
+
+    /// Set the number of data bytes still expected from the stream.
+    ///
+    /// Once `len` bytes have been passed to the callback, the remaining data in that block is
+    /// treated as padding and discarded, and any further codewords are dropped without being
+    /// decoded. Without a length, every byte of every block is passed on.
+    pub fn set_length(&mut self, len: usize) {
+        self.remaining = Some(len);
+    }
+
+    /// Write `llrs` to the stream, calling `emit` for each codeword completed.
+    ///
+    /// `llrs` may be any length. `emit` is called with the decoded data of each codeword,
+    /// normally k/8 bytes but shorter for the final block if `set_length` was used, and the
+    /// statistics of its decode, so failed blocks can be detected.
+    ///
+    /// As for `decode_ms`, the LLRs must be finite; they are not checked here.
+    ///
+    /// Returns the number of codewords decoded.
+    pub fn write<F: FnMut(&[u8], DecodeStats)>(&mut self, llrs: &[T], mut emit: F) -> usize {
+        let n = self.code().n();
+        let kb = self.code().k() / 8;
+        let mut decoded = 0;
+        let mut llrs = llrs;
+        while !llrs.is_empty() {
+            let len = llrs.len().min(n - self.fill);
+            self.llrs[self.fill..self.fill + len].copy_from_slice(&llrs[..len]);
+            self.fill += len;
+            llrs = &llrs[len..];
+
+            if self.fill == n {
+                self.fill = 0;
+                let len = match self.remaining {
+                    Some(0) => continue,
+                    Some(remaining) => remaining.min(kb),
+                    None => kb,
+                };
+                // The buffer lengths were checked at construction
+                let stats = self.decoder.decode_unchecked(self.llrs, self.output, self.maxiters);
+                emit(&self.output[..len], stats);
+                if let Some(ref mut remaining) = self.remaining {
+                    *remaining -= len;
+                }
+                decoded += 1;
+            }
+        }
+        decoded
+    }
+}
+
+/// Min-sum decoder which owns its working areas.
+///
+/// Identical to `MsDecoder`, except the working areas are allocated on the heap at
+/// construction instead of being borrowed from the caller. Only available with the `alloc`
+/// feature.
+///
+/// ```
+/// # use labrador_ldpc::LDPCCode;
+/// let code = LDPCCode::TC128;
+/// let mut decoder = code.ms_decoder_alloc::<i8>();
+///
+/// let llrs = vec![1i8; code.n()];
+/// let mut output = vec![0u8; code.output_len()];
+/// let stats = decoder.decode(&llrs, &mut output, LDPCCode::DEFAULT_MAX_ITERS).unwrap();
+/// assert!(stats.converged);
+/// ```
+#[cfg(feature = "alloc")]
+pub struct OwnedMsDecoder<T: DecodeFrom> {
+    code: LDPCCode,
+    working: Vec<T>,
+    working_u8: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: DecodeFrom> OwnedMsDecoder<T> {
+    /// Create a new min-sum decoder for `code`, allocating its working areas.
+    pub fn new(code: LDPCCode) -> OwnedMsDecoder<T> {
+        OwnedMsDecoder {
+            code,
+            working: alloc::vec![T::zero(); code.decode_ms_working_len()],
+            working_u8: alloc::vec![0; code.decode_ms_working_u8_len()],
+        }
+    }
+
+    /// Get the code this decoder was created for.
+    pub fn code(&self) -> LDPCCode {
+        self.code
+    }
+
+    /// Borrow this decoder's working areas as an `MsDecoder`, for example to use
+    /// `MsDecoder::decode_batch`.
+    pub fn decoder<'a>(&'a mut self) -> MsDecoder<'a, T> {
+        MsDecoder { code: self.code, working: &mut self.working, working_u8: &mut self.working_u8 }
+    }
+
+    /// Decode `llrs` into `output`, running for at most `maxiters` iterations.
+    ///
+    /// See `MsDecoder::decode` for details.
+    pub fn decode(&mut self, llrs: &[T], output: &mut [u8], maxiters: usize)
+        -> Result<DecodeStats, DecodeError>
+    {
+        self.decoder().decode(llrs, output, maxiters)
+    }
 }
 
 impl LDPCCode {
 
+    /// A suitable default for the `maxiters` argument of the decoders.
+    ///
+    /// The decoders never choose an iteration limit themselves; the soft decoding examples in
+    /// this documentation use this value, which is ample for the soft decoders at useful
+    /// signal to noise ratios, where most codewords converge in a handful of iterations.
+    /// Every decoder stops after at most `maxiters` iterations, each costing a small fixed
+    /// number of passes over the `paritycheck_sum()` edges of the parity check matrix, so
+    /// together these bound the worst-case decode time. The hard decision decoders may
+    /// additionally run up to `maxiters` iterations of erasure decoding on codes with
+    /// punctured bits.
+    pub const DEFAULT_MAX_ITERS: usize = 20;
+
+    /// Create a min-sum decoder for this code which allocates its own working areas.
+    ///
+    /// Only available with the `alloc` feature. See `OwnedMsDecoder`.
+    #[cfg(feature = "alloc")]
+    pub fn ms_decoder_alloc<T: DecodeFrom>(self) -> OwnedMsDecoder<T> {
+        OwnedMsDecoder::new(self)
+    }
+
     /// Get the length of [u8] required for the working area of `decode_bf`.
     ///
     /// Equal to n + punctured_bits.
-    pub fn decode_bf_working_len(self) -> usize {
+    pub const fn decode_bf_working_len(self) -> usize {
         self.n() + self.punctured_bits()
     }
 
     /// Get the length of [T] required for the working area of `decode_ms`.
     ///
     /// Equal to 2 * paritycheck_sum + 3*n + 3*punctured_bits - 2*k.
-    pub fn decode_ms_working_len(self) -> usize {
+    pub const fn decode_ms_working_len(self) -> usize {
         2 * self.paritycheck_sum() as usize + 3*self.n() + 3*self.punctured_bits() - 2*self.k()
     }
 
+    /// Get the length of [u8] required for the working area of `try_decode_ms_bytes`.
+    ///
+    /// Equal to 4 * decode_ms_working_len, the size of the `f32` working area in bytes.
+    pub const fn decode_ms_working_len_bytes(self) -> usize {
+        self.decode_ms_working_len() * mem::size_of::<f32>()
+    }
+
     /// Get the length of [u8] required for the working_u8 area of `decode_ms`.
     ///
     /// Equal to (n + punctured_bits - k)/8.
-    pub fn decode_ms_working_u8_len(self) -> usize {
+    pub const fn decode_ms_working_u8_len(self) -> usize {
         (self.n() + self.punctured_bits() - self.k()) / 8
     }
 
     /// Get the length of [u8] required for the output of any decoder.
     ///
     /// Equal to (n+punctured_bits)/8.
-    pub fn output_len(self) -> usize {
+    pub const fn output_len(self) -> usize {
         (self.n() + self.punctured_bits()) / 8
     }
 
-    /// Hard erasure decoding algorithm.
+    /// Get the length of [u8] required for the working area of `decode_gallager_b`.
     ///
-    /// Used to preprocess punctured codes before attempting bit-flipping decoding,
-    /// as the bit-flipping algorithm cannot handle erasures.
+    /// Equal to n + punctured_bits + ceil(paritycheck_sum/8) + (n + punctured_bits - k)/8.
+    pub const fn decode_gallager_b_working_len(self) -> usize {
+        self.n() + self.punctured_bits() + (self.paritycheck_sum() as usize).div_ceil(8)
+            + (self.n() + self.punctured_bits() - self.k()) / 8
+    }
+
+    /// Get the length of [u8] required for the working area of `decode_wbf`.
+    ///
+    /// Equal to n + punctured_bits.
+    pub const fn decode_wbf_working_len(self) -> usize {
+        self.n() + self.punctured_bits()
+    }
+
+    /// Get the length of [i16] required for the working_i16 area of `decode_wbf`.
+    ///
+    /// Equal to 2*n + 2*punctured_bits - k.
+    pub const fn decode_wbf_working_i16_len(self) -> usize {
+        2 * (self.n() + self.punctured_bits()) - self.k()
+    }
+
+    /// Get the length of [u8] required for the working area of `decode_gdbf`.
+    ///
+    /// Equal to n + punctured_bits.
+    pub const fn decode_gdbf_working_len(self) -> usize {
+        self.n() + self.punctured_bits()
+    }
+
+    /// Get the length of [f32] required for the working_f32 area of `decode_gdbf`.
+    ///
+    /// Equal to n + punctured_bits.
+    pub const fn decode_gdbf_working_f32_len(self) -> usize {
+        self.n() + self.punctured_bits()
+    }
+
+    /// Get the lengths of every buffer required by the decoder `kind`.
+    ///
+    /// This gathers the individual `*_len()` methods for each decoder in one place, so that
+    /// exactly the buffers required can be allocated. No decoder ever needs the parity check
+    /// matrix in RAM, as it is always generated on the fly from compact constants.
+    pub const fn decoder_buffers(self, kind: DecoderKind) -> DecoderBuffers {
+        let (working_u8, working_soft) = match kind {
+            DecoderKind::BitFlipping => (self.decode_bf_working_len(), 0),
+            DecoderKind::GallagerB => (self.decode_gallager_b_working_len(), 0),
+            DecoderKind::WeightedBitFlipping =>
+                (self.decode_wbf_working_len(), self.decode_wbf_working_i16_len()),
+            DecoderKind::GradientDescentBitFlipping =>
+                (self.decode_gdbf_working_len(), self.decode_gdbf_working_f32_len()),
+            DecoderKind::MinSum | DecoderKind::SumProduct =>
+                (self.decode_ms_working_u8_len(), self.decode_ms_working_len()),
+        };
+        DecoderBuffers { output: self.output_len(), working_u8, working_soft }
+    }
+
+    /// Get the lengths of every buffer, including the inputs, read or written by the decoder
+    /// `kind`, so that they can all be allocated in one pass.
+    pub const fn buffer_plan(self, kind: DecoderKind) -> BufferPlan {
+        let (input_u8, input_soft) = match kind {
+            DecoderKind::BitFlipping | DecoderKind::GallagerB => (self.n()/8, 0),
+            DecoderKind::WeightedBitFlipping => (self.n()/8, self.n()),
+            DecoderKind::GradientDescentBitFlipping | DecoderKind::MinSum
+                | DecoderKind::SumProduct => (0, self.n()),
+        };
+        BufferPlan { input_u8, input_soft, buffers: self.decoder_buffers(kind) }
+    }
+
+    /// Get the total memory in bytes of the output and working areas of the decoder `kind`.
+    ///
+    /// `T` is the type of the soft values used by the min-sum and sum-product decoders, such
+    /// as `i8` or `f32`, and is ignored for the other decoders, which always use the types
+    /// listed in `DecoderBuffers`. The input is not included, and no decoder needs any memory
+    /// for the parity check matrix, which is always generated from compact constants.
+    ///
+    /// This is intended for deciding at runtime whether a decoder fits in the RAM available.
+    pub const fn total_decoder_bytes<T>(self, kind: DecoderKind) -> usize {
+        let buffers = self.decoder_buffers(kind);
+        let soft_size = match kind {
+            DecoderKind::BitFlipping | DecoderKind::GallagerB => 0,
+            DecoderKind::WeightedBitFlipping => mem::size_of::<i16>(),
+            DecoderKind::GradientDescentBitFlipping => mem::size_of::<f32>(),
+            DecoderKind::MinSum | DecoderKind::SumProduct => mem::size_of::<T>(),
+        };
+        buffers.output + buffers.working_u8 + buffers.working_soft * soft_size
+    }
+
+    /// Get the length of [u8] required for the syndrome written by `compute_syndrome`.
+    ///
+    /// Equal to (n + punctured_bits - k)/8.
+    pub const fn syndrome_len(self) -> usize {
+        (self.n() + self.punctured_bits() - self.k()) / 8
+    }
+
+    /// Compute the syndrome of a codeword.
+    ///
+    /// Each bit of `syndrome` is set to the result of one parity check on `codeword`, packed in
+    /// the same bit order as codewords, so a bit is 1 exactly when that parity check fails.
+    /// This is the same test the decoders use to decide whether they have found a codeword.
+    ///
+    /// The syndrome is found by walking the sparse parity check edges, so each check reads a
+    /// handful of scattered bits rather than contiguous words. The `simd` feature therefore
+    /// does not change this method; the decoders likewise fold their parity tests into their
+    /// main message passing loops.
+    ///
+    /// * `codeword` must be the full codeword including any punctured bits, so must be
+    ///   `output_len()` long, for example the output of a decoder.
+    /// * `syndrome` must be `syndrome_len()` long.
+    ///
+    /// Returns true if every parity check is satisfied.
+    pub fn compute_syndrome(self, codeword: &[u8], syndrome: &mut [u8]) -> bool {
+        assert_eq!(codeword.len(), self.output_len(), "codeword.len() != (n+p)/8");
+        assert_eq!(syndrome.len(), self.syndrome_len(), "syndrome.len() != (n+p-k)/8");
+
+        for s in &mut syndrome[..] { *s = 0 }
+        for (check, var) in self.iter_paritychecks() {
+            if (codeword[var/8] >> (7-(var%8))) & 1 == 1 {
+                syndrome[check/8] ^= 1 << (7-(check%8));
+            }
+        }
+
+        syndrome.iter().all(|s| *s == 0)
+    }
+
+    /// Check whether `codeword` is a valid codeword, satisfying every parity check.
+    ///
+    /// This gives the same answer as `compute_syndrome` without running a decoder, but
+    /// computes the syndrome one block row of checks at a time and stops at the first block
+    /// row containing a failed check, so invalid codewords are usually rejected quickly.
+    ///
+    /// * `codeword` must be the full codeword including any punctured bits, so must be
+    ///   `output_len()` long.
+    /// * `syndrome` must be `syndrome_len()` long, and is used as a working area; its
+    ///   contents afterwards are unspecified.
+    pub fn is_codeword(self, codeword: &[u8], syndrome: &mut [u8]) -> bool {
+        assert_eq!(codeword.len(), self.output_len(), "codeword.len() != (n+p)/8");
+        assert_eq!(syndrome.len(), self.syndrome_len(), "syndrome.len() != (n+p-k)/8");
+
+        let m = self.submatrix_size();
+        for s in &mut syndrome[..] { *s = 0 }
+        for (row, row_syndrome) in syndrome.chunks_mut(m/8).enumerate() {
+            for (check, var) in self.iter_paritychecks_row(row) {
+                if (codeword[var/8] >> (7-(var%8))) & 1 == 1 {
+                    let check = check - row*m;
+                    row_syndrome[check/8] ^= 1 << (7-(check%8));
+                }
+            }
+            if row_syndrome.iter().any(|s| *s != 0) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Count how many parity checks a codeword fails.
+    ///
+    /// This is a cheap indication of channel quality: it is zero for a valid codeword and
+    /// grows with the number of bit errors, without needing to run a decoder.
+    ///
+    /// Parity checks are not stored contiguously, so `syndrome` is required as a working area
+    /// and is left containing the syndrome, as for `compute_syndrome`.
+    ///
+    /// * `codeword` must be the full codeword including any punctured bits, so must be
+    ///   `output_len()` long.
+    /// * `syndrome` must be `syndrome_len()` long.
+    pub fn count_unsatisfied_checks(self, codeword: &[u8], syndrome: &mut [u8]) -> usize {
+        self.compute_syndrome(codeword, syndrome);
+        syndrome.iter().map(|s| s.count_ones() as usize).sum()
+    }
+
+    /// Estimate how many bit errors a received codeword contains, without decoding it.
+    ///
+    /// This is intended as a cheap link quality indicator. Any punctured bits are first filled
+    /// in using the erasure decoder, then the number of failed parity checks is divided by the
+    /// average number of checks each bit takes part in, since each isolated bit error fails
+    /// about that many checks.
+    ///
+    /// The result is only an estimate and not an exact count. For the TC codes it is close to
+    /// the true count for a few scattered errors. For the TM codes, received errors also corrupt
+    /// the recovered punctured bits, so the estimate is typically around twice the true count.
+    /// Errors sharing a parity check cancel out, so it saturates as the error count grows, and
+    /// it is zero for any valid codeword, including a wrong one. It is best used as a relative
+    /// indication of link health rather than an absolute bit error count.
+    ///
+    /// * `input` must be n/8 long, the received hard information, and is not modified.
+    /// * `codeword` must be `output_len()` long, and is used as a working area.
+    /// * `working` must be `decode_bf_working_len()` long.
+    pub fn estimate_errors(self, input: &[u8], codeword: &mut [u8], working: &mut [u8])
+        -> usize
+    {
+        assert_eq!(input.len(), self.n()/8, "input.len() != n/8");
+        assert_eq!(codeword.len(), self.output_len(), "codeword.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_bf_working_len(), "working.len() incorrect");
+
+        let p = self.punctured_bits();
+        codeword[..self.n()/8].copy_from_slice(input);
+        if p > 0 {
+            // Each pass of the erasure decoder recovers at least one bit or stops
+            self.decode_erasures_inner(|_| true, codeword, working, p);
+        }
+
+        let syndrome = &mut working[..self.syndrome_len()];
+        let unsatisfied = self.count_unsatisfied_checks(codeword, syndrome);
+        let edges = self.paritycheck_sum() as usize;
+        (unsatisfied * (self.n() + p) + edges/2) / edges
+    }
+
+    /// Erasure decoder.
+    ///
+    /// Recovers erased bits of a codeword using only the parity checks, for channels which
+    /// mark unreliable bits as erased (for example packet-level FEC), or to fill in the
+    /// punctured bits of the TM codes. Bits which are not erased are assumed to be correct.
     ///
     /// The algorithm is:
     ///     * We compute the parity of each check over all non-erased bits
@@ -131,25 +1024,45 @@ impl LDPCCode {
     ///       that variable, +1 if check parity is 1, otherwise -1
     ///     * Each variable that receives a majority vote (i.e. not equal 0) is set to that
     ///       vote and marked decoded
-    ///     * Iterate until all variables are decoded or we reach the iteration limit
+    ///     * Iterate until all variables are decoded, no more can be decoded, or we reach
+    ///       the iteration limit
     ///
     /// This is based on the paper:
     /// Novel multi-Gbps bit-flipping decoders for punctured LDPC codes,
     /// by Archonta, Kanistras, and Paliouras, MOCAST 2016.
     ///
+    /// * `known` must be n long, and is false for each received bit which is erased.
+    ///   Punctured bits are always treated as erased.
     /// * `codeword` must be (n+p)/8 long (`self.output_len()`), with the first n/8 bytes already
-    ///   set to the received hard information, and the punctured bits at the end will be updated.
+    ///   set to the received hard information. Erased bits are overwritten with their decoded
+    ///   value, or 0 if they could not be decoded.
     /// * `working` must be (n+p) bytes long (`self.decode_bf_working_len()`).
     ///
-    /// Returns `(success, number of iterations run)`. Success only indicates that every punctured
-    /// bit got a majority vote; but they might still be wrong; likewise failure means not every
-    /// bit got a vote but many may still have been determined correctly.
-    #[allow(clippy::many_single_char_names)]
-    fn decode_erasures(self, codeword: &mut [u8], working: &mut [u8], maxiters: usize)
+    /// Returns `(success, number of iterations run)`. Success indicates every erased bit was
+    /// recovered, though if any non-erased bits were wrong, recovered bits may be too.
+    pub fn decode_erasures(self, known: &[bool], codeword: &mut [u8], working: &mut [u8],
+                           maxiters: usize)
         -> (bool, usize)
     {
-        assert_eq!(codeword.len(), self.output_len());
-        assert_eq!(working.len(), self.decode_bf_working_len());
+        assert_eq!(known.len(), self.n(), "known.len() != n");
+        assert_eq!(codeword.len(), self.output_len(), "codeword.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_bf_working_len(), "working.len() incorrect");
+        self.decode_erasures_inner(|var| known[var], codeword, working, maxiters)
+    }
+
+    /// Erasure decoder implementation, see `decode_erasures`.
+    ///
+    /// `known(var)` is called for each var in 0..n and returns false if it is erased.
+    /// Used to preprocess punctured codes before attempting bit-flipping decoding,
+    /// as the bit-flipping algorithm cannot handle erasures.
+    #[allow(clippy::many_single_char_names)]
+    pub(crate) fn decode_erasures_inner<F>(self, known: F, codeword: &mut [u8], working: &mut [u8],
+                                maxiters: usize)
+        -> (bool, usize)
+        where F: Fn(usize) -> bool
+    {
+        assert_eq!(codeword.len(), self.output_len());
+        assert_eq!(working.len(), self.decode_bf_working_len());
 
         let n = self.n();
         let p = self.punctured_bits();
@@ -162,18 +1075,30 @@ impl LDPCCode {
         // * The fourth top bit 0x10 for byte 'a' indicates whether variable 'a' is erased
         // * The lowest four bits 0x0F for byte 'a' indicate the votes received for variable 'a',
         //   starting at 8 for 0 votes and being incremented and decremented from there.
+        //   No variable has more than 7 checks, so this cannot overflow.
 
-        // Initialse working area: mark all punctured bits as erased
-        for w in &mut working[..n] { *w = 0x00 }
-        for w in &mut working[n..] { *w = 0x10 }
-
-        // Also write all the punctured bits in the codeword to zero
-        for c in &mut codeword[n/8..] { *c = 0x00 }
+        // Initialse working area: mark all erased and punctured bits as erased,
+        // and write all erased bits in the codeword to zero.
+        let mut erasures = 0;
+        for (var, w) in working[..(n+p)].iter_mut().enumerate() {
+            if var < n && known(var) {
+                *w = 0x00;
+            } else {
+                *w = 0x10;
+                codeword[var/8] &= !(1<<(7-(var%8)));
+                erasures += 1;
+            }
+        }
 
         // Keep track of how many bits we've fixed
         let mut bits_fixed = 0;
 
         for iter in 0..maxiters {
+            if bits_fixed == erasures {
+                // Hurray we're done
+                return (true, iter)
+            }
+
             // Initialise parity and erasure counts to zero, reset votes, preserve erasure bit
             for w in &mut working[..] { *w = (*w & 0x10) | 0x08 }
 
@@ -206,24 +1131,25 @@ impl LDPCCode {
             }
 
             // Finally fix all bits that are erased and have a majority vote
+            let fixed_before = bits_fixed;
             for (var, working) in working[0..(n+p)].iter_mut().enumerate() {
-                if *working & 0x10 == 0x10 {
+                if *working & 0x10 == 0x10 && *working & 0x0F != 0x08 {
                     if *working & 0x0F > 0x08 {
                         codeword[var/8] |= 1<<(7-(var%8));
-                        *working &= !0x10;
                     }
+                    *working &= !0x10;
                     bits_fixed += 1;
                 }
             }
 
-            if bits_fixed == p {
-                // Hurray we're done
-                return (true, iter)
+            // If no bits could be fixed, further iterations will not help
+            if bits_fixed == fixed_before {
+                return (false, iter + 1);
             }
         }
 
-        // If we finished the iteration loop then we did not succeed.
-        (false, maxiters)
+        // If we finished the iteration loop then we succeeded only if every bit was fixed.
+        (bits_fixed == erasures, maxiters)
     }
 
     /// Bit flipping decoder.
@@ -232,6 +1158,10 @@ impl LDPCCode {
     /// lot of error-correcting capability behind. It is around 1-2dB worse than the min-sum
     /// decoder. However, it requires much less memory and is a lot quicker.
     ///
+    /// The parity check matrix is walked directly from the compact constants using
+    /// `iter_paritychecks`, so it never needs expanding into RAM; the only RAM used is the
+    /// input, output, and working area described below.
+    ///
     /// Requires:
     ///
     /// * `input` must be `n/8` long, where each bit is the received hard information
@@ -239,11 +1169,18 @@ impl LDPCCode {
     ///   with the decoded codeword, so the user data is present in the first `k/8` bytes.
     /// * `working` must be `n+punctured_bits` (=`self.decode_bf_working_len()`) bytes long.
     ///
+    /// In each iteration, every bit taking part in the largest number of failed parity checks is
+    /// flipped, so all bits tied for that number are flipped together. Use
+    /// `decode_bf_with_strategy` to flip only one of them instead.
+    ///
     /// Runs for at most `maxiters` iterations, both when attempting to fix punctured erasures on
     /// applicable codes, and in the main bit flipping decoder.
     ///
-    /// Returns `(decoding success, iters)`. For punctured codes, `iters` includes iterations
-    /// of the erasure decoding algorithm which is run first.
+    /// Returns `(decoding success, iters)`. Success means every parity check was satisfied when
+    /// the decoder stopped, including after the final iteration's flips. For punctured codes,
+    /// `iters` includes iterations of the erasure decoding algorithm which is run first.
+    /// An error-free input therefore returns `(true, 0)` on the TC codes, and `(true, 1)` on
+    /// the punctured TM codes, whose punctured bits are all recovered in one erasure iteration.
     pub fn decode_bf(self, input: &[u8], output: &mut [u8],
                      working: &mut [u8], maxiters: usize)
         -> (bool, usize)
@@ -253,19 +1190,159 @@ impl LDPCCode {
         assert_eq!(working.len(), self.decode_bf_working_len(), "working.len() incorrect");
 
         output[..self.n()/8].copy_from_slice(input);
+        self.decode_bf_loaded(output, None, working, maxiters, BfStrategy::FlipAll)
+    }
+
+    /// Bit flipping decoder with the given bit packing.
+    ///
+    /// Identical to `decode_bf`, except `input` is packed in `order`, and `output` is written
+    /// in `order` too. `BitOrder::MsbFirst` behaves exactly as `decode_bf`.
+    pub fn decode_bf_ordered(self, input: &[u8], output: &mut [u8], working: &mut [u8],
+                             maxiters: usize, order: BitOrder)
+        -> (bool, usize)
+    {
+        assert_eq!(input.len(), self.n()/8, "input.len() != n/8");
+        assert_eq!(output.len(), self.output_len(), "output.len != (n+p)/8");
+        assert_eq!(working.len(), self.decode_bf_working_len(), "working.len() incorrect");
+
+        output[..self.n()/8].copy_from_slice(input);
+        order.reorder(&mut output[..self.n()/8]);
+        let result = self.decode_bf_loaded(output, None, working, maxiters, BfStrategy::FlipAll);
+        order.reorder(output);
+        result
+    }
+
+    /// Bit flipping decoder taking hard information as one `bool` per bit.
+    ///
+    /// Identical to `decode_bf`, except `input` is n long, with `true` for each received 1 bit.
+    /// The output is packed as usual; use `bits::unpack_bools` if you need it as `bool`s.
+    pub fn decode_bf_bools(self, input: &[bool], output: &mut [u8],
+                           working: &mut [u8], maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(input.len(), self.n(), "input.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len != (n+p)/8");
+        assert_eq!(working.len(), self.decode_bf_working_len(), "working.len() incorrect");
+
+        pack_bools(input, &mut output[..self.n()/8]);
+        self.decode_bf_loaded(output, None, working, maxiters, BfStrategy::FlipAll)
+    }
+
+    /// Bit flipping decoder with explicitly erased received bits.
+    ///
+    /// Identical to `decode_bf`, except `erased` must be n long and is true for each received
+    /// bit the demodulator marked as erased. These bits are recovered by the same erasure
+    /// decoding step as the punctured bits before bit flipping begins, instead of being
+    /// treated as hard decisions. Bits which are not marked behave exactly as for `decode_bf`.
+    ///
+    /// All other buffers and the return value are as for `decode_bf`.
+    pub fn decode_bf_erasures(self, input: &[u8], erased: &[bool], output: &mut [u8],
+                              working: &mut [u8], maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(input.len(), self.n()/8, "input.len() != n/8");
+        assert_eq!(erased.len(), self.n(), "erased.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len != (n+p)/8");
+        assert_eq!(working.len(), self.decode_bf_working_len(), "working.len() incorrect");
+
+        output[..self.n()/8].copy_from_slice(input);
+        self.decode_bf_loaded(output, Some(erased), working, maxiters, BfStrategy::FlipAll)
+    }
+
+    /// Bit flipping decoder with a choice of which tied bits to flip.
+    ///
+    /// In each iteration, `decode_bf` flips every bit which takes part in the largest number of
+    /// failed parity checks, so when several bits are tied for that number they are all
+    /// flipped together. This method is identical, except `strategy` selects which of the tied
+    /// bits are flipped. `BfStrategy::FlipAll` gives exactly the same result as `decode_bf`;
+    /// flipping a single bit per iteration is more cautious but needs more iterations.
+    ///
+    /// All buffers and the return value are as for `decode_bf`.
+    pub fn decode_bf_with_strategy(self, input: &[u8], output: &mut [u8], working: &mut [u8],
+                                   maxiters: usize, strategy: BfStrategy)
+        -> (bool, usize)
+    {
+        assert_eq!(input.len(), self.n()/8, "input.len() != n/8");
+        assert_eq!(output.len(), self.output_len(), "output.len != (n+p)/8");
+        assert_eq!(working.len(), self.decode_bf_working_len(), "working.len() incorrect");
+
+        output[..self.n()/8].copy_from_slice(input);
+        self.decode_bf_loaded(output, None, working, maxiters, strategy)
+    }
+
+    /// Serial bit flipping decoder, flipping a single bit per iteration.
+    ///
+    /// Identical to `decode_bf`, except each iteration flips only the one bit taking part in
+    /// the most failed parity checks (the lowest indexed, if several are tied), which is the
+    /// textbook serial schedule and the same as `decode_bf_with_strategy` with
+    /// `BfStrategy::FlipLowest`. As at most one bit is flipped per iteration, give it a larger
+    /// `maxiters`, at least the number of errors expected.
+    ///
+    /// Flipping every tied bit at once can oscillate on some error patterns without ever
+    /// converging, so a good approach is to try `decode_bf` first and fall back to this if it
+    /// fails. It uses the same buffers as `decode_bf`, and the return value is as for
+    /// `decode_bf`.
+    pub fn decode_bf_single(self, input: &[u8], output: &mut [u8], working: &mut [u8],
+                            maxiters: usize)
+        -> (bool, usize)
+    {
+        self.decode_bf_with_strategy(input, output, working, maxiters, BfStrategy::FlipLowest)
+    }
+
+    /// Bit flipping decoder for several codewords stored back to back.
+    ///
+    /// For the short TC codes the fixed cost of each call, such as checking buffer lengths,
+    /// is a noticeable fraction of the decoding time. This decodes `m` independent codewords
+    /// in one call, checking every length once and reusing the same working area for each
+    /// codeword in turn. Each codeword is decoded exactly as by `decode_bf`.
+    ///
+    /// * `input` must be `m*n/8` long, holding the received hard information for each
+    ///   codeword one after another
+    /// * `output` must be `m*output_len()` long, and is written with each decoded codeword in
+    ///   the same order
+    /// * `working` must be `decode_bf_working_len()` long, as for `decode_bf`
+    /// * `results` must be `m` long, and is set to the `(decoding success, iters)` of each
+    ///   codeword, as returned by `decode_bf`
+    pub fn decode_bf_multi(self, m: usize, input: &[u8], output: &mut [u8], working: &mut [u8],
+                           maxiters: usize, results: &mut [(bool, usize)])
+    {
+        assert_eq!(input.len(), m * self.n()/8, "input.len() != m*n/8");
+        assert_eq!(output.len(), m * self.output_len(), "output.len != m*(n+p)/8");
+        assert_eq!(working.len(), self.decode_bf_working_len(), "working.len() incorrect");
+        assert_eq!(results.len(), m, "results.len() != m");
 
-        // For punctured codes we must first try and fix all the punctured bits.
-        // We run them through an erasure decoding algorithm and record how many iterations
-        // it took (so we can return the total).
-        let erasure_iters = if self.punctured_bits() > 0 {
-            let (_, iters) = self.decode_erasures(output, working, maxiters);
+        let inputs = input.chunks_exact(self.n()/8);
+        let outputs = output.chunks_exact_mut(self.output_len());
+        for ((input, output), result) in inputs.zip(outputs).zip(results.iter_mut()) {
+            output[..self.n()/8].copy_from_slice(input);
+            *result = self.decode_bf_loaded(output, None, working, maxiters, BfStrategy::FlipAll);
+        }
+    }
+
+    /// Bit flipping decoder implementation, see `decode_bf`.
+    ///
+    /// The received hard information must already be in the first n/8 bytes of `output`,
+    /// and all buffer lengths must already have been validated. If given, `erased` marks
+    /// received bits to be treated as erasures alongside the punctured bits.
+    fn decode_bf_loaded(self, output: &mut [u8], erased: Option<&[bool]>, working: &mut [u8],
+                        maxiters: usize, strategy: BfStrategy)
+        -> (bool, usize)
+    {
+        // For punctured codes, or when erasures are marked, we must first try and fix all the
+        // erased bits. We run them through an erasure decoding algorithm and record how many
+        // iterations it took (so we can return the total).
+        let erasure_iters = if self.punctured_bits() > 0 || erased.is_some() {
+            let known = |var: usize| !matches!(erased, Some(erased) if erased[var]);
+            let (_, iters) = self.decode_erasures_inner(known, output, working, maxiters);
             iters
         } else { 0 };
 
         // Working area: we use the top bit of the first k bytes to store that parity check,
         // and the remaining 7 bits of the first n+p bytes to store violation count for that var.
 
-        for iter in 0..maxiters {
+        // We run one extra pass at the end which only checks the parity, so that if the final
+        // set of flips produced a valid codeword we still report success.
+        for iter in 0..=maxiters {
             // Zero out violation counts
             for v in &mut working[..] { *v = 0 }
 
@@ -291,12 +1368,152 @@ impl LDPCCode {
 
             if max_violations == 0 {
                 return (true, iter + erasure_iters);
-            } else {
-                // Flip all the bits that have the maximum number of violations
-                for (var, violations) in working.iter().enumerate() {
-                    if *violations & 0x7F == max_violations {
+            } else if iter < maxiters {
+                // Flip the bits that have the maximum number of violations
+                let tied = |violations: &u8| *violations & 0x7F == max_violations;
+                match strategy {
+                    BfStrategy::FlipAll => {
+                        for (var, violations) in working.iter().enumerate() {
+                            if tied(violations) {
+                                output[var/8] ^= 1<<(7-(var%8));
+                            }
+                        }
+                    },
+                    BfStrategy::FlipLowest => if let Some(var) = working.iter().position(tied) {
                         output[var/8] ^= 1<<(7-(var%8));
-                    }
+                    },
+                    BfStrategy::FlipHighest => if let Some(var) = working.iter().rposition(tied) {
+                        output[var/8] ^= 1<<(7-(var%8));
+                    },
+                }
+            }
+        }
+
+        (false, maxiters + erasure_iters)
+    }
+
+    /// Gallager-B hard decision message passing decoder.
+    ///
+    /// Like `decode_bf` this only needs hard information, but rather than flipping whole bits
+    /// it passes a hard message along each edge of the parity check matrix. A variable sends
+    /// each check its received value, unless at least the current threshold of its *other*
+    /// checks disagree with that value, in which case it sends the opposite. Each check
+    /// replies to each variable with the value that would satisfy it given its other inputs.
+    /// After each iteration every bit is decided by a majority vote of its checks' messages,
+    /// keeping its received value in a tie.
+    ///
+    /// The threshold used in iteration `i` is `thresholds[i]`, or the final entry of
+    /// `thresholds` once `i` is past the end, so `&[4, 3]` starts with a threshold of 4 and
+    /// then uses 3 for all subsequent iterations. Since the codes have variables of several
+    /// different degrees, the threshold for each variable is limited to between 1 and one
+    /// less than its degree, so a large threshold means every other check must disagree.
+    /// Higher thresholds are more conservative; try 3 or 4 to begin with.
+    ///
+    /// On the TM codes this typically corrects more errors than `decode_bf` at a similar cost,
+    /// while on the TC codes `decode_bf` usually performs better.
+    ///
+    /// As for `decode_bf`, punctured bits are first recovered with the erasure decoder.
+    ///
+    /// * `input` must be `n/8` long, where each bit is the received hard information
+    /// * `output` must be `output_len()` bytes long and is written with the decoded codeword
+    /// * `working` must be `decode_gallager_b_working_len()` bytes long
+    /// * `thresholds` must not be empty
+    ///
+    /// Returns `(success, number of iterations run)`, counted as for `decode_bf`.
+    pub fn decode_gallager_b(self, input: &[u8], output: &mut [u8], working: &mut [u8],
+                             thresholds: &[u8], maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(input.len(), self.n()/8, "input.len() != n/8");
+        assert_eq!(output.len(), self.output_len(), "output.len != (n+p)/8");
+        assert_eq!(working.len(), self.decode_gallager_b_working_len(), "working.len() incorrect");
+        assert!(!thresholds.is_empty(), "thresholds must not be empty");
+
+        let n = self.n();
+        let p = self.punctured_bits();
+        output[..n/8].copy_from_slice(input);
+
+        let erasure_iters = if p > 0 {
+            let (_, iters) = self.decode_erasures_inner(|_| true, output, &mut working[..n+p],
+                                                        maxiters);
+            iters
+        } else { 0 };
+
+        // Working area: for each variable, one byte storing its received value in the top bit,
+        // its degree in bits 3 to 5, and the number of disagreeing check messages in bits 0 to
+        // 2 (no variable has degree above 7). Then one bit per edge holding the latest variable
+        // to check message, and one bit per check for the parity of its incoming messages.
+        let edge_bytes = (self.paritycheck_sum() as usize).div_ceil(8);
+        let (vars, working) = working.split_at_mut(n + p);
+        let (edges, parities) = working.split_at_mut(edge_bytes);
+
+        // Every variable starts by sending its received value
+        for (var, v) in vars.iter_mut().enumerate() {
+            *v = ((output[var/8] >> (7-(var%8))) & 1) << 7;
+        }
+        for e in &mut edges[..] { *e = 0 }
+        for (idx, (_, var)) in self.iter_paritychecks().enumerate() {
+            if vars[var] & 0x80 == 0x80 {
+                edges[idx/8] |= 1 << (idx%8);
+            }
+        }
+
+        for iter in 0..=maxiters {
+            // Check whether the current decisions satisfy every parity check
+            for x in &mut parities[..] { *x = 0 }
+            for (check, var) in self.iter_paritychecks() {
+                if output[var/8] >> (7-(var%8)) & 1 == 1 {
+                    parities[check/8] ^= 1 << (check%8);
+                }
+            }
+            if parities.iter().all(|x| *x == 0) {
+                return (true, iter + erasure_iters);
+            } else if iter == maxiters {
+                break;
+            }
+
+            // Find the parity of each check's incoming messages
+            for x in &mut parities[..] { *x = 0 }
+            for (idx, (check, _)) in self.iter_paritychecks().enumerate() {
+                if edges[idx/8] >> (idx%8) & 1 == 1 {
+                    parities[check/8] ^= 1 << (check%8);
+                }
+            }
+
+            // Count each variable's degree and how many check messages disagree with it
+            for v in &mut vars[..] { *v &= 0x80 }
+            for (idx, (check, var)) in self.iter_paritychecks().enumerate() {
+                let m_cv = (parities[check/8] >> (check%8) ^ edges[idx/8] >> (idx%8)) & 1;
+                vars[var] += 1 << 3;
+                if m_cv != vars[var] >> 7 {
+                    vars[var] += 1;
+                }
+            }
+
+            // Send new messages from each variable, excluding the destination check's opinion.
+            // A variable with only one check has no other opinions, so always sends its value.
+            let threshold = thresholds[iter.min(thresholds.len() - 1)];
+            for (idx, (check, var)) in self.iter_paritychecks().enumerate() {
+                let m_cv = (parities[check/8] >> (check%8) ^ edges[idx/8] >> (idx%8)) & 1;
+                let r = vars[var] >> 7;
+                let others = (vars[var] & 0x07) - if m_cv != r { 1 } else { 0 };
+                let degree = (vars[var] >> 3) & 0x07;
+                if (others >= threshold.min(degree - 1).max(1)) != (r == 1) {
+                    edges[idx/8] |= 1 << (idx%8);
+                } else {
+                    edges[idx/8] &= !(1 << (idx%8));
+                }
+            }
+
+            // Decide each bit by majority vote of its checks, keeping the received value on a tie
+            for (var, v) in vars.iter().enumerate() {
+                let disagree = v & 0x07;
+                let degree = (v >> 3) & 0x07;
+                let bit = (v >> 7) ^ if 2*disagree > degree { 1 } else { 0 };
+                if bit == 1 {
+                    output[var/8] |= 1 << (7-(var%8));
+                } else {
+                    output[var/8] &= !(1 << (7-(var%8)));
                 }
             }
         }
@@ -316,7 +1533,7 @@ impl LDPCCode {
     /// * `llrs` must be `n` long, with positive numbers more likely to be a 0 bit.
     /// * `output` must be allocated to (n+punctured_bits)/8 bytes, aka `output_len()`, of which
     ///   the first k/8 bytes will be set to the decoded message (and the rest to the parity bits
-    ///   of the complete codeword)
+    ///   of the complete codeword, followed by the decoder's estimate of any punctured bits)
     /// * `working` is the main working area which must be provided and must have
     ///   `decode_ms_working_len()` elements, equal to
     ///   2*paritycheck_sum + 3*n + 3*punctured_bits - 2*k
@@ -327,6 +1544,14 @@ impl LDPCCode {
     ///
     /// Returns decoding success and the number of iterations run for.
     ///
+    /// Before the first iteration, the hard decisions of the input LLRs are checked against
+    /// every parity check, and if they already form a codeword the decoder returns `(true, 0)`
+    /// at once without passing any messages, at the cost of a single syndrome evaluation. On
+    /// high-SNR links where most frames are error-free this greatly increases throughput. The
+    /// punctured bits of the TM codes have no input LLR to decide them from, so this fast path
+    /// only applies to the TC codes, and the TM codes may need one iteration of message
+    /// passing to recover them, returning `(true, 1)`.
+    ///
     /// ## Log Likelihood Ratios and choice of `T`
     ///
     /// The `llrs` input is a list of signed numbers, one per bit, where positive numbers mean
@@ -343,348 +1568,3634 @@ impl LDPCCode {
     /// for LLR inputs, so that several full-scale messages can be accumulated before saturation
     /// occurs. On floating point types this is less of a concern.
     ///
+    /// Every accumulated marginal and every variable-to-check message is bounded to
+    /// `-maxval()..=maxval()`. Integer types saturate there anyway, while for floating point
+    /// types the bound stops very large LLRs overflowing to infinity, where subtracting a
+    /// message would give NaN. The input LLRs are used exactly as given, so the headroom is up
+    /// to you: hard information at the full scale of an integer `T` saturates the marginals
+    /// from the first iteration and rarely corrects more than a few errors, while the same
+    /// information at half scale decodes normally.
+    ///
     /// This also means if you only have hard information it makes no practical difference what
     /// exact value you give the LLRs, but in the interests of avoiding saturation you may as
     /// well pick +-1 in any unit (and you may as well use i8 since the additional range will
     /// not be of benefit).
-    #[allow(clippy::cognitive_complexity,clippy::many_single_char_names)]
+    ///
+    /// ## Fixed-point operation
+    ///
+    /// With `T=i8` (or `i16`, `i32`) the decoder uses only integer arithmetic, so it runs well
+    /// on targets without an FPU such as a Cortex-M0. The working area is then sized in bytes
+    /// (`decode_ms_working_len()` elements of one byte each). A convenient interpretation for
+    /// `i8` is Q4.4, i.e. an LLR of 1.0 is represented as 16, giving a range of roughly +-8,
+    /// which is ample for the soft information of most receivers.
+    ///
+    /// All accumulation saturates symmetrically to `-T::MAX..=T::MAX`; for `i8` this means
+    /// -127 to +127. An input LLR of `T::MIN` (e.g. -128) is accepted and treated as -127 once
+    /// messages are accumulated. Full-scale inputs are accepted, but a saturated marginal no
+    /// longer gives the exact message to each check, so leaving some headroom as described
+    /// above gives much better decoding performance.
+    ///
+    /// ## Non-finite inputs
+    ///
+    /// With floating point `T`, every LLR must be finite. A NaN or infinite LLR, for example
+    /// from dividing by a zero noise estimate, propagates through the messages and decoding
+    /// fails with an unspecified output. Use `try_decode_ms` or `MsDecoder` to have such
+    /// inputs rejected with `DecodeError::NonFiniteInput` instead.
     pub fn decode_ms<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
                                     working: &mut [T], working_u8: &mut [u8],
                                     maxiters: usize)
         -> (bool, usize)
     {
-        let n = self.n();
-        let k = self.k();
-        let p = self.punctured_bits();
-
-        assert_eq!(llrs.len(), n, "llrs.len() != n");
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
         assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
         assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
         assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
 
-        // Rename output to parities as we'll use it to keep track of the parity bits until the end
-        let parities = output;
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output, working,
+                                 working_u8, maxiters, MsCorrection::None,
+                                 |_| true, false, |_, _| ())
+    }
 
-        // Rename working_u8 to ui_sgns, we'll use it to accumulate signs for each check
-        let ui_sgns = working_u8;
+    /// Normalised min-sum decoder.
+    ///
+    /// Identical to `decode_ms`, except the magnitude of every check-to-variable message is
+    /// multiplied by `alpha` before being sent. Plain min-sum overestimates these magnitudes,
+    /// and scaling them down closes much of the gap to full sum-product decoding at almost
+    /// no extra cost. Typical values of `alpha` are between 0.75 and 0.875; an `alpha` of 1.0
+    /// gives exactly the same result as `decode_ms`.
+    ///
+    /// For integer `T` the scaled magnitude is rounded towards zero.
+    ///
+    /// `alpha` must be in (0, 1]. All buffers are as for `decode_ms`.
+    pub fn decode_ms_normalized<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                               working: &mut [T], working_u8: &mut [u8],
+                                               maxiters: usize, alpha: f32)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+        assert!(alpha > 0.0 && alpha <= 1.0, "alpha must be in (0, 1]");
 
-        // Zero the working area and split it up
-        for w in &mut working[..] { *w = T::zero() }
-        let (u, working)        = working.split_at_mut(self.paritycheck_sum() as usize);
-        let (v, working)        = working.split_at_mut(self.paritycheck_sum() as usize);
-        let (va, working)       = working.split_at_mut(n + p);
-        let (ui_min1, ui_min2)  = working.split_at_mut(n + p - k);
+        let correction = if alpha == 1.0 {
+            MsCorrection::None
+        } else {
+            MsCorrection::Normalized(alpha)
+        };
 
-        for iter in 0..maxiters {
-            // Initialise the marginals to the input LLRs (and to 0 for punctured bits)
-            va[..llrs.len()].copy_from_slice(llrs);
-            for x in &mut va[llrs.len()..] { *x = T::zero() }
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output, working,
+                                 working_u8, maxiters, correction, |_| true, false, |_, _| ())
+    }
 
-            // You'd think .enumerate() would be sensible, but actually it prevents
-            // inlining the iterator's next() method, which leads to a big performance hit.
-            let mut idx = 0;
-            for (check, var) in self.iter_paritychecks() {
-                // Work out messages to this variable
-                if v[idx].abs() == ui_min1[check] {
-                    u[idx] = ui_min2[check];
-                } else {
-                    u[idx] = ui_min1[check];
-                }
-                if ui_sgns[check/8] >> (check%8) & 1 == 1 {
-                    u[idx] = -u[idx];
-                }
-                if v[idx] < T::zero() {
-                    u[idx] = -u[idx];
-                }
+    /// Offset min-sum decoder.
+    ///
+    /// Identical to `decode_ms`, except `beta` is subtracted from the magnitude of every
+    /// check-to-variable message before it is sent, saturating at zero so a message never
+    /// changes sign. Like `decode_ms_normalized` this corrects the overestimated message
+    /// magnitudes of plain min-sum, but it requires only a subtraction and so suits integer
+    /// `T` particularly well. A `beta` of zero gives exactly the same result as `decode_ms`.
+    ///
+    /// `beta` is in the same units as your LLRs and must not be negative. As a starting point,
+    /// try `beta` around 0.15 times a typical input LLR magnitude for the TC codes, rising
+    /// towards 0.3 times for the higher-rate TM codes whose checks each have more inputs.
+    /// The best value depends on your channel and is best found by simulation.
+    ///
+    /// All buffers are as for `decode_ms`.
+    pub fn decode_ms_offset<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                           working: &mut [T], working_u8: &mut [u8],
+                                           maxiters: usize, beta: T)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+        assert!(beta >= T::zero(), "beta must not be negative");
 
-                // Accumulate incoming messages to each variable
-                va[var] = va[var].saturating_add(u[idx]);
+        let correction = MsCorrection::Offset(beta);
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output, working,
+                                 working_u8, maxiters, correction, |_| true, false, |_, _| ())
+    }
 
-                // DIY enumerate
-                idx += 1;
-            }
+    /// Damped min-sum decoder.
+    ///
+    /// Identical to `decode_ms`, except each new check-to-variable message is blended with the
+    /// previous message on the same edge, sending `(1 - lambda) * new + lambda * previous`.
+    /// This damps the oscillations which sometimes stop min-sum converging, particularly on the
+    /// TM codes, at the cost of slower progress per iteration. `lambda` must be in [0, 1);
+    /// values around 0.2 to 0.3 are a good starting point, and a `lambda` of zero gives exactly
+    /// the same result as `decode_ms`.
+    ///
+    /// The previous messages are already kept in the working area between iterations, so no
+    /// extra memory is needed. For integer `T` each scaled term is rounded towards zero.
+    ///
+    /// All buffers are as for `decode_ms`.
+    pub fn decode_ms_damped<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                           working: &mut [T], working_u8: &mut [u8],
+                                           maxiters: usize, lambda: f32)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+        assert!((0.0..1.0).contains(&lambda), "lambda must be in [0, 1)");
 
-            for x in &mut ui_min1[..] { *x = T::maxval() }
-            for x in &mut ui_min2[..] { *x = T::maxval() }
-            for x in &mut ui_sgns[..] { *x = 0 }
-            for x in &mut parities[..] { *x = 0 }
-            idx = 0;
-            for (check, var) in self.iter_paritychecks() {
-                // Work out messages to this parity check
-                let new_v_ai = va[var] - u[idx];
-                if v[idx] != T::zero() && (new_v_ai >= T::zero()) != (v[idx] >= T::zero()) {
-                    v[idx] = T::zero();
-                } else {
-                    v[idx] = new_v_ai;
-                }
+        let correction = if lambda == 0.0 {
+            MsCorrection::None
+        } else {
+            MsCorrection::Damped(lambda)
+        };
 
-                // Accumulate two minimums
-                if v[idx].abs() < ui_min1[check] {
-                    ui_min2[check] = ui_min1[check];
-                    ui_min1[check] = v[idx].abs();
-                } else if v[idx].abs() < ui_min2[check] {
-                    ui_min2[check] = v[idx].abs();
-                }
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output, working,
+                                 working_u8, maxiters, correction, |_| true, false, |_, _| ())
+    }
 
-                // Accumulate signs
-                if v[idx] < T::zero() {
-                    ui_sgns[check/8] ^= 1<<(check%8);
-                }
+    /// Min-sum decoder with an additional check on the decoded data, such as a CRC.
+    ///
+    /// Identical to `decode_ms`, except that whenever the decoder finds a codeword satisfying
+    /// every parity check, `data_check` is called with its first k/8 bytes (the decoded data).
+    /// If it returns false the decoder keeps iterating, in case it can still move to the
+    /// correct codeword, and reports failure if no accepted codeword is found within
+    /// `maxiters` iterations. This catches the occasional convergence to a valid but wrong
+    /// codeword, which otherwise goes undetected.
+    ///
+    /// All buffers are as for `decode_ms`.
+    pub fn decode_ms_crc<T, F>(self, llrs: &[T], output: &mut [u8], working: &mut [T],
+                               working_u8: &mut [u8], maxiters: usize, data_check: F)
+        -> (bool, usize)
+        where T: DecodeFrom, F: Fn(&[u8]) -> bool
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
 
-                // Accumulate parity
-                if va[var] <= T::zero() {
-                    parities[check/8] ^= 1<<(check%8);
-                }
+        let k = self.k();
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output, working,
+                                 working_u8, maxiters, MsCorrection::None,
+                                 |codeword| data_check(&codeword[..k/8]), false, |_, _| ())
+    }
 
-                idx += 1;
+    /// Clamped min-sum decoder.
+    ///
+    /// Identical to `decode_ms`, except that the magnitude of every input LLR, every
+    /// check-to-variable message, and every accumulated marginal is limited to `llr_max`.
+    /// This keeps the decoder well behaved when given badly scaled LLRs, and models the
+    /// limited range of a fixed-point implementation. An `llr_max` larger than any value
+    /// reached while decoding gives exactly the same result as `decode_ms`.
+    ///
+    /// `llr_max` is in the same units as your LLRs and must be positive.
+    ///
+    /// All buffers are as for `decode_ms`.
+    pub fn decode_ms_clamped<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                            working: &mut [T], working_u8: &mut [u8],
+                                            maxiters: usize, llr_max: T)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+        assert!(llr_max > T::zero(), "llr_max must be positive");
+
+        let init_llrs = |va: &mut [T]| {
+            for (x, &llr) in va.iter_mut().zip(llrs.iter()) {
+                *x = if llr > llr_max { llr_max } else if llr < -llr_max { -llr_max } else { llr };
             }
+        };
+        self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output, working,
+                                 working_u8, maxiters, MsCorrection::Clamp(llr_max),
+                                 |_| true, false, |_, _| ())
+    }
 
-            // Check parities. If none are 1 then we have a valid codeword.
-            if *parities.iter().max().unwrap() == 0 {
-                // Hard decode marginals into the output
-                let output = parities;
-                for o in &mut output[..] { *o = 0 }
-                for (var, &va) in va[0..(n+p)].iter().enumerate() {
-                    if va <= T::zero() {
-                        output[var/8] |= 1 << (7 - (var%8));
+    /// Min-sum decoder using only a subset of the parity checks.
+    ///
+    /// Identical to `decode_ms`, except that parity checks whose entry in `check_enabled` is
+    /// false are ignored entirely: they send no messages to their variables and are not
+    /// included in the test for a valid codeword. This is useful for experimenting with
+    /// degraded or rate-adaptive schemes where some checks are unavailable. With every check
+    /// enabled the result is exactly the same as `decode_ms`.
+    ///
+    /// Note that success only means every enabled check is satisfied, so with fewer checks
+    /// enabled the decoder is more likely to report success on an incorrect codeword.
+    ///
+    /// `check_enabled` must be n+p-k long, one entry per parity check. All other buffers are as
+    /// for `decode_ms`.
+    pub fn decode_ms_masked<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                           working: &mut [T], working_u8: &mut [u8],
+                                           check_enabled: &[bool], maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+        assert_eq!(check_enabled.len(), self.n() + self.punctured_bits() - self.k(),
+                   "check_enabled.len() != n+p-k");
+
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        let edges = || self.iter_paritychecks().filter(move |&(check, _)| check_enabled[check]);
+        self.decode_ms_unchecked(edges, init_llrs, output, working,
+                                 working_u8, maxiters, MsCorrection::None,
+                                 |_| true, false, |_, _| ())
+    }
+
+    /// Min-sum decoder for a shortened codeword, as produced by `encode_shortened`.
+    ///
+    /// `llrs` holds the LLRs of the `info_bits` message bits followed by the n-k parity bits.
+    /// The untransmitted data bits are known to be zero, so they are given the largest LLR
+    /// which `T` can safely hold, making the shortened code considerably stronger than the
+    /// full code. Messages from the parity checks can still push them towards one, so decoding
+    /// only stops at a codeword whose padding bits are all zero, and the padding bits are set
+    /// to zero in `output` even when decoding fails.
+    ///
+    /// * `llrs` must be n - k + info_bits long
+    /// * `info_bits` must be at most k
+    /// * `output` receives the full decoded codeword including the zero padding, so the
+    ///   message is in its first `info_bits` bits
+    ///
+    /// All other buffers and the return value are as for `decode_ms`.
+    pub fn decode_ms_shortened<T: DecodeFrom>(self, llrs: &[T], info_bits: usize,
+                                              output: &mut [u8], working: &mut [T],
+                                              working_u8: &mut [u8], maxiters: usize)
+        -> (bool, usize)
+    {
+        assert!(info_bits <= self.k(), "info_bits > k");
+        assert_eq!(llrs.len(), self.n() - self.k() + info_bits, "llrs.len() != n-k+info_bits");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+
+        // Integer types saturate so can use their maximum value directly, but floats need
+        // headroom so that adding up to seven messages cannot overflow to infinity.
+        let known = if T::maxval().saturating_add(T::maxval()) == T::maxval() {
+            T::maxval()
+        } else {
+            T::maxval().scale(1.0 / 16.0)
+        };
+
+        let k = self.k();
+        let init_llrs = |va: &mut [T]| {
+            va[..info_bits].copy_from_slice(&llrs[..info_bits]);
+            for x in &mut va[info_bits..k] { *x = known }
+            va[k..].copy_from_slice(&llrs[info_bits..]);
+        };
+        let padding_zero = |codeword: &[u8]| {
+            (info_bits..k).all(|bit| (codeword[bit/8] >> (7 - (bit%8))) & 1 == 0)
+        };
+        let result = self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output,
+                                              working, working_u8, maxiters,
+                                              MsCorrection::None, padding_zero, false,
+                                              |_, _| ());
+        for bit in info_bits..k {
+            output[bit/8] &= !(1 << (7 - (bit%8)));
+        }
+        result
+    }
+
+    /// Min-sum decoder for fixed-point `i16` LLRs, decoded in `f32`.
+    ///
+    /// Many demodulators output soft decisions as integers with some number of fractional
+    /// bits. This converts each LLR to `f32` as `llr / 2^frac_bits` as it is loaded, and is
+    /// otherwise identical to running `decode_ms` with `f32` LLRs, without needing a separate
+    /// buffer for the converted values.
+    ///
+    /// The min-sum decoder is invariant to scaling, so `frac_bits` does not change the result,
+    /// but it keeps the working area in the same units as your demodulator. To decode entirely
+    /// in integer arithmetic instead, pass the `i16` LLRs straight to `decode_ms`.
+    ///
+    /// * `llrs` must be n long
+    /// * `frac_bits` must be less than 16
+    /// * `working` must be `decode_ms_working_len()` long
+    ///
+    /// All other buffers and the return value are as for `decode_ms`.
+    pub fn decode_ms_q(self, llrs: &[i16], frac_bits: u8, output: &mut [u8],
+                       working: &mut [f32], working_u8: &mut [u8], maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert!(frac_bits < 16, "frac_bits must be less than 16");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+
+        let scale = 1.0 / f32::from(1u16 << frac_bits);
+        let init_llrs = |va: &mut [f32]| {
+            for (x, &llr) in va.iter_mut().zip(llrs.iter()) {
+                *x = f32::from(llr) * scale;
+            }
+        };
+        self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output, working,
+                                 working_u8, maxiters, MsCorrection::None,
+                                 |_| true, false, |_, _| ())
+    }
+
+    /// Resume a previous min-sum decode with updated LLRs.
+    ///
+    /// This is for incremental redundancy (HARQ) schemes, where a first decode attempt is made
+    /// with some bits punctured (given an LLR of zero), and on failure those bits are sent
+    /// separately. Instead of starting again from scratch, update `llrs` with the newly
+    /// received values and call this method: it continues from the messages left in `working`
+    /// and `working_u8` by the previous attempt, so the work already done is not discarded.
+    ///
+    /// `working` and `working_u8` must not have been modified since a previous call to
+    /// `decode_ms` or `decode_ms_resume` for this code, and the corrections of
+    /// `decode_ms_normalized` and `decode_ms_offset` are not applied.
+    ///
+    /// All buffers are as for `decode_ms`.
+    pub fn decode_ms_resume<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                           working: &mut [T], working_u8: &mut [u8],
+                                           maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output, working,
+                                 working_u8, maxiters, MsCorrection::None,
+                                 |_| true, true, |_, _| ())
+    }
+
+    /// Min-sum decoder which reports its progress after every iteration, for debugging.
+    ///
+    /// Identical to `decode_ms`, except `on_iter(iter, unsatisfied)` is called after each
+    /// iteration with the iteration number, starting from 0, and the number of parity checks
+    /// not satisfied by the current hard decisions. The trajectory of this syndrome weight
+    /// shows whether a failing decode is oscillating or stuck, which helps when choosing
+    /// between the corrected variants such as `decode_ms_normalized` and `decode_ms_damped`.
+    ///
+    /// All buffers and the return value are as for `decode_ms`.
+    pub fn decode_ms_debug<T, F>(self, llrs: &[T], output: &mut [u8], working: &mut [T],
+                                 working_u8: &mut [u8], maxiters: usize, on_iter: F)
+        -> (bool, usize)
+        where T: DecodeFrom, F: FnMut(usize, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output, working,
+                                 working_u8, maxiters, MsCorrection::None, |_| true, false,
+                                 on_iter)
+    }
+
+    /// Min-sum decoder with the given output bit packing.
+    ///
+    /// Identical to `decode_ms`, except `output` is written in `order`. The LLRs are one per
+    /// bit in transmission order, so are unaffected. `BitOrder::MsbFirst` behaves exactly as
+    /// `decode_ms`.
+    pub fn decode_ms_ordered<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                            working: &mut [T], working_u8: &mut [u8],
+                                            maxiters: usize, order: BitOrder)
+        -> (bool, usize)
+    {
+        let result = self.decode_ms(llrs, output, working, working_u8, maxiters);
+        order.reorder(output);
+        result
+    }
+
+    /// Min-sum decoder which also outputs the final a-posteriori LLRs.
+    ///
+    /// Identical to `decode_ms`, but additionally writes the final marginal LLR of each
+    /// codeword bit (the channel LLR plus all incoming check messages) to `app_llrs`. This is
+    /// useful when the decoder is part of a larger iterative receiver.
+    ///
+    /// `app_llrs` must be either n long, for the transmitted bits only, or n + punctured_bits
+    /// long, in which case it is followed by the decoder's estimates for the punctured bits,
+    /// which were never transmitted. These can be forwarded by a relay for a later node to
+    /// combine, for example.
+    ///
+    /// The hard output is always consistent with `app_llrs`: a bit is 1 in `output` exactly
+    /// when its APP LLR is less than or equal to zero.
+    pub fn decode_ms_soft<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8], app_llrs: &mut [T],
+                                         working: &mut [T], working_u8: &mut [u8],
+                                         maxiters: usize)
+        -> (bool, usize)
+    {
+        assert!(app_llrs.len() == self.n() || app_llrs.len() == self.n() + self.punctured_bits(),
+                "app_llrs.len() != n or n + punctured_bits");
+        let result = self.decode_ms(llrs, output, working, working_u8, maxiters);
+
+        let (_, marginals) = self.ms_results(working);
+        app_llrs.copy_from_slice(&marginals[..app_llrs.len()]);
+        result
+    }
+
+    /// Min-sum decoder taking a-priori LLRs and returning extrinsic LLRs.
+    ///
+    /// For iterative receivers which alternate between a detector (such as an equaliser or
+    /// demapper) and the decoder. On entry, `priors` holds the a-priori LLR of each codeword
+    /// bit from the detector, which is added to the channel LLR in `llrs` before decoding. On
+    /// return it holds the extrinsic LLR of each bit, the sum of the final messages from its
+    /// parity checks, which is the a-posteriori LLR less both the a-priori and channel LLRs.
+    /// This is what should be fed back to the detector to avoid positive feedback. It is
+    /// summed directly from the messages, so it stays correct even when the a-posteriori LLR
+    /// has saturated. Pass all zeros for the first iteration.
+    ///
+    /// `priors` must be n long. All other buffers and the return value are as for `decode_ms`,
+    /// and the hard output is the decision on the a-posteriori LLRs, as for `decode_ms_soft`.
+    pub fn decode_ms_apriori<T: DecodeFrom>(self, llrs: &[T], priors: &mut [T],
+                                            output: &mut [u8], working: &mut [T],
+                                            working_u8: &mut [u8], maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(priors.len(), self.n(), "priors.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+
+        let init_llrs = |va: &mut [T]| {
+            for ((v, llr), prior) in va.iter_mut().zip(llrs).zip(priors.iter()) {
+                *v = llr.saturating_add(*prior);
+            }
+        };
+        let result = self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output,
+                                              working, working_u8, maxiters,
+                                              MsCorrection::None, |_| true, false, |_, _| ());
+
+        // Sum the final check-to-variable messages to each bit
+        let (messages, _) = self.ms_results(working);
+        for prior in &mut priors[..] { *prior = T::zero() }
+        for (&u, (_, var)) in messages.iter().zip(self.iter_paritychecks()) {
+            if var < self.n() {
+                priors[var] = priors[var].saturating_add(u);
+            }
+        }
+        result
+    }
+
+    /// Min-sum decoder which also classifies its confidence in the result.
+    ///
+    /// Identical to `decode_ms`, but instead of only reporting success, returns a
+    /// `Confidence` for link control such as a hybrid ARQ scheme, which might request a
+    /// retransmission early on a `Confidence::Marginal` result:
+    ///
+    /// * `Confidence::Failed` if decoding did not converge
+    /// * `Confidence::Marginal` if it converged, but took more than `thresholds.max_iters`
+    ///   iterations, or the smallest magnitude of the final a-posteriori LLR of any codeword bit
+    ///   (as written by `decode_ms_soft`) is less than `thresholds.min_llr`
+    /// * `Confidence::High` otherwise
+    ///
+    /// Suitable thresholds depend on the code, the LLR scaling and the channel, so should be
+    /// chosen by simulation. All buffers are as for `decode_ms`.
+    ///
+    /// Returns the confidence and the number of iterations run.
+    pub fn decode_ms_confidence<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                               working: &mut [T], working_u8: &mut [u8],
+                                               maxiters: usize,
+                                               thresholds: ConfidenceThresholds<T>)
+        -> (Confidence, usize)
+    {
+        let (success, iters) = self.decode_ms(llrs, output, working, working_u8, maxiters);
+        if !success {
+            return (Confidence::Failed, iters);
+        }
+
+        let (_, marginals) = self.ms_results(working);
+        let app_llrs = &marginals[..self.n()];
+        let marginal = iters > thresholds.max_iters
+                       || app_llrs.iter().any(|llr| llr.abs() < thresholds.min_llr);
+        if marginal {
+            (Confidence::Marginal, iters)
+        } else {
+            (Confidence::High, iters)
+        }
+    }
+
+    /// Min-sum decoder for hard information.
+    ///
+    /// Runs `decode_ms` directly on hard received bits, treating each bit as an LLR of magnitude
+    /// `confidence` (negative for 1 bits, positive for 0 bits), exactly as if `input` had first
+    /// been converted with `hard_to_llrs_scaled`, but without needing an n-long buffer of LLRs.
+    ///
+    /// * `input` is the received codeword, n/8 bytes long, packed as for `decode_bf`
+    /// * `confidence` must be positive; as the decoder is invariant to LLR scaling this only
+    ///   affects how much headroom remains in `T` before saturation, so 1 is a good choice
+    ///
+    /// All other buffers and the return value are as for `decode_ms`. This typically corrects
+    /// more errors than `decode_bf`, at the cost of the larger working area.
+    pub fn decode_ms_hard<T: DecodeFrom>(self, input: &[u8], confidence: T, output: &mut [u8],
+                                         working: &mut [T], working_u8: &mut [u8],
+                                         maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(input.len(), self.n()/8, "input.len() != n/8");
+        assert!(confidence > T::zero(), "confidence must be positive");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+
+        let init_llrs = |va: &mut [T]| {
+            for (idx, llr) in va.iter_mut().enumerate() {
+                let bit = (input[idx/8] >> (7-(idx%8))) & 1;
+                *llr = if bit == 1 { -confidence } else { confidence };
+            }
+        };
+        self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output, working,
+                                 working_u8, maxiters, MsCorrection::None,
+                                 |_| true, false, |_, _| ())
+    }
+
+    /// Get the length of [u16] required for the lookup table used by `decode_ms_lookup`.
+    ///
+    /// Equal to 2 * paritycheck_sum.
+    pub const fn ms_lookup_len(self) -> usize {
+        2 * self.paritycheck_sum() as usize
+    }
+
+    /// Initialise a lookup table for `decode_ms_lookup`.
+    ///
+    /// Every parity check edge is written to `table` as a pair of `(check, variable)` entries,
+    /// in the same order as `iter_paritychecks()`.
+    ///
+    /// The table depends only on the code, so it may be computed once, stored (for example in
+    /// flash or on disk), and reloaded later instead of being recomputed at startup. It is a
+    /// plain array of integers, so no special serialisation support is required. The generator
+    /// matrices are compiled-in constants and never need expanding. Store `fingerprint()` with
+    /// a persisted table and check it on loading, so a table for a different code is rejected.
+    ///
+    /// `table` must be `ms_lookup_len()` long.
+    pub fn init_ms_lookup(self, table: &mut [u16]) {
+        assert_eq!(table.len(), self.ms_lookup_len(), "table.len() incorrect");
+        for (entry, (check, var)) in table.chunks_mut(2).zip(self.iter_paritychecks()) {
+            entry[0] = check as u16;
+            entry[1] = var as u16;
+        }
+    }
+
+    /// Initialise part of a lookup table for `decode_ms_lookup`, one block row of checks at a
+    /// time.
+    ///
+    /// For the larger codes `init_ms_lookup` walks several tens of thousands of edges in one
+    /// call. Systems that cannot block for that long at startup may instead call this method
+    /// repeatedly, for example in idle slots, to spread the work out. Each call writes the
+    /// edges for the next block row of `submatrix_size()` parity checks.
+    ///
+    /// `pos` is the number of entries of `table` already filled, which must be 0 on the first
+    /// call, and each call returns the new value to pass to the next one. Once the returned
+    /// value equals `table.len()` the table is complete and identical to that written by
+    /// `init_ms_lookup`; further calls do nothing. A partly filled table must not be passed to
+    /// `decode_ms_lookup`.
+    ///
+    /// `table` must be `ms_lookup_len()` long.
+    pub fn init_ms_lookup_step(self, table: &mut [u16], pos: usize) -> usize {
+        assert_eq!(table.len(), self.ms_lookup_len(), "table.len() incorrect");
+        assert!(pos <= table.len() && pos % 2 == 0, "pos incorrect");
+        if pos == table.len() {
+            return pos;
+        }
+
+        // Resume from the block row after the last check written
+        let row = match pos {
+            0 => 0,
+            _ => table[pos - 2] as usize / self.submatrix_size() + 1,
+        };
+        let mut pos = pos;
+        for (check, var) in self.iter_paritychecks_row(row) {
+            table[pos] = check as u16;
+            table[pos + 1] = var as u16;
+            pos += 2;
+        }
+        pos
+    }
+
+    /// Min-sum decoder using a precomputed lookup table of parity check edges.
+    ///
+    /// Identical to `decode_ms`, except that instead of computing each parity check edge from
+    /// the compact constants on every pass, the edges are read from `table`, which must have
+    /// been initialised by `init_ms_lookup()` for this code. This trades `4*paritycheck_sum`
+    /// bytes of RAM for less computation in the decoder's inner loops, and gives identical
+    /// results to `decode_ms`.
+    ///
+    /// All other buffers are as for `decode_ms`.
+    pub fn decode_ms_lookup<T: DecodeFrom>(self, table: &[u16], llrs: &[T], output: &mut [u8],
+                                           working: &mut [T], working_u8: &mut [u8],
+                                           maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(table.len(), self.ms_lookup_len(), "table.len() incorrect");
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+
+        let edges = || table.chunks_exact(2).map(|e| (e[0] as usize, e[1] as usize));
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        self.decode_ms_unchecked(edges, init_llrs, output, working, working_u8,
+                                 maxiters, MsCorrection::None, |_| true, false, |_, _| ())
+    }
+
+    /// Reference min-sum decoder, for cross-checking the optimised decoders.
+    ///
+    /// This runs exactly the algorithm of `decode_ms`, written as directly as possible from
+    /// its definition and deliberately never optimised: each check-to-variable message is
+    /// found by searching the parity check edges for the other variables of the same check,
+    /// taking the smallest magnitude and the product of the signs of their messages. It is
+    /// therefore very much slower than `decode_ms`, especially for the larger codes, and is
+    /// not intended for normal use. Instead it serves as a trusted baseline: for the same
+    /// inputs `decode_ms` and `decode_ms_lookup` must give bit-for-bit identical results, so
+    /// any difference indicates a bug in one of the faster paths. `decode_ms_layered` uses a
+    /// different schedule so will not in general match it.
+    ///
+    /// The arguments and return value are exactly as for `decode_ms`, and the same buffers
+    /// may be used. Only the first `2*paritycheck_sum + n + punctured_bits` elements of
+    /// `working` are used, while `working_u8` holds the syndrome of each hard decision.
+    pub fn decode_ms_reference<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                              working: &mut [T], working_u8: &mut [u8],
+                                              maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+
+        let n = self.n();
+        let p = self.punctured_bits();
+        let rows = (n + p - self.k()) / self.submatrix_size();
+
+        // u holds the check-to-variable and v the variable-to-check message for each edge,
+        // in the order given by iter_paritychecks(), and va holds the marginal of each bit.
+        for w in &mut working[..] { *w = T::zero() }
+        let (u, working)  = working.split_at_mut(self.paritycheck_sum() as usize);
+        let (v, working)  = working.split_at_mut(self.paritycheck_sum() as usize);
+        let va = &mut working[..n + p];
+
+        for iter in 0..maxiters {
+            // Each marginal starts from its input LLR, or 0 for punctured bits
+            for (var, x) in va.iter_mut().enumerate() {
+                *x = if var < n { llrs[var] } else { T::zero() };
+            }
+
+            // Message from each check to each of its variables, from every other variable on
+            // that check. All of a check's edges lie in the same block row.
+            let mut start = 0;
+            for row in 0..rows {
+                let mut len = 0;
+                for (i, (check, var)) in self.iter_paritychecks_row(row).enumerate() {
+                    let mut mag = T::maxval();
+                    let mut neg = false;
+                    for (j, (other, _)) in self.iter_paritychecks_row(row).enumerate() {
+                        if other == check && j != i {
+                            let x = v[start + j];
+                            if x.abs() < mag { mag = x.abs() }
+                            if x < T::zero() { neg = !neg }
+                        }
                     }
+                    u[start + i] = if neg { -mag } else { mag };
+                    va[var] = va[var].saturating_add(u[start + i]);
+                    len += 1;
+                }
+                start += len;
+            }
+            // Bound the marginals
+            for x in va.iter_mut() {
+                if *x > T::maxval() {
+                    *x = T::maxval();
+                } else if *x < -T::maxval() {
+                    *x = -T::maxval();
                 }
+            }
+
+            // Message from each variable to each of its checks, from every other check on
+            // that variable and bounded like the marginals, erased to 0 if its sign has changed
+            // since the last iteration
+            for (idx, (_, var)) in self.iter_paritychecks().enumerate() {
+                let mut x = va[var].saturating_sub(u[idx]);
+                if x > T::maxval() { x = T::maxval() } else if x < -T::maxval() { x = -T::maxval() }
+                let flipped = (x >= T::zero()) != (v[idx] >= T::zero());
+                v[idx] = if v[idx] != T::zero() && flipped { T::zero() } else { x };
+            }
+
+            // Hard decide every marginal and stop once all the parity checks are satisfied
+            for o in &mut output[..] { *o = 0 }
+            for (var, &x) in va.iter().enumerate() {
+                if x <= T::zero() {
+                    output[var/8] |= 1 << (7 - (var%8));
+                }
+            }
+            if self.compute_syndrome(output, working_u8) {
                 return (true, iter);
             }
         }
 
-        // If we failed to find a codeword, at least hard decode the marginals into the output
-        let output = parities;
-        for o in &mut output[..] { *o = 0 }
-        for (var, &va) in va[0..(n+p)].iter().enumerate() {
-            if va <= T::zero() {
-                output[var/8] |= 1 << (7 - (var%8));
-            }
+        (false, maxiters)
+    }
+
+    /// Weighted bit flipping decoder.
+    ///
+    /// This is the weighted bit flipping algorithm of Kou, Lin, and Fossorier, which uses a
+    /// coarse per-bit reliability alongside the hard information. It typically corrects more
+    /// errors than `decode_bf` while needing far less memory and computation than `decode_ms`.
+    ///
+    /// Each parity check is weighted by the smallest reliability of the received bits it
+    /// involves. Every iteration, each bit sums the weights of its failing checks minus the
+    /// weights of its satisfied checks, and the single bit with the largest sum is flipped.
+    /// As only one bit is flipped per iteration, `maxiters` should be at least the number of
+    /// bit errors you hope to correct.
+    ///
+    /// * `input` must be the received hard information, n/8 bytes long, as for `decode_bf`
+    /// * `reliability` must be n long, giving the reliability of each received bit, where
+    ///   larger values mean more confidence (for example the magnitude of a soft sample)
+    /// * `output` must be allocated to (n+punctured_bits)/8 bytes, aka `output_len()`
+    /// * `working` must be allocated to `decode_wbf_working_len()` bytes, equal to
+    ///   n+punctured_bits
+    /// * `working_i16` must be allocated to `decode_wbf_working_i16_len()` elements, equal to
+    ///   2*n + 2*punctured_bits - k
+    ///
+    /// As with `decode_bf`, punctured codes first have their punctured bits recovered by
+    /// erasure decoding. Punctured bits have no reliability so do not affect the check weights.
+    ///
+    /// Returns `(decoding success, iters)` with the same meaning as `decode_bf`.
+    pub fn decode_wbf(self, input: &[u8], reliability: &[u8], output: &mut [u8],
+                      working: &mut [u8], working_i16: &mut [i16], maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(input.len(), self.n()/8, "input.len() != n/8");
+        assert_eq!(reliability.len(), self.n(), "reliability.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len != (n+p)/8");
+        assert_eq!(working.len(), self.decode_wbf_working_len(), "working.len() incorrect");
+        assert_eq!(working_i16.len(), self.decode_wbf_working_i16_len(),
+                   "working_i16.len() incorrect");
+
+        let n = self.n();
+        let k = self.k();
+        let p = self.punctured_bits();
+
+        output[..n/8].copy_from_slice(input);
+
+        // Recover punctured bits first, exactly as decode_bf does.
+        let erasure_iters = if p > 0 {
+            let (_, iters) = self.decode_erasures_inner(|_| true, output, working, maxiters);
+            iters
+        } else { 0 };
+
+        // Working areas:
+        // * `weights` holds the weight of each check, the minimum reliability of its
+        //   non-punctured variables
+        // * `flips` holds the flipping metric for each variable
+        // * `checks` holds the parity of each check, and then its signed weight
+        let weights = &mut working[..n+p-k];
+        let (flips, checks) = working_i16.split_at_mut(n + p);
+
+        for w in &mut weights[..] { *w = u8::MAX }
+        for (check, var) in self.iter_paritychecks() {
+            if var < n && reliability[var] < weights[check] {
+                weights[check] = reliability[var];
+            }
+        }
+
+        // As in decode_bf, the final pass only checks the parity.
+        for iter in 0..=maxiters {
+            // Calculate the parity of each parity check
+            for c in &mut checks[..] { *c = 0 }
+            for (check, var) in self.iter_paritychecks() {
+                if output[var/8] >> (7-(var%8)) & 1 == 1 {
+                    checks[check] ^= 1;
+                }
+            }
+
+            if checks.iter().all(|c| *c == 0) {
+                return (true, iter + erasure_iters);
+            } else if iter == maxiters {
+                break;
+            }
+
+            // Failing checks count for their variables, satisfied checks count against
+            for (c, &w) in checks.iter_mut().zip(weights.iter()) {
+                *c = if *c == 1 { i16::from(w) } else { -i16::from(w) };
+            }
+            for f in &mut flips[..] { *f = 0 }
+            for (check, var) in self.iter_paritychecks() {
+                flips[var] += checks[check];
+            }
+
+            // Flip the first variable with the largest metric
+            let mut flip_var = 0;
+            for (var, &f) in flips.iter().enumerate() {
+                if f > flips[flip_var] {
+                    flip_var = var;
+                }
+            }
+            output[flip_var/8] ^= 1<<(7-(flip_var%8));
+        }
+
+        (false, maxiters + erasure_iters)
+    }
+
+    /// Gradient descent bit flipping decoder.
+    ///
+    /// This is the single-bit gradient descent bit flipping algorithm of Wadayama et al. Each
+    /// iteration computes the inversion function for each bit, the product of its bipolar
+    /// value (+1 for a 0 bit, -1 for a 1 bit) with its LLR plus the bipolar value of every
+    /// check it is connected to, and flips the single bit where this is smallest. No messages
+    /// are passed, so it needs much less memory than `decode_ms`, and usually corrects more
+    /// errors than `decode_wbf`. As only one bit is flipped per iteration, `maxiters` should
+    /// be at least the number of bit errors you hope to correct.
+    ///
+    /// * `llrs` must be n long, with positive numbers more likely to be a 0 bit, as for
+    ///   `decode_ms`. Unlike `decode_ms` this decoder is not invariant to the scale of the
+    ///   LLRs, as they are weighed against the checks, each of which counts for 1.
+    /// * `output` must be allocated to (n+punctured_bits)/8 bytes, aka `output_len()`
+    /// * `working` must be allocated to `decode_gdbf_working_len()` bytes, equal to
+    ///   n+punctured_bits
+    /// * `working_f32` must be allocated to `decode_gdbf_working_f32_len()` elements, equal to
+    ///   n+punctured_bits
+    ///
+    /// As with `decode_bf`, punctured codes first have their punctured bits recovered by
+    /// erasure decoding.
+    ///
+    /// Returns `(decoding success, iters)` with the same meaning as `decode_bf`.
+    pub fn decode_gdbf(self, llrs: &[f32], output: &mut [u8], working: &mut [u8],
+                       working_f32: &mut [f32], maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len != (n+p)/8");
+        assert_eq!(working.len(), self.decode_gdbf_working_len(), "working.len() incorrect");
+        assert_eq!(working_f32.len(), self.decode_gdbf_working_f32_len(),
+                   "working_f32.len() incorrect");
+
+        let n = self.n();
+        let k = self.k();
+        let p = self.punctured_bits();
+
+        // Hard decide the LLRs into the output
+        for o in &mut output[..] { *o = 0 }
+        for (var, llr) in llrs.iter().enumerate() {
+            if *llr < 0.0 {
+                output[var/8] |= 1 << (7 - (var%8));
+            }
+        }
+
+        // Recover punctured bits first, exactly as decode_bf does.
+        let erasure_iters = if p > 0 {
+            let (_, iters) = self.decode_erasures_inner(|_| true, output, working, maxiters);
+            iters
+        } else { 0 };
+
+        // Working areas: `checks` holds the parity of each check, and `inversion` holds
+        // the inversion function for each variable.
+        let checks = &mut working[..n+p-k];
+        let inversion = working_f32;
+
+        // As in decode_bf, the final pass only checks the parity.
+        for iter in 0..=maxiters {
+            // Calculate the parity of each parity check
+            for c in &mut checks[..] { *c = 0 }
+            for (check, var) in self.iter_paritychecks() {
+                if output[var/8] >> (7-(var%8)) & 1 == 1 {
+                    checks[check] ^= 1;
+                }
+            }
+
+            if checks.iter().all(|c| *c == 0) {
+                return (true, iter + erasure_iters);
+            } else if iter == maxiters {
+                break;
+            }
+
+            // Correlation of each bit with its LLR; punctured bits have no LLR
+            for (var, inv) in inversion.iter_mut().enumerate() {
+                *inv = if var >= n {
+                    0.0
+                } else if output[var/8] >> (7-(var%8)) & 1 == 1 {
+                    -llrs[var]
+                } else {
+                    llrs[var]
+                };
+            }
+
+            // Satisfied checks count +1 and failing checks -1
+            for (check, var) in self.iter_paritychecks() {
+                inversion[var] += if checks[check] == 0 { 1.0 } else { -1.0 };
+            }
+
+            // Flip the first variable with the smallest inversion function
+            let mut flip_var = 0;
+            for (var, &inv) in inversion.iter().enumerate() {
+                if inv < inversion[flip_var] {
+                    flip_var = var;
+                }
+            }
+            output[flip_var/8] ^= 1<<(7-(flip_var%8));
+        }
+
+        (false, maxiters + erasure_iters)
+    }
+
+    /// Bit flipping decoder, returning `DecodeStats`.
+    ///
+    /// Identical to `decode_bf` but returns a `DecodeStats`, where `converged` is true only if
+    /// every parity check was satisfied when the decoder stopped.
+    pub fn decode_bf_stats(self, input: &[u8], output: &mut [u8],
+                           working: &mut [u8], maxiters: usize)
+        -> DecodeStats
+    {
+        DecodeStats::from(self.decode_bf(input, output, working, maxiters))
+    }
+
+    /// Layered min-sum decoder.
+    ///
+    /// This is the same min-sum algorithm as `decode_ms`, but using a layered schedule: the
+    /// parity checks are processed one block row of the parity check matrix at a time, and the
+    /// marginal of each variable is updated as soon as each layer has been processed, so later
+    /// layers in the same iteration already see the improved information. This typically
+    /// converges in fewer iterations than the flooding schedule used by `decode_ms`, and
+    /// corrects slightly more errors in the same number.
+    ///
+    /// All arguments and the return value are as for `decode_ms`, including the working areas.
+    /// Integer marginals saturate as they do there, so the same fixed-point guidance applies.
+    // As in decode_ms, we count edges by hand rather than using .enumerate().
+    #[allow(clippy::explicit_counter_loop)]
+    pub fn decode_ms_layered<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                            working: &mut [T], working_u8: &mut [u8],
+                                            maxiters: usize)
+        -> (bool, usize)
+    {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_ms_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_ms_working_u8_len(), "working_u8 != (n+p-k)/8");
+
+        let n = self.n();
+        let k = self.k();
+        let p = self.punctured_bits();
+        let m = self.submatrix_size();
+        let layers = (n + p - k) / m;
+
+        // Rename output to parities as we'll use it to keep track of the parity bits until the end
+        let parities = output;
+
+        // Rename working_u8 to ui_sgns, we'll use it to accumulate signs for each check
+        let ui_sgns = working_u8;
+
+        // Zero the working area and split it up, with the same layout as decode_ms
+        for w in &mut working[..] { *w = T::zero() }
+        let (u, working)        = working.split_at_mut(self.paritycheck_sum() as usize);
+        let (v, working)        = working.split_at_mut(self.paritycheck_sum() as usize);
+        let (va, working)       = working.split_at_mut(n + p);
+        let (ui_min1, ui_min2)  = working.split_at_mut(n + p - k);
+
+        // The marginals persist between iterations, always equal to the input LLR plus
+        // all current messages to that variable. Punctured bits start at 0.
+        //
+        // Replacing a message in a marginal is only exact while nothing saturates, so once any
+        // marginal reaches T::maxval() they are all rebuilt from the input LLRs and current
+        // messages at the end of the layer, saturating in the same way as decode_ms.
+        va[..n].copy_from_slice(llrs);
+        let mut saturated = false;
+
+        for iter in 0..maxiters {
+            let mut layer_start = 0;
+            for layer in 0..layers {
+                let checks = layer*m .. (layer+1)*m;
+                for x in &mut ui_min1[checks.clone()] { *x = T::maxval() }
+                for x in &mut ui_min2[checks.clone()] { *x = T::maxval() }
+                for check in checks {
+                    ui_sgns[check/8] &= !(1<<(check%8));
+                }
+
+                // Work out messages to each check in this layer, removing the check's own
+                // previous message from the marginal. As in decode_ms, a message whose sign
+                // has changed since the last iteration is erased to zero instead.
+                let mut idx = layer_start;
+                for (check, var) in self.iter_paritychecks_row(layer) {
+                    let new_v = bound(va[var].saturating_sub(u[idx]));
+                    if v[idx] != T::zero() && (new_v >= T::zero()) != (v[idx] >= T::zero()) {
+                        v[idx] = T::zero();
+                    } else {
+                        v[idx] = new_v;
+                    }
+                    accumulate_mins(v[idx].abs(), &mut ui_min1[check], &mut ui_min2[check]);
+                    if v[idx] < T::zero() {
+                        ui_sgns[check/8] ^= 1<<(check%8);
+                    }
+                    idx += 1;
+                }
+
+                // Work out new messages to each variable and replace the old messages in the
+                // marginals straight away
+                let mut idx = layer_start;
+                for (check, var) in self.iter_paritychecks_row(layer) {
+                    let mut new_u = reply_magnitude(v[idx].abs(), ui_min1[check], ui_min2[check]);
+                    if ui_sgns[check/8] >> (check%8) & 1 == 1 {
+                        new_u = -new_u;
+                    }
+                    if v[idx] < T::zero() {
+                        new_u = -new_u;
+                    }
+                    let old_va = va[var];
+                    let removed = bound(old_va.saturating_sub(u[idx]));
+                    va[var] = bound(removed.saturating_add(new_u));
+                    if old_va.abs() >= T::maxval() || removed.abs() >= T::maxval()
+                        || va[var].abs() >= T::maxval()
+                    {
+                        saturated = true;
+                    }
+                    u[idx] = new_u;
+                    idx += 1;
+                }
+
+                layer_start = idx;
+
+                if saturated {
+                    va[..n].copy_from_slice(llrs);
+                    for x in &mut va[n..] { *x = T::zero() }
+                    for (idx, (_, var)) in self.iter_paritychecks().enumerate() {
+                        va[var] = bound(va[var].saturating_add(u[idx]));
+                    }
+                    saturated = false;
+                }
+            }
+
+            // Check parities. If none are 1 then we have a valid codeword.
+            for x in &mut parities[..] { *x = 0 }
+            for (check, var) in self.iter_paritychecks() {
+                if va[var] <= T::zero() {
+                    parities[check/8] ^= 1<<(check%8);
+                }
+            }
+            if parities.iter().all(|x| *x == 0) {
+                let output = parities;
+                for (var, &va) in va[0..(n+p)].iter().enumerate() {
+                    if va <= T::zero() {
+                        output[var/8] |= 1 << (7 - (var%8));
+                    }
+                }
+                return (true, iter);
+            }
+        }
+
+        // If we failed to find a codeword, at least hard decode the marginals into the output
+        let output = parities;
+        for o in &mut output[..] { *o = 0 }
+        for (var, &va) in va[0..(n+p)].iter().enumerate() {
+            if va <= T::zero() {
+                output[var/8] |= 1 << (7 - (var%8));
+            }
+        }
+        (false, maxiters)
+    }
+
+    /// Message passing based min-sum decoder, returning `DecodeStats`.
+    ///
+    /// Identical to `decode_ms` but returns a `DecodeStats`, where `converged` is true only if
+    /// every parity check was satisfied when the decoder stopped.
+    pub fn decode_ms_stats<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                          working: &mut [T], working_u8: &mut [u8],
+                                          maxiters: usize)
+        -> DecodeStats
+    {
+        DecodeStats::from(self.decode_ms(llrs, output, working, working_u8, maxiters))
+    }
+
+    /// Bit flipping decoder with checked buffer lengths.
+    ///
+    /// Identical to `decode_bf`, but instead of panicking when a buffer is the wrong length,
+    /// returns `DecodeError::BadBufferLen` describing which buffer was wrong. On success
+    /// returns the `DecodeStats` for the decode.
+    pub fn try_decode_bf(self, input: &[u8], output: &mut [u8],
+                         working: &mut [u8], maxiters: usize)
+        -> Result<DecodeStats, DecodeError>
+    {
+        check_len("input", self.n()/8, input.len())?;
+        check_len("output", self.output_len(), output.len())?;
+        check_len("working", self.decode_bf_working_len(), working.len())?;
+        Ok(self.decode_bf_stats(input, output, working, maxiters))
+    }
+
+    /// Message passing based min-sum decoder with checked buffer lengths.
+    ///
+    /// Identical to `decode_ms`, but instead of panicking when a buffer is the wrong length,
+    /// returns `DecodeError::BadBufferLen` describing which buffer was wrong. Any NaN or
+    /// infinite LLR is rejected with `DecodeError::NonFiniteInput` before decoding starts.
+    /// On success returns the `DecodeStats` for the decode.
+    pub fn try_decode_ms<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8],
+                                        working: &mut [T], working_u8: &mut [u8],
+                                        maxiters: usize)
+        -> Result<DecodeStats, DecodeError>
+    {
+        check_len("llrs", self.n(), llrs.len())?;
+        check_len("output", self.output_len(), output.len())?;
+        check_len("working", self.decode_ms_working_len(), working.len())?;
+        check_len("working_u8", self.decode_ms_working_u8_len(), working_u8.len())?;
+        check_finite(llrs)?;
+        let init_llrs = |va: &mut [T]| va.copy_from_slice(llrs);
+        let result = self.decode_ms_unchecked(|| self.iter_paritychecks(), init_llrs, output,
+                                              working, working_u8, maxiters,
+                                              MsCorrection::None, |_| true, false, |_, _| ());
+        Ok(DecodeStats::from(result))
+    }
+
+    /// `f32` min-sum decoder using a working area of bytes, with checked buffer lengths.
+    ///
+    /// Identical to `try_decode_ms` with `f32` LLRs, except `working` is given as bytes, for
+    /// example when it comes from a byte arena allocator. It is used as `f32` internally, so
+    /// must be `decode_ms_working_len_bytes()` long and aligned for `f32` (to 4 bytes on most
+    /// targets).
+    ///
+    /// Returns `DecodeError::BadBufferLen` if any buffer is the wrong length, or
+    /// `DecodeError::BadAlignment` if `working` is not suitably aligned.
+    pub fn try_decode_ms_bytes(self, llrs: &[f32], output: &mut [u8], working: &mut [u8],
+                               working_u8: &mut [u8], maxiters: usize)
+        -> Result<DecodeStats, DecodeError>
+    {
+        check_len("working", self.decode_ms_working_len_bytes(), working.len())?;
+        let align = mem::align_of::<f32>();
+        if working.as_ptr().align_offset(align) != 0 {
+            return Err(DecodeError::BadAlignment { which: "working", required: align });
+        }
+
+        // The length and alignment are checked above, and every bit pattern is a valid f32.
+        let working = unsafe {
+            slice::from_raw_parts_mut(working.as_mut_ptr() as *mut f32,
+                                      self.decode_ms_working_len())
+        };
+        self.try_decode_ms(llrs, output, working, working_u8, maxiters)
+    }
+
+    /// Decode `input` with the decoder selected by `kind`, for selecting a decoder at runtime.
+    ///
+    /// This is a thin wrapper around the individual decoders, using their default settings:
+    ///
+    /// * `DecoderKind::BitFlipping` runs `decode_bf` on `DecodeInput::Hard` input
+    /// * `DecoderKind::MinSum` runs `decode_ms` on `DecodeInput::Soft` input
+    /// * `DecoderKind::GradientDescentBitFlipping` runs `decode_gdbf` on `DecodeInput::Soft`
+    ///   input
+    /// * `DecoderKind::SumProduct` runs `decode_sp` on `DecodeInput::Soft` input, with the
+    ///   `std` feature
+    ///
+    /// Any other combination, including the decoders which need extra parameters, returns
+    /// `DecodeError::Unsupported`.
+    ///
+    /// `output` must be `output_len()` long, and `working` and `working_soft` must be the
+    /// `working_u8` and `working_soft` lengths given by `buffer_plan(kind)`.
+    ///
+    /// Returns `DecodeError::BadBufferLen` if any buffer is the wrong length, otherwise the
+    /// statistics of the decode.
+    pub fn decode(self, kind: DecoderKind, input: DecodeInput, output: &mut [u8],
+                  working: &mut [u8], working_soft: &mut [f32], maxiters: usize)
+        -> Result<DecodeStats, DecodeError>
+    {
+        let buffers = self.decoder_buffers(kind);
+        match (kind, input) {
+            (DecoderKind::BitFlipping, DecodeInput::Hard(input)) => {
+                check_len("working_soft", buffers.working_soft, working_soft.len())?;
+                self.try_decode_bf(input, output, working, maxiters)
+            },
+            (DecoderKind::MinSum, DecodeInput::Soft(llrs)) =>
+                self.try_decode_ms(llrs, output, working_soft, working, maxiters),
+            (DecoderKind::GradientDescentBitFlipping, DecodeInput::Soft(llrs)) => {
+                check_len("llrs", self.n(), llrs.len())?;
+                check_len("output", buffers.output, output.len())?;
+                check_len("working", buffers.working_u8, working.len())?;
+                check_len("working_soft", buffers.working_soft, working_soft.len())?;
+                Ok(DecodeStats::from(self.decode_gdbf(llrs, output, working, working_soft,
+                                                      maxiters)))
+            },
+            #[cfg(feature = "std")]
+            (DecoderKind::SumProduct, DecodeInput::Soft(llrs)) => {
+                check_len("llrs", self.n(), llrs.len())?;
+                check_len("output", buffers.output, output.len())?;
+                check_len("working", buffers.working_u8, working.len())?;
+                check_len("working_soft", buffers.working_soft, working_soft.len())?;
+                Ok(DecodeStats::from(self.decode_sp(llrs, output, working_soft, working,
+                                                    maxiters)))
+            },
+            _ => Err(DecodeError::Unsupported { kind }),
+        }
+    }
+
+    /// Min-sum decoder implementation, without any checks on buffer lengths.
+    ///
+    /// See `decode_ms` for details. All buffer lengths must already have been validated.
+    ///
+    /// `edges()` must return an iterator over every parity check edge, in the same order as
+    /// `iter_paritychecks()`. `llrs(va)` must fill the n-long `va` with the input LLRs.
+    /// `accept(output)` is called with each hard decision that satisfies every parity check,
+    /// and decoding only stops successfully if it returns true. If `resume` is true, the
+    /// working areas are not cleared, so decoding continues from their current state.
+    /// `on_iter(iter, unsatisfied)` is called after each iteration with the number of parity
+    /// checks not satisfied by the current hard decisions.
+    #[allow(clippy::cognitive_complexity,clippy::many_single_char_names,clippy::too_many_arguments)]
+    fn decode_ms_unchecked<T, E, I, L, A, C>(self, edges: E, llrs: L, output: &mut [u8],
+                                             working: &mut [T], working_u8: &mut [u8],
+                                             maxiters: usize, correction: MsCorrection<T>,
+                                             accept: A, resume: bool, mut on_iter: C)
+        -> (bool, usize)
+        where T: DecodeFrom, E: Fn() -> I, I: Iterator<Item=(usize, usize)>, L: Fn(&mut [T]),
+              A: Fn(&[u8]) -> bool, C: FnMut(usize, usize)
+    {
+        let n = self.n();
+        let k = self.k();
+        let p = self.punctured_bits();
+
+        // Rename output to parities as we'll use it to keep track of the parity bits until the end
+        let parities = output;
+
+        // Rename working_u8 to ui_sgns, we'll use it to accumulate signs for each check
+        let ui_sgns = working_u8;
+
+        // Zero the working area (unless resuming a previous decode) and split it up. The
+        // results are read back by `ms_results`, which must match this layout.
+        if !resume {
+            for w in &mut working[..] { *w = T::zero() }
+        }
+        let (u, working)        = working.split_at_mut(self.paritycheck_sum() as usize);
+        let (v, working)        = working.split_at_mut(self.paritycheck_sum() as usize);
+        let (va, working)       = working.split_at_mut(n + p);
+        let (ui_min1, ui_min2)  = working.split_at_mut(n + p - k);
+
+        // Fast path: if the hard decisions of the input already satisfy every parity check,
+        // stop before any message passing. The first iteration would only exchange zero
+        // messages, so this gives the same result in a fraction of the time. Punctured codes
+        // have no input for their punctured bits, so must always pass messages.
+        if !resume && p == 0 && maxiters > 0 {
+            llrs(&mut va[..n]);
+            if let MsCorrection::Clamp(max) = correction {
+                for x in &mut va[..] {
+                    if *x > max { *x = max } else if *x < -max { *x = -max }
+                }
+            }
+            for o in &mut parities[..] { *o = 0 }
+            for (var, &va) in va.iter().enumerate() {
+                if va <= T::zero() {
+                    parities[var/8] |= 1 << (7 - (var%8));
+                }
+            }
+            for s in &mut ui_sgns[..] { *s = 0 }
+            for (check, var) in edges() {
+                if parities[var/8] >> (7 - (var%8)) & 1 == 1 {
+                    ui_sgns[check/8] ^= 1 << (check%8);
+                }
+            }
+            if ui_sgns.iter().all(|&s| s == 0) && accept(parities) {
+                on_iter(0, 0);
+                return (true, 0);
+            }
+        }
+
+        for iter in 0..maxiters {
+            // Initialise the marginals to the input LLRs (and to 0 for punctured bits)
+            llrs(&mut va[..n]);
+            for x in &mut va[n..] { *x = T::zero() }
+
+            // You'd think .enumerate() would be sensible, but actually it prevents
+            // inlining the iterator's next() method, which leads to a big performance hit.
+            let mut idx = 0;
+            for (check, var) in edges() {
+                // Work out messages to this variable
+                let mut msg = correction.apply(reply_magnitude(v[idx].abs(), ui_min1[check],
+                                                               ui_min2[check]));
+                if ui_sgns[check/8] >> (check%8) & 1 == 1 {
+                    msg = -msg;
+                }
+                if v[idx] < T::zero() {
+                    msg = -msg;
+                }
+                if let MsCorrection::Damped(lambda) = correction {
+                    msg = msg.scale(1.0 - lambda).saturating_add(u[idx].scale(lambda));
+                }
+                u[idx] = msg;
+
+                // Accumulate incoming messages to each variable
+                va[var] = va[var].saturating_add(u[idx]);
+
+                // DIY enumerate
+                idx += 1;
+            }
+
+            // Bound the marginals, see `decode_ms`. Integer types already saturate here.
+            let max = if let MsCorrection::Clamp(max) = correction { max } else { T::maxval() };
+            for x in &mut va[..] {
+                if *x > max { *x = max } else if *x < -max { *x = -max }
+            }
+
+            for x in &mut ui_min1[..] { *x = T::maxval() }
+            for x in &mut ui_min2[..] { *x = T::maxval() }
+            for x in &mut ui_sgns[..] { *x = 0 }
+            for x in &mut parities[..] { *x = 0 }
+            idx = 0;
+            for (check, var) in edges() {
+                // Work out messages to this parity check, bounded like the marginals
+                let new_v_ai = bound(va[var].saturating_sub(u[idx]));
+                if v[idx] != T::zero() && (new_v_ai >= T::zero()) != (v[idx] >= T::zero()) {
+                    v[idx] = T::zero();
+                } else {
+                    v[idx] = new_v_ai;
+                }
+
+                // Accumulate two minimums
+                accumulate_mins(v[idx].abs(), &mut ui_min1[check], &mut ui_min2[check]);
+
+                // Accumulate signs
+                if v[idx] < T::zero() {
+                    ui_sgns[check/8] ^= 1<<(check%8);
+                }
+
+                // Accumulate parity
+                if va[var] <= T::zero() {
+                    parities[check/8] ^= 1<<(check%8);
+                }
+
+                idx += 1;
+            }
+
+            on_iter(iter, parities.iter().map(|x| x.count_ones() as usize).sum());
+
+            // Check parities. If none are 1 then we have a valid codeword.
+            if *parities.iter().max().unwrap() == 0 {
+                // Hard decode marginals into the output
+                for o in &mut parities[..] { *o = 0 }
+                for (var, &va) in va[0..(n+p)].iter().enumerate() {
+                    if va <= T::zero() {
+                        parities[var/8] |= 1 << (7 - (var%8));
+                    }
+                }
+
+                // Stop unless the caller rejects this codeword, in which case the parities
+                // are recomputed over the output next iteration
+                if accept(parities) {
+                    return (true, iter);
+                }
+            }
+        }
+
+        // If we failed to find a codeword, at least hard decode the marginals into the output
+        let output = parities;
+        for o in &mut output[..] { *o = 0 }
+        for (var, &va) in va[0..(n+p)].iter().enumerate() {
+            if va <= T::zero() {
+                output[var/8] |= 1 << (7 - (var%8));
+            }
+        }
+        (false, maxiters)
+    }
+
+    /// The final check-to-variable messages and marginals left in the working area by
+    /// `decode_ms_unchecked`, split up as laid out there: one message per parity check edge
+    /// in the order of `iter_paritychecks()`, and one marginal per bit including the
+    /// punctured bits.
+    fn ms_results<T: DecodeFrom>(self, working: &[T]) -> (&[T], &[T]) {
+        let edges = self.paritycheck_sum() as usize;
+        let marginals = &working[2*edges..2*edges + self.n() + self.punctured_bits()];
+        (&working[..edges], marginals)
+    }
+
+    /// Convert hard information into LLRs.
+    ///
+    /// The min-sum decoding used in `decode_ms` is invariant to linear scaling
+    /// in LLR, so it doesn't matter which value is picked so long as the sign
+    /// is correct. This function just assigns -/+ 1 for 1/0 bits.
+    ///
+    /// `input` must be n/8 long, `llrs` must be n long.
+    pub fn hard_to_llrs<T: DecodeFrom>(self, input: &[u8], llrs: &mut [T]) {
+        self.hard_to_llrs_scaled(input, T::one(), llrs);
+    }
+
+    /// Convert hard information into LLRs of a given magnitude.
+    ///
+    /// Each 1 bit becomes `-magnitude` and each 0 bit becomes `+magnitude`, using the same bit
+    /// ordering as the encoders. This is useful when hard bits need to be combined with soft
+    /// information from elsewhere, where the scale of the LLRs does matter.
+    ///
+    /// `input` must be n/8 long, `llrs` must be n long, and `magnitude` must be positive.
+    pub fn hard_to_llrs_scaled<T: DecodeFrom>(self, input: &[u8], magnitude: T, llrs: &mut [T]) {
+        assert_eq!(input.len(), self.n()/8, "input.len() != n/8");
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert!(magnitude > T::zero(), "magnitude must be positive");
+        let llr = -magnitude;
+        for (idx, byte) in input.iter().enumerate() {
+            for i in 0..8 {
+                llrs[idx*8 + i] = if (byte >> (7-i)) & 1 == 1 { llr } else { -llr };
+            }
+        }
+    }
+
+    /// Restore LLRs for bits removed by `puncture`, ready for decoding.
+    ///
+    /// Each punctured position is given an LLR of zero (no information), and the received
+    /// LLRs are placed in the remaining positions in order.
+    ///
+    /// * `received` must be n - extra_puncture.len() long
+    /// * `extra_puncture` lists the punctured positions in 0..n, in strictly increasing
+    ///   order, exactly as given to `puncture`
+    /// * `llrs` must be n long
+    pub fn depuncture_llrs<T: DecodeFrom>(self, received: &[T], extra_puncture: &[usize],
+                                          llrs: &mut [T])
+    {
+        assert_eq!(received.len() + extra_puncture.len(), self.n(), "received.len() incorrect");
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert!(extra_puncture.windows(2).all(|w| w[0] < w[1]), "extra_puncture must be sorted");
+        assert!(extra_puncture.iter().all(|&x| x < self.n()), "extra_puncture >= n");
+
+        let mut punctured = extra_puncture.iter().peekable();
+        let mut received = received.iter();
+        for (bit, llr) in llrs.iter_mut().enumerate() {
+            if punctured.peek() == Some(&&bit) {
+                punctured.next();
+                *llr = T::zero();
+            } else {
+                *llr = *received.next().unwrap();
+            }
+        }
+    }
+
+    /// Convert received BPSK samples from an AWGN channel into LLRs.
+    ///
+    /// Assumes each 0 bit was transmitted as +1 and each 1 bit as -1, in the same bit order as
+    /// the encoders, and that the channel added Gaussian noise of variance `sigma2`. The LLR
+    /// for each sample `y` is then `2*y/sigma2`.
+    ///
+    /// `samples` and `llrs` must both be n long, and `sigma2` must be positive.
+    pub fn bpsk_awgn_to_llrs(self, samples: &[f32], sigma2: f32, llrs: &mut [f32]) {
+        assert_eq!(samples.len(), self.n(), "samples.len() != n");
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert!(sigma2 > 0.0, "sigma2 must be positive");
+        let scale = 2.0 / sigma2;
+        for (llr, y) in llrs.iter_mut().zip(samples.iter()) {
+            *llr = y * scale;
+        }
+    }
+
+    /// Convert LLRs into hard information.
+    ///
+    /// `llrs` must be n long, `output` must be n/8 long.
+    pub fn llrs_to_hard<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8]) {
+        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
+        assert_eq!(output.len(), self.n()/8, "output.len() != n/8");
+
+        for o in &mut output[..] { *o = 0 }
+
+        for (i, llr) in llrs.iter().enumerate() {
+            if *llr < T::zero() {
+                output[i/8] |= 1 << (7 - (i%8));
+            }
+        }
+    }
+
+    /// Find which received bits a decoder changed.
+    ///
+    /// Sets each bit of `flipped` to 1 where the decoded `output` differs from the received
+    /// hard information `input`, revealing the error pattern the decoder corrected. This is
+    /// useful for gathering channel error statistics, or spotting hardware faults such as a
+    /// stuck bit. Only the n transmitted bits are compared, not any punctured bits. For the
+    /// soft decoders, first convert the received LLRs with `llrs_to_hard`.
+    ///
+    /// * `input` must be n/8 long, the received hard information
+    /// * `output` must be `output_len()` long, the output of a decoder
+    /// * `flipped` must be n/8 long, and is packed as for codewords
+    ///
+    /// Returns the number of bits changed.
+    pub fn flipped_bits(self, input: &[u8], output: &[u8], flipped: &mut [u8]) -> usize {
+        assert_eq!(input.len(), self.n()/8, "input.len() != n/8");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(flipped.len(), self.n()/8, "flipped.len() != n/8");
+
+        for ((f, i), o) in flipped.iter_mut().zip(input.iter()).zip(output.iter()) {
+            *f = i ^ o;
+        }
+        flipped.iter().map(|f| f.count_ones() as usize).sum()
+    }
+}
+
+#[cfg(feature = "std")]
+impl LDPCCode {
+    /// Convert hard information received over a binary symmetric channel into LLRs.
+    ///
+    /// Each bit becomes an LLR of magnitude ln((1-p)/p), where `crossover_p` is the channel's
+    /// probability of flipping a bit, negative for 1 bits and positive for 0 bits. Without the
+    /// `std` feature, pass a precomputed magnitude to `hard_to_llrs_scaled` instead.
+    ///
+    /// `input` must be n/8 long, `llrs` must be n long, and `crossover_p` must be strictly
+    /// between 0 and 0.5.
+    pub fn bsc_to_llrs(self, input: &[u8], crossover_p: f32, llrs: &mut [f32]) {
+        assert!(crossover_p > 0.0 && crossover_p < 0.5, "crossover_p must be in (0, 0.5)");
+        let magnitude = ((1.0 - crossover_p) / crossover_p).ln();
+        self.hard_to_llrs_scaled(input, magnitude, llrs);
+    }
+
+    /// Get the length of [T] required for the working area of `decode_sp`.
+    ///
+    /// Equal to `decode_ms_working_len()`, so the same working area can be used for both.
+    pub const fn decode_sp_working_len(self) -> usize {
+        self.decode_ms_working_len()
+    }
+
+    /// Get the length of [u8] required for the working_u8 area of `decode_sp`.
+    ///
+    /// Equal to `decode_ms_working_u8_len()`, so the same working area can be used for both.
+    pub const fn decode_sp_working_u8_len(self) -> usize {
+        self.decode_ms_working_u8_len()
+    }
+
+    /// Message passing based sum-product decoder.
+    ///
+    /// This is the full belief propagation decoder which `decode_ms` approximates. Each check
+    /// sends `2 atanh(prod(tanh(x/2)))` over its other inputs, evaluated in the log domain as
+    /// `phi(sum(phi(|x|)))` with `phi(x) = -ln(tanh(x/2))`. It is considerably slower than the
+    /// min-sum decoder as it requires transcendental functions for every edge, but it provides
+    /// optimal message passing performance, which is useful for offline analysis and for
+    /// generating reference error rate curves. Only available with the `std` feature.
+    ///
+    /// Unlike min-sum, this decoder is *not* invariant to scaling of the LLRs, so `llrs` must be
+    /// true log likelihood ratios, `ln(P(bit=0)/P(bit=1))`, for best performance.
+    ///
+    /// Requires:
+    ///
+    /// * `llrs` must be `n` long, with positive numbers more likely to be a 0 bit.
+    /// * `output` must be allocated to (n+punctured_bits)/8 bytes, aka `output_len()`, and
+    ///   is written with the decoded codeword as for `decode_ms`.
+    /// * `working` must have `decode_sp_working_len()` elements.
+    /// * `working_u8` must have `decode_sp_working_u8_len()` elements.
+    ///
+    /// Will run for at most `maxiters` iterations.
+    ///
+    /// Returns decoding success and the number of iterations run for.
+    #[allow(clippy::many_single_char_names)]
+    pub fn decode_sp<T: DecodeFromFloat>(self, llrs: &[T], output: &mut [u8],
+                                         working: &mut [T], working_u8: &mut [u8],
+                                         maxiters: usize)
+        -> (bool, usize)
+    {
+        let n = self.n();
+        let k = self.k();
+        let p = self.punctured_bits();
+
+        assert_eq!(llrs.len(), n, "llrs.len() != n");
+        assert_eq!(output.len(), self.output_len(), "output.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_sp_working_len(), "working.len() incorrect");
+        assert_eq!(working_u8.len(), self.decode_sp_working_u8_len(), "working_u8 != (n+p-k)/8");
+
+        // Rename output to parities as we'll use it to keep track of the parity bits until the end
+        let parities = output;
+
+        // Rename working_u8 to ui_sgns, we'll use it to accumulate signs for each check
+        let ui_sgns = working_u8;
+
+        // Zero the working area and split it up. The final n+p-k elements are unused.
+        for w in &mut working[..] { *w = T::zero() }
+        let (u, working)        = working.split_at_mut(self.paritycheck_sum() as usize);
+        let (v, working)        = working.split_at_mut(self.paritycheck_sum() as usize);
+        let (va, working)       = working.split_at_mut(n + p);
+        let (ui_phis, _)        = working.split_at_mut(n + p - k);
+
+        // Initialise the messages to each check with just the channel LLRs
+        for x in &mut ui_sgns[..] { *x = 0 }
+        let mut idx = 0;
+        for (check, var) in self.iter_paritychecks() {
+            v[idx] = if var < n { llrs[var] } else { T::zero() };
+            let phi_v = T::from_f64(phi(v[idx].abs().to_f64()));
+            ui_phis[check] += phi_v;
+            if v[idx] < T::zero() {
+                ui_sgns[check/8] ^= 1<<(check%8);
+            }
+            idx += 1;
+        }
+
+        for iter in 0..maxiters {
+            // Initialise the marginals to the input LLRs (and to 0 for punctured bits)
+            va[..llrs.len()].copy_from_slice(llrs);
+            for x in &mut va[llrs.len()..] { *x = T::zero() }
+
+            idx = 0;
+            for (check, var) in self.iter_paritychecks() {
+                // Work out messages to this variable, removing this edge's own contribution
+                let phi_v = phi(v[idx].abs().to_f64());
+                let others = (ui_phis[check].to_f64() - phi_v).max(0.0);
+                u[idx] = T::from_f64(phi(others));
+                if ui_sgns[check/8] >> (check%8) & 1 == 1 {
+                    u[idx] = -u[idx];
+                }
+                if v[idx] < T::zero() {
+                    u[idx] = -u[idx];
+                }
+
+                // Accumulate incoming messages to each variable
+                va[var] += u[idx];
+
+                idx += 1;
+            }
+
+            for x in &mut ui_phis[..] { *x = T::zero() }
+            for x in &mut ui_sgns[..] { *x = 0 }
+            for x in &mut parities[..] { *x = 0 }
+            idx = 0;
+            for (check, var) in self.iter_paritychecks() {
+                // Work out messages to this parity check
+                v[idx] = va[var] - u[idx];
+
+                // Accumulate phi of magnitudes and signs
+                let phi_v = T::from_f64(phi(v[idx].abs().to_f64()));
+                ui_phis[check] += phi_v;
+                if v[idx] < T::zero() {
+                    ui_sgns[check/8] ^= 1<<(check%8);
+                }
+
+                // Accumulate parity
+                if va[var] <= T::zero() {
+                    parities[check/8] ^= 1<<(check%8);
+                }
+
+                idx += 1;
+            }
+
+            // Check parities. If none are 1 then we have a valid codeword.
+            if *parities.iter().max().unwrap() == 0 {
+                let output = parities;
+                for o in &mut output[..] { *o = 0 }
+                for (var, &va) in va[0..(n+p)].iter().enumerate() {
+                    if va <= T::zero() {
+                        output[var/8] |= 1 << (7 - (var%8));
+                    }
+                }
+                return (true, iter);
+            }
+        }
+
+        // If we failed to find a codeword, at least hard decode the marginals into the output
+        let output = parities;
+        for o in &mut output[..] { *o = 0 }
+        for (var, &va) in va[0..(n+p)].iter().enumerate() {
+            if va <= T::zero() {
+                output[var/8] |= 1 << (7 - (var%8));
+            }
+        }
+        (false, maxiters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::v1::*;
+
+    use super::{DecodeError, DecodeFrom};
+    use crate::codes::{encodable, LDPCCode, CodeParams,
+                       TC128_PARAMS,  TC256_PARAMS,  TC512_PARAMS,
+                       TM1280_PARAMS, TM1536_PARAMS, TM2048_PARAMS,
+                       TM5120_PARAMS, TM6144_PARAMS, TM8192_PARAMS};
+
+    const CODES: [LDPCCode;  9] = [LDPCCode::TC128,   LDPCCode::TC256,   LDPCCode::TC512,
+                                   LDPCCode::TM1280,  LDPCCode::TM1536,  LDPCCode::TM2048,
+                                   LDPCCode::TM5120,  LDPCCode::TM6144,  LDPCCode::TM8192,
+    ];
+
+    const PARAMS: [CodeParams; 9] = [TC128_PARAMS,  TC256_PARAMS,  TC512_PARAMS,
+                                     TM1280_PARAMS, TM1536_PARAMS, TM2048_PARAMS,
+                                     TM5120_PARAMS, TM6144_PARAMS, TM8192_PARAMS,
+    ];
+
+    #[test]
+    fn test_decode_ms_working_len() {
+        for (code, param) in CODES.iter().zip(PARAMS.iter()) {
+            assert_eq!(code.decode_ms_working_len(), param.decode_ms_working_len);
+            assert_eq!(code.decode_ms_working_u8_len(), param.decode_ms_working_u8_len);
+        }
+    }
+
+    #[test]
+    fn test_decode_bf_working_len() {
+        for (code, param) in CODES.iter().zip(PARAMS.iter()) {
+            assert_eq!(code.decode_bf_working_len(), param.decode_bf_working_len);
+        }
+    }
+
+    #[test]
+    fn test_output_len() {
+        for (code, param) in CODES.iter().zip(PARAMS.iter()) {
+            assert_eq!(code.output_len(), param.output_len);
+        }
+    }
+
+    #[test]
+    fn test_hard_to_llrs() {
+        let code = LDPCCode::TC128;
+        let hard = vec![255, 254, 253, 252, 251, 250, 249, 248,
+                        203, 102, 103, 120, 107,  30, 157, 169];
+        let mut llrs = vec![0f32; code.n()];
+        let llr = -1.0;
+        code.hard_to_llrs(&hard, &mut llrs);
+        assert_eq!(llrs, vec![
+             llr,  llr,  llr,  llr,  llr,  llr,  llr,  llr,
+             llr,  llr,  llr,  llr,  llr,  llr,  llr, -llr,
+             llr,  llr,  llr,  llr,  llr,  llr, -llr,  llr,
+             llr,  llr,  llr,  llr,  llr,  llr, -llr, -llr,
+             llr,  llr,  llr,  llr,  llr, -llr,  llr,  llr,
+             llr,  llr,  llr,  llr,  llr, -llr,  llr, -llr,
+             llr,  llr,  llr,  llr,  llr, -llr, -llr,  llr,
+             llr,  llr,  llr,  llr,  llr, -llr, -llr, -llr,
+             llr,  llr, -llr, -llr,  llr, -llr,  llr,  llr,
+            -llr,  llr,  llr, -llr, -llr,  llr,  llr, -llr,
+            -llr,  llr,  llr, -llr, -llr,  llr,  llr,  llr,
+            -llr,  llr,  llr,  llr,  llr, -llr, -llr, -llr,
+            -llr,  llr,  llr, -llr,  llr, -llr,  llr,  llr,
+            -llr, -llr, -llr,  llr,  llr,  llr,  llr, -llr,
+             llr, -llr, -llr,  llr,  llr,  llr, -llr,  llr,
+             llr, -llr,  llr, -llr,  llr, -llr, -llr,  llr]);
+    }
+
+    #[test]
+    fn test_flipped_bits() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<3;
+            rxcode[code.n()/8 - 1] ^= 1<<0;
+
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut output = vec![0u8; code.output_len()];
+            assert!(code.decode_bf(&rxcode, &mut output, &mut working, 50).0);
+
+            let mut flipped = vec![0u8; code.n()/8];
+            assert_eq!(code.flipped_bits(&rxcode, &output, &mut flipped), 3);
+            assert_eq!(flipped[0], 1<<7 | 1<<3);
+            assert_eq!(flipped[code.n()/8 - 1], 1);
+            assert!(flipped[1..code.n()/8 - 1].iter().all(|f| *f == 0));
+        }
+    }
+
+    #[test]
+    fn test_llrs_to_hard() {
+        let code = LDPCCode::TC128;
+        let llr = -1.0;
+        let llrs = vec![
+             llr,  llr,  llr,  llr,  llr,  llr,  llr,  llr,
+             llr,  llr,  llr,  llr,  llr,  llr,  llr, -llr,
+             llr,  llr,  llr,  llr,  llr,  llr, -llr,  llr,
+             llr,  llr,  llr,  llr,  llr,  llr, -llr, -llr,
+             llr,  llr,  llr,  llr,  llr, -llr,  llr,  llr,
+             llr,  llr,  llr,  llr,  llr, -llr,  llr, -llr,
+             llr,  llr,  llr,  llr,  llr, -llr, -llr,  llr,
+             llr,  llr,  llr,  llr,  llr, -llr, -llr, -llr,
+             llr,  llr, -llr, -llr,  llr, -llr,  llr,  llr,
+            -llr,  llr,  llr, -llr, -llr,  llr,  llr, -llr,
+            -llr,  llr,  llr, -llr, -llr,  llr,  llr,  llr,
+            -llr,  llr,  llr,  llr,  llr, -llr, -llr, -llr,
+            -llr,  llr,  llr, -llr,  llr, -llr,  llr,  llr,
+            -llr, -llr, -llr,  llr,  llr,  llr,  llr, -llr,
+             llr, -llr, -llr,  llr,  llr,  llr, -llr,  llr,
+             llr, -llr,  llr, -llr,  llr, -llr, -llr,  llr];
+        let mut hard = vec![0u8; code.n()/8];
+        code.llrs_to_hard(&llrs, &mut hard);
+        assert_eq!(hard, vec![255, 254, 253, 252, 251, 250, 249, 248,
+                              203, 102, 103, 120, 107,  30, 157, 169]);
+    }
+
+    #[test]
+    fn test_decode_erasures() {
+        for code in &encodable(&CODES) {
+            // Only bother testing codes that actually have punctured bits
+            if code.punctured_bits() == 0 {
+                continue;
+            }
+
+            // Encode a codeword
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Allocate working area
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut output = vec![0u8; code.output_len()];
+
+            // Copy TX codeword into output manually (normally done by `decode_bf()`).
+            output[..txcode.len()].copy_from_slice(&txcode);
+
+            // Run erasure decoder, with only the punctured bits erased
+            let known = vec![true; code.n()];
+            let (success, _) = code.decode_erasures(&known, &mut output, &mut working, 50);
+
+            assert!(success);
+
+            // Now compare the result against the min-sum decoder which should
+            // also correctly decode the punctured parity bits
+            let mut llrs = vec![0i8; code.n()];
+            let mut working_ms = vec![0i8; code.decode_ms_working_len()];
+            let mut working_u8_ms = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output_ms = vec![0u8; code.output_len()];
+            code.hard_to_llrs(&txcode, &mut llrs);
+            let (success, _) = code.decode_ms(&llrs, &mut output_ms, &mut working_ms,
+                                              &mut working_u8_ms, 50);
+
+            assert!(success);
+            assert_eq!(output, output_ms);
+        }
+    }
+
+    #[test]
+    fn test_decode_bf() {
+        for code in &encodable(&CODES) {
+            // Make up some TX data
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Copy it and corrupt some bits
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+
+            // Allocate working area and output area
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut output = vec![0u8; code.output_len()];
+
+            // Run decoder
+            let (success, _) = code.decode_bf(&rxcode, &mut output, &mut working, 50);
+
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+
+    }
+
+    #[test]
+    fn test_decode_gallager_b() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+
+            let mut working = vec![0u8; code.decode_gallager_b_working_len()];
+            let mut output = vec![0u8; code.output_len()];
+
+            // An uncorrupted codeword is returned unchanged
+            let (success, _) = code.decode_gallager_b(&txcode, &mut output, &mut working,
+                                                      &[2], 50);
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+
+            let (success, _) = code.decode_gallager_b(&rxcode, &mut output, &mut working,
+                                                      &[4, 3], 50);
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tm1280")]
+    fn test_decode_bf_bools() {
+        use crate::bits::unpack_bools;
+        let code = LDPCCode::TM1280;
+        let txdata: Vec<bool> = (0..code.k()).map(|x| x % 3 == 0).collect();
+        let mut txcode = vec![0u8; code.n()/8];
+        code.copy_encode_bools(&txdata, &mut txcode);
+        let mut rxbits = vec![false; code.n()];
+        unpack_bools(&txcode, &mut rxbits);
+        assert_eq!(&rxbits[..code.k()], &txdata[..]);
+
+        rxbits[3] = !rxbits[3];
+        rxbits[700] = !rxbits[700];
+        let mut working = vec![0u8; code.decode_bf_working_len()];
+        let mut output = vec![0u8; code.output_len()];
+        let (success, _) = code.decode_bf_bools(&rxbits, &mut output, &mut working, 50);
+        assert!(success);
+        assert_eq!(&txcode[..], &output[..txcode.len()]);
+    }
+
+    #[test]
+    fn test_decode_bf_erasures() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| (x * 3) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Erase every 17th bit, setting each to 1 as a demodulator might
+            let mut rxcode = txcode.clone();
+            let mut erased = vec![false; code.n()];
+            for bit in (0..code.n()).step_by(17) {
+                erased[bit] = true;
+                rxcode[bit/8] |= 1 << (7 - (bit%8));
+            }
+
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let (success, _) = code.decode_bf_erasures(&rxcode, &erased, &mut output,
+                                                       &mut working, 50);
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+
+            // With nothing erased, the result matches decode_bf exactly
+            let mut output_bf = vec![0u8; code.output_len()];
+            let erased = vec![false; code.n()];
+            let result = code.decode_bf_erasures(&rxcode, &erased, &mut output, &mut working, 10);
+            let result_bf = code.decode_bf(&rxcode, &mut output_bf, &mut working, 10);
+            assert_eq!(result, result_bf);
+            assert_eq!(output, output_bf);
+        }
+    }
+
+    #[test]
+    fn test_decode_bf_with_strategy() {
+        use super::BfStrategy;
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5;
+
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let mut output_bf = vec![0u8; code.output_len()];
+            for strategy in &[BfStrategy::FlipAll, BfStrategy::FlipLowest,
+                              BfStrategy::FlipHighest]
+            {
+                let (success, _) = code.decode_bf_with_strategy(&rxcode, &mut output,
+                                                                &mut working, 50, *strategy);
+                assert!(success);
+                assert_eq!(&txcode[..], &output[..txcode.len()]);
+            }
+
+            // The default must match decode_bf exactly, even when decoding fails
+            for x in &mut rxcode[..8] { *x ^= 0x5A }
+            let result = code.decode_bf_with_strategy(&rxcode, &mut output, &mut working, 10,
+                                                      BfStrategy::default());
+            let result_bf = code.decode_bf(&rxcode, &mut output_bf, &mut working, 10);
+            assert_eq!(result, result_bf);
+            assert_eq!(output, output_bf);
+        }
+    }
+
+    #[test]
+    fn test_decode_bf_single() {
+        use super::BfStrategy;
+        for code in &encodable(&[LDPCCode::TC256, LDPCCode::TM1280]) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| (x * 3) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[1] ^= 1<<6;
+            rxcode[5] ^= 1<<1;
+
+            // Try the parallel decoder first, falling back to the serial one with the same
+            // buffers
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let (mut success, _) = code.decode_bf(&rxcode, &mut output, &mut working, 1);
+            if !success {
+                success = code.decode_bf_single(&rxcode, &mut output, &mut working, 50).0;
+            }
+            assert!(success);
+            assert_eq!(&output[..txcode.len()], &txcode[..]);
+
+            let mut output_lowest = vec![0u8; code.output_len()];
+            let result = code.decode_bf_single(&rxcode, &mut output, &mut working, 50);
+            assert_eq!(result, code.decode_bf_with_strategy(&rxcode, &mut output_lowest,
+                                                            &mut working, 50,
+                                                            BfStrategy::FlipLowest));
+            assert_eq!(output, output_lowest);
+        }
+    }
+
+    #[test]
+    fn test_decode_bf_multi() {
+        for code in &encodable(&[LDPCCode::TC128, LDPCCode::TM1280]) {
+            // Several codewords with a different number of errors in each
+            let m = 6;
+            let mut rxcodes = Vec::new();
+            for i in 0..m {
+                let txdata: Vec<u8> = (0..code.k()/8).map(|x| (x * 7 + i) as u8).collect();
+                let mut txcode = vec![0u8; code.n()/8];
+                code.copy_encode(&txdata, &mut txcode);
+                for e in 0..i*3 {
+                    txcode[(e * 5) % (code.n()/8)] ^= 1 << (e % 8);
+                }
+                rxcodes.extend_from_slice(&txcode);
+            }
+
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut output = vec![0u8; m * code.output_len()];
+            let mut results = vec![(false, 0); m];
+            code.decode_bf_multi(m, &rxcodes, &mut output, &mut working, 20, &mut results);
+
+            // Each must match decoding that codeword alone
+            let mut output_one = vec![0u8; code.output_len()];
+            for (i, rxcode) in rxcodes.chunks(code.n()/8).enumerate() {
+                let result = code.decode_bf(rxcode, &mut output_one, &mut working, 20);
+                assert_eq!(results[i], result);
+                assert_eq!(&output[i*code.output_len()..][..code.output_len()], &output_one[..]);
+            }
+            assert!(results[0].0);
+        }
+    }
+
+    #[test]
+    fn test_decode_ms() {
+        for code in &encodable(&CODES) {
+            // Make up a TX codeword
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Copy it and corrupt some bits
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+
+            // Convert the hard data to LLRs
+            let mut llrs = vec![0i8; code.n()];
+            code.hard_to_llrs(&rxcode, &mut llrs);
+
+            // Allocate working area and output area
+            let mut working = vec![0i8; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.output_len() - code.k()/8];
+            let mut output = vec![0u8; code.output_len()];
+
+            // Run decoder
+            let (success, _) = code.decode_ms(&llrs, &mut output, &mut working,
+                                              &mut working_u8, 50);
+
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
+
+    #[test]
+    fn test_estimate_errors() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut codeword = vec![0u8; code.output_len()];
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+
+            let mut rxcode = txcode.clone();
+            assert_eq!(code.estimate_errors(&rxcode, &mut codeword, &mut working), 0);
+            assert_eq!(rxcode, txcode);
+
+            // One error gives a small non-zero estimate, and more errors a larger one
+            rxcode[3] ^= 1 << 4;
+            let one = code.estimate_errors(&rxcode, &mut codeword, &mut working);
+            assert!((1..=2).contains(&one));
+            rxcode[6] ^= 1 << 4;
+            rxcode[9] ^= 1 << 4;
+            let three = code.estimate_errors(&rxcode, &mut codeword, &mut working);
+            assert!(three > one);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tm2048")]
+    fn test_decode_ms_resume() {
+        let code = LDPCCode::TM2048;
+        let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+        let mut txcode = vec![0u8; code.n()/8];
+        code.copy_encode(&txdata, &mut txcode);
+        let mut rxcode = txcode.clone();
+        for i in 0..12 { rxcode[i * 19] ^= 1 << (i % 8) }
+        let mut llrs = vec![0i16; code.n()];
+        code.hard_to_llrs(&rxcode, &mut llrs);
+        let full_llrs = llrs.clone();
+
+        // Withhold the last quarter of the parity bits, which is too many to decode
+        for llr in &mut llrs[code.n() - code.n()/4..] { *llr = 0 }
+        let mut working = vec![0i16; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        let mut output = vec![0u8; code.output_len()];
+        let (success, _) = code.decode_ms(&llrs, &mut output, &mut working, &mut working_u8, 5);
+        assert!(!success);
+
+        // Once the withheld bits arrive, resuming finishes the decode
+        let (success, iters) = code.decode_ms_resume(&full_llrs, &mut output, &mut working,
+                                                     &mut working_u8, 50);
+        assert!(success);
+        assert!(iters < 50);
+        assert_eq!(&txcode[..], &output[..txcode.len()]);
+    }
+
+    #[test]
+    #[cfg(feature = "tc256")]
+    fn test_decode_ms_crc() {
+        use core::cell::Cell;
+
+        let code = LDPCCode::TC256;
+        let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+        let mut txcode = vec![0u8; code.n()/8];
+        code.copy_encode(&txdata, &mut txcode);
+        let mut rxcode = txcode.clone();
+        rxcode[0] ^= 1<<7 | 1<<5;
+        let mut llrs = vec![0i8; code.n()];
+        code.hard_to_llrs(&rxcode, &mut llrs);
+
+        let mut working = vec![0i8; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        let mut output = vec![0u8; code.output_len()];
+
+        // Accepting every codeword is the same as plain decode_ms
+        let result = code.decode_ms(&llrs, &mut output, &mut working, &mut working_u8, 50);
+        let crc_result = code.decode_ms_crc(&llrs, &mut output, &mut working, &mut working_u8,
+                                            50, |data| data == &txdata[..]);
+        assert_eq!(crc_result, result);
+        assert_eq!(&txcode[..], &output[..txcode.len()]);
+
+        // Rejecting the first codeword found makes the decoder keep going
+        let calls = Cell::new(0);
+        let (success, iters) = code.decode_ms_crc(&llrs, &mut output, &mut working,
+                                                  &mut working_u8, 50, |_| {
+                                                      calls.set(calls.get() + 1);
+                                                      calls.get() > 1
+                                                  });
+        assert!(success);
+        assert_eq!(calls.get(), 2);
+        assert!(iters > result.1);
+
+        // Rejecting every codeword is a failure
+        let (success, iters) = code.decode_ms_crc(&llrs, &mut output, &mut working,
+                                                  &mut working_u8, 50, |_| false);
+        assert!(!success);
+        assert_eq!(iters, 50);
+    }
+
+    #[test]
+    #[cfg(feature = "tm1280")]
+    fn test_decode_ms_f32_f64_agree() {
+        let code = LDPCCode::TM1280;
+        let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+        let mut txcode = vec![0u8; code.n()/8];
+        code.copy_encode(&txdata, &mut txcode);
+        let mut rxcode = txcode.clone();
+        rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+        rxcode[9] ^= 1<<2;
+
+        let mut llrs32 = vec![0f32; code.n()];
+        let mut llrs64 = vec![0f64; code.n()];
+        code.hard_to_llrs(&rxcode, &mut llrs32);
+        code.hard_to_llrs(&rxcode, &mut llrs64);
+
+        let mut working32 = vec![0f32; code.decode_ms_working_len()];
+        let mut working64 = vec![0f64; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        let mut output32 = vec![0u8; code.output_len()];
+        let mut output64 = vec![0u8; code.output_len()];
+
+        let result32 = code.decode_ms(&llrs32, &mut output32, &mut working32,
+                                      &mut working_u8, 50);
+        let result64 = code.decode_ms(&llrs64, &mut output64, &mut working64,
+                                      &mut working_u8, 50);
+
+        assert!(result32.0);
+        assert_eq!(result32, result64);
+        assert_eq!(output32, output64);
+        assert_eq!(&txcode[..], &output64[..txcode.len()]);
+    }
+
+    #[test]
+    fn test_decode_ms_i8_full_scale() {
+        for code in &encodable(&CODES) {
+            // Make up a TX codeword
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Copy it and corrupt some bits
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+
+            // Convert to full-scale i8 LLRs, including i8::MIN for 1 bits
+            let mut llrs = vec![0i8; code.n()];
+            code.hard_to_llrs(&rxcode, &mut llrs);
+            for llr in &mut llrs[..] {
+                *llr = if *llr < 0 { i8::MIN } else { i8::MAX };
+            }
+
+            // Allocate working area and output area
+            let mut working = vec![0i8; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+
+            // Run decoder, which must not overflow
+            let (success, _) = code.decode_ms(&llrs, &mut output, &mut working,
+                                              &mut working_u8, 50);
+
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tm1280")]
+    fn test_ms_decoder() {
+        use super::MsDecoder;
+        let code = LDPCCode::TM1280;
+
+        // Wrongly sized working areas are rejected at construction
+        let mut working = vec![0i16; code.decode_ms_working_len() - 1];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        assert_eq!(MsDecoder::new(code, &mut working, &mut working_u8).err(),
+                   Some(DecodeError::BadBufferLen {
+                       which: "working",
+                       expected: code.decode_ms_working_len(),
+                       got: code.decode_ms_working_len() - 1,
+                   }));
+
+        // Make up a TX codeword, corrupt it, and convert to LLRs
+        let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+        let mut txcode = vec![0u8; code.n()/8];
+        code.copy_encode(&txdata, &mut txcode);
+        let mut rxcode = txcode.clone();
+        rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+        let mut llrs = vec![0i16; code.n()];
+        code.hard_to_llrs(&rxcode, &mut llrs);
+
+        let mut working = vec![0i16; code.decode_ms_working_len()];
+        let mut decoder = MsDecoder::new(code, &mut working, &mut working_u8).unwrap();
+        assert_eq!(decoder.code(), code);
+
+        // Wrongly sized inputs and outputs are rejected when decoding
+        let mut output = vec![0u8; code.output_len() + 1];
+        assert!(decoder.decode(&llrs, &mut output, 50).is_err());
+        assert!(decoder.decode(&llrs[1..], &mut output[1..], 50).is_err());
+
+        // The decoder can be reused many times
+        let mut output = vec![0u8; code.output_len()];
+        for _ in 0..2 {
+            let stats = decoder.decode(&llrs, &mut output, 50).unwrap();
+            assert!(stats.converged);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
+
+    #[test]
+    fn test_stream_decoder() {
+        use super::{MsDecoder, StreamDecoder};
+        use crate::encoder::{Encoder, StreamEncoder};
+
+        for code in &encodable(&CODES) {
+            let kb = code.k() / 8;
+            let data: Vec<u8> = (0..5*kb/2).map(|i| (i * 7) as u8).collect();
+
+            // Encode the stream, corrupting one bit of each codeword
+            let mut llrs = Vec::new();
+            let mut buf = vec![0u8; code.n()/8];
+            let mut encoder = StreamEncoder::new(Encoder::new(*code).unwrap(), &mut buf).unwrap();
+            let mut push = |c: &[u8]| {
+                let mut rx = c.to_vec();
+                rx[3] ^= 1 << 2;
+                let mut block = vec![0f32; code.n()];
+                code.hard_to_llrs(&rx, &mut block);
+                llrs.extend_from_slice(&block);
+            };
+            encoder.write(&data, &mut push);
+            encoder.finish(&mut push);
+            assert_eq!(llrs.len(), 3 * code.n());
+
+            let mut working = vec![0f32; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut buf = vec![0f32; code.n()];
+            let mut output = vec![0u8; code.output_len()];
+            let decoder = MsDecoder::new(*code, &mut working, &mut working_u8).unwrap();
+            let mut stream = StreamDecoder::new(decoder, &mut buf, &mut output, 20).unwrap();
+            stream.set_length(data.len());
+
+            // Feed in uneven pieces which straddle codeword boundaries
+            let mut rxdata = Vec::new();
+            let mut decoded = 0;
+            for chunk in llrs.chunks(code.n()/3 + 5) {
+                decoded += stream.write(chunk, |d, stats| {
+                    assert!(stats.converged);
+                    rxdata.extend_from_slice(d);
+                });
+            }
+            assert_eq!(decoded, 3);
+            assert_eq!(stream.buffered(), 0);
+            assert_eq!(rxdata, data);
+
+            // Once the length is reached further codewords are dropped
+            assert_eq!(stream.write(&llrs[..code.n()], |_, _| unreachable!()), 0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tc256")]
+    fn test_ms_decoder_batch() {
+        use super::{DecodeStats, MsDecoder};
+        let code = LDPCCode::TC256;
+        let mut working = vec![0i16; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        let mut decoder = MsDecoder::new(code, &mut working, &mut working_u8).unwrap();
+
+        // Make up three different codewords, each with a different corruption
+        let mut txcodes = Vec::new();
+        let mut llrs = Vec::new();
+        for i in 0..3 {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| (x * (i + 1)) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[i] ^= 1<<(7 - i);
+            let mut block = vec![0i16; code.n()];
+            code.hard_to_llrs(&rxcode, &mut block);
+            txcodes.push(txcode);
+            llrs.push(block);
+        }
+        let llrs_blocks: Vec<&[i16]> = llrs.iter().map(|b| &b[..]).collect();
+        let mut outputs = vec![vec![0u8; code.output_len()]; 3];
+        let mut stats = [DecodeStats { iterations: 0, converged: false }; 3];
+
+        // Mismatched batch sizes are rejected before anything is decoded
+        {
+            let mut output_slices: Vec<&mut [u8]> = outputs.iter_mut().map(|o| &mut o[..])
+                                                                      .collect();
+            assert!(decoder.decode_batch(&llrs_blocks, &mut output_slices, &mut stats[..2], 50)
+                           .is_err());
+            assert!(decoder.decode_batch(&llrs_blocks, &mut output_slices, &mut stats, 50)
+                           .is_ok());
+        }
+
+        for (i, txcode) in txcodes.iter().enumerate() {
+            assert!(stats[i].converged);
+            assert_eq!(&txcode[..], &outputs[i][..txcode.len()]);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "alloc", feature = "tm1280"))]
+    fn test_owned_ms_decoder() {
+        let code = LDPCCode::TM1280;
+        let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+        let mut txcode = vec![0u8; code.n()/8];
+        code.copy_encode(&txdata, &mut txcode);
+        let mut rxcode = txcode.clone();
+        rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+        let mut llrs = vec![0i16; code.n()];
+        code.hard_to_llrs(&rxcode, &mut llrs);
+
+        let mut decoder = code.ms_decoder_alloc::<i16>();
+        assert_eq!(decoder.code(), code);
+        let mut output = vec![0u8; code.output_len()];
+        let stats = decoder.decode(&llrs, &mut output, 50).unwrap();
+        assert!(stats.converged);
+        assert_eq!(&txcode[..], &output[..txcode.len()]);
+        assert!(decoder.decode(&llrs[1..], &mut output, 50).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "tc128")]
+    fn test_decode_stats() {
+        use super::DecodeStats;
+        let code = LDPCCode::TC128;
+        let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+        let mut txcode = vec![0u8; code.n()/8];
+        code.copy_encode(&txdata, &mut txcode);
+
+        // A single bit error is fixed by the first round of flips
+        let mut rxcode = txcode.clone();
+        rxcode[3] ^= 1<<2;
+        let mut working = vec![0u8; code.decode_bf_working_len()];
+        let mut output = vec![0u8; code.output_len()];
+
+        // With no iterations allowed, we can only report the codeword is invalid
+        let stats = code.decode_bf_stats(&rxcode, &mut output, &mut working, 0);
+        assert_eq!(stats, DecodeStats { iterations: 0, converged: false });
+
+        // A single iteration's flips must be recognised as having converged
+        let stats = code.decode_bf_stats(&rxcode, &mut output, &mut working, 1);
+        assert_eq!(stats, DecodeStats { iterations: 1, converged: true });
+        assert_eq!(&txcode[..], &output[..txcode.len()]);
+
+        // The min-sum decoder reports the same way
+        let mut llrs = vec![0f32; code.n()];
+        code.hard_to_llrs(&rxcode, &mut llrs);
+        let mut working = vec![0f32; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        let stats = code.decode_ms_stats(&llrs, &mut output, &mut working, &mut working_u8, 50);
+        assert!(stats.converged);
+        assert_eq!(&txcode[..], &output[..txcode.len()]);
+    }
+
+    #[test]
+    fn test_decode_ms_normalized() {
+        for code in &encodable(&CODES) {
+            // Make up a TX codeword
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Copy it and corrupt some bits
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+
+            // Convert the hard data to LLRs, leaving room for scaling in i16
+            let mut llrs = vec![0i16; code.n()];
+            code.hard_to_llrs(&rxcode, &mut llrs);
+            for llr in &mut llrs[..] { *llr *= 16 }
+
+            let mut working = vec![0i16; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let mut output_ms = vec![0u8; code.output_len()];
+
+            // An alpha of 1.0 must be identical to plain min-sum
+            let result = code.decode_ms_normalized(&llrs, &mut output, &mut working,
+                                                   &mut working_u8, 50, 1.0);
+            let result_ms = code.decode_ms(&llrs, &mut output_ms, &mut working,
+                                           &mut working_u8, 50);
+            assert_eq!(result, result_ms);
+            assert_eq!(output, output_ms);
+
+            // A typical alpha still decodes correctly
+            let (success, _) = code.decode_ms_normalized(&llrs, &mut output, &mut working,
+                                                         &mut working_u8, 50, 0.8);
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
+
+    #[test]
+    fn test_decode_ms_clamped() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+
+            // Badly scaled LLRs, close to the limit of f32
+            let mut llrs = vec![0f32; code.n()];
+            code.hard_to_llrs_scaled(&rxcode, 1e38, &mut llrs);
+
+            let mut working = vec![0f32; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let mut output_ms = vec![0u8; code.output_len()];
+
+            let (success, _) = code.decode_ms_clamped(&llrs, &mut output, &mut working,
+                                                      &mut working_u8, 50, 16.0);
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+
+            // A clamp which is never reached must be identical to plain min-sum
+            code.hard_to_llrs(&rxcode, &mut llrs);
+            let result = code.decode_ms_clamped(&llrs, &mut output, &mut working,
+                                                &mut working_u8, 50, 1e6);
+            let result_ms = code.decode_ms(&llrs, &mut output_ms, &mut working,
+                                           &mut working_u8, 50);
+            assert_eq!(result, result_ms);
+            assert_eq!(output, output_ms);
+        }
+    }
+
+    #[test]
+    fn test_decode_ms_masked() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+            let mut llrs = vec![0f32; code.n()];
+            code.hard_to_llrs(&rxcode, &mut llrs);
+
+            let checks = code.n() + code.punctured_bits() - code.k();
+            let mut working = vec![0f32; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let mut output_ms = vec![0u8; code.output_len()];
+
+            // With every check enabled we must match plain min-sum
+            let enabled = vec![true; checks];
+            let result = code.decode_ms_masked(&llrs, &mut output, &mut working,
+                                               &mut working_u8, &enabled, 50);
+            let result_ms = code.decode_ms(&llrs, &mut output_ms, &mut working,
+                                           &mut working_u8, 50);
+            assert_eq!(result, result_ms);
+            assert_eq!(output, output_ms);
+
+            // With every check disabled we immediately succeed with the hard decisions
+            let disabled = vec![false; checks];
+            let result = code.decode_ms_masked(&llrs, &mut output, &mut working,
+                                               &mut working_u8, &disabled, 50);
+            assert_eq!(result, (true, 0));
+            assert_eq!(&rxcode[..], &output[..rxcode.len()]);
+
+            // Dropping a few checks still corrects a few errors
+            let mut enabled = vec![true; checks];
+            for idx in (0..checks).step_by(checks / 4) {
+                enabled[idx] = false;
+            }
+            let (success, _) = code.decode_ms_masked(&llrs, &mut output, &mut working,
+                                                     &mut working_u8, &enabled, 50);
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
+
+    #[test]
+    fn test_decode_ms_shortened() {
+        for code in &encodable(&CODES) {
+            let info_bits = code.k() / 4;
+            let data: Vec<u8> = (0..info_bits/8).map(|x| x as u8).collect();
+            let mut codeword = vec![0u8; code.n()/8];
+            let mut txcode = vec![0u8; code.shortened_len(info_bits)];
+            code.encode_shortened(&data, info_bits, &mut codeword, &mut txcode);
+
+            let mut working_i8 = vec![0i8; code.decode_ms_working_len()];
+            let mut working_f32 = vec![0f32; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+
+            // Corrupt some message and parity bits
+            txcode[0] ^= 1<<7 | 1<<2;
+            txcode[info_bits/8 + 5] ^= 1<<4;
+            let mut llrs_i8 = vec![0i8; code.n() - code.k() + info_bits];
+            let mut llrs_f32 = vec![0f32; llrs_i8.len()];
+            for (idx, (a, b)) in llrs_i8.iter_mut().zip(llrs_f32.iter_mut()).enumerate() {
+                let bit = (txcode[idx/8] >> (7-(idx%8))) & 1;
+                *a = if bit == 1 { -32 } else { 32 };
+                *b = if bit == 1 { -1.0 } else { 1.0 };
+            }
+
+            let (success, _) = code.decode_ms_shortened(&llrs_i8, info_bits, &mut output,
+                                                         &mut working_i8, &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..code.n()/8], &codeword[..]);
+            let (success, _) = code.decode_ms_shortened(&llrs_f32, info_bits, &mut output,
+                                                        &mut working_f32, &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..code.n()/8], &codeword[..]);
+
+            // The padding is zero even when decoding fails
+            let (success, _) = code.decode_ms_shortened(&llrs_i8, info_bits, &mut output,
+                                                         &mut working_i8, &mut working_u8, 0);
+            assert!(!success);
+            assert!(output[info_bits/8..code.k()/8].iter().all(|&x| x == 0));
+        }
+    }
+
+    #[test]
+    fn test_decode_ms_q() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+
+            // Q4.12 LLRs of varying magnitude, and the same values as f32
+            let mut llrs_q = vec![0i16; code.n()];
+            code.hard_to_llrs_scaled(&rxcode, 3 << 11, &mut llrs_q);
+            for (idx, llr) in llrs_q.iter_mut().enumerate() {
+                *llr += (idx % 7) as i16 * 100 * llr.signum();
+            }
+            let llrs_f: Vec<f32> = llrs_q.iter().map(|&x| f32::from(x) / 4096.0).collect();
+
+            let mut working = vec![0f32; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let mut output_f = vec![0u8; code.output_len()];
+            let result = code.decode_ms_q(&llrs_q, 12, &mut output, &mut working,
+                                          &mut working_u8, 50);
+            let app = working.clone();
+            let result_f = code.decode_ms(&llrs_f, &mut output_f, &mut working,
+                                          &mut working_u8, 50);
+            assert!(result.0);
+            assert_eq!(result, result_f);
+            assert_eq!(output, output_f);
+            assert_eq!(app, working);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
+
+    #[test]
+    fn test_decode_ms_damped() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+            let mut llrs = vec![0i16; code.n()];
+            code.hard_to_llrs_scaled(&rxcode, 64, &mut llrs);
+
+            let mut working = vec![0i16; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let mut output_ms = vec![0u8; code.output_len()];
+
+            let (success, _) = code.decode_ms_damped(&llrs, &mut output, &mut working,
+                                                     &mut working_u8, 50, 0.25);
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+
+            // No damping must be identical to plain min-sum
+            let result = code.decode_ms_damped(&llrs, &mut output, &mut working,
+                                               &mut working_u8, 50, 0.0);
+            let result_ms = code.decode_ms(&llrs, &mut output_ms, &mut working,
+                                           &mut working_u8, 50);
+            assert_eq!(result, result_ms);
+            assert_eq!(output, output_ms);
+        }
+    }
+
+    #[test]
+    fn test_decode_ms_offset() {
+        for code in &encodable(&CODES) {
+            // Make up a TX codeword
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Copy it and corrupt some bits
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+
+            // Convert the hard data to Q4.4 LLRs in i8
+            let mut llrs = vec![0i8; code.n()];
+            code.hard_to_llrs(&rxcode, &mut llrs);
+            for llr in &mut llrs[..] { *llr *= 16 }
+
+            let mut working = vec![0i8; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let mut output_ms = vec![0u8; code.output_len()];
+
+            // A beta of 0 must be identical to plain min-sum
+            let result = code.decode_ms_offset(&llrs, &mut output, &mut working,
+                                               &mut working_u8, 50, 0);
+            let result_ms = code.decode_ms(&llrs, &mut output_ms, &mut working,
+                                           &mut working_u8, 50);
+            assert_eq!(result, result_ms);
+            assert_eq!(output, output_ms);
+
+            // A typical beta still decodes correctly
+            let (success, _) = code.decode_ms_offset(&llrs, &mut output, &mut working,
+                                                     &mut working_u8, 50, 3);
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_bsc_to_llrs() {
+        let code = LDPCCode::TC128;
+        let mut input = vec![0u8; code.n()/8];
+        input[0] = 0x80;
+        let mut llrs = vec![0f32; code.n()];
+        code.bsc_to_llrs(&input, 0.1, &mut llrs);
+        let magnitude = 9.0f32.ln();
+        assert!((llrs[0] + magnitude).abs() < 1e-6);
+        assert!(llrs[1..].iter().all(|x| (x - magnitude).abs() < 1e-6));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_decode_sp() {
+        for code in &encodable(&CODES) {
+            // Make up a TX codeword
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Copy it and corrupt some bits
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+
+            // Convert the hard data to LLRs for a channel with a 5% error rate
+            let mut llrs = vec![0f64; code.n()];
+            code.hard_to_llrs(&rxcode, &mut llrs);
+            for llr in &mut llrs[..] { *llr *= (0.95f64 / 0.05).ln() }
+
+            let mut working = vec![0f64; code.decode_sp_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_sp_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+
+            let (success, _) = code.decode_sp(&llrs, &mut output, &mut working,
+                                              &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
+
+    #[test]
+    fn test_decode_ms_lookup() {
+        for code in &encodable(&CODES) {
+            // The lookup table must match the parity check iterator
+            let mut table = vec![0u16; code.ms_lookup_len()];
+            code.init_ms_lookup(&mut table);
+            for (entry, (check, var)) in table.chunks(2).zip(code.iter_paritychecks()) {
+                assert_eq!((entry[0] as usize, entry[1] as usize), (check, var));
+            }
+
+            // Filling it one step at a time must give an identical table
+            let mut stepped = vec![0u16; code.ms_lookup_len()];
+            let mut pos = 0;
+            let mut steps = 0;
+            while pos < stepped.len() {
+                pos = code.init_ms_lookup_step(&mut stepped, pos);
+                steps += 1;
+            }
+            assert_eq!(stepped, table);
+            assert_eq!(steps * code.submatrix_size(), code.n() + code.punctured_bits() - code.k());
+            assert_eq!(code.init_ms_lookup_step(&mut stepped, pos), pos);
+
+            // Make up a TX codeword
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Copy it and corrupt some bits
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+            let mut llrs = vec![0i8; code.n()];
+            code.hard_to_llrs(&rxcode, &mut llrs);
+
+            let mut working = vec![0i8; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let mut output_ms = vec![0u8; code.output_len()];
+
+            // Results must be identical to plain min-sum
+            let result = code.decode_ms_lookup(&table, &llrs, &mut output, &mut working,
+                                               &mut working_u8, 50);
+            let result_ms = code.decode_ms(&llrs, &mut output_ms, &mut working,
+                                           &mut working_u8, 50);
+            assert_eq!(result, result_ms);
+            assert_eq!(output, output_ms);
+            assert!(result.0);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
+
+    #[test]
+    fn test_decode_ms_reference() {
+        fn check<T: DecodeFrom + core::fmt::Debug>(code: LDPCCode, llrs: &[T], maxiters: usize) {
+            let mut table = vec![0u16; code.ms_lookup_len()];
+            code.init_ms_lookup(&mut table);
+            let mut working = vec![T::zero(); code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output_ref = vec![0u8; code.output_len()];
+            let mut output = vec![0u8; code.output_len()];
+
+            let result_ref = code.decode_ms_reference(llrs, &mut output_ref, &mut working,
+                                                      &mut working_u8, maxiters);
+            let result = code.decode_ms(llrs, &mut output, &mut working, &mut working_u8,
+                                        maxiters);
+            assert_eq!((result, &output), (result_ref, &output_ref));
+            let result = code.decode_ms_lookup(&table, llrs, &mut output, &mut working,
+                                               &mut working_u8, maxiters);
+            assert_eq!((result, &output), (result_ref, &output_ref));
+        }
+
+        // Simple LCG so the test is repeatable
+        let mut state = 12345u32;
+        let mut rand = move || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as i32 % 64 - 32
+        };
+
+        // The reference decoder is slow, so only check the smaller codes
+        let codes = [LDPCCode::TC128, LDPCCode::TC256, LDPCCode::TC512];
+        for &code in &encodable(&codes) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|i| (i * 29) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut clean = vec![0i16; code.n()];
+            code.hard_to_llrs_scaled(&txcode, 24, &mut clean);
+
+            // Noisy codewords at several noise levels, some of which fail to decode, and
+            // completely random LLRs which never decode
+            for noise in &[8, 16, 24, 32] {
+                let llrs: Vec<i16> = clean.iter().map(|&x| x + (rand() * noise) as i16 / 16)
+                                          .collect();
+                check(code, &llrs, 20);
+                check(code, &llrs.iter().map(|&x| x as i8).collect::<Vec<i8>>(), 20);
+                check(code, &llrs.iter().map(|&x| x as f32 / 8.0).collect::<Vec<f32>>(), 20);
+            }
+            let llrs: Vec<i8> = (0..code.n()).map(|_| rand() as i8).collect();
+            check(code, &llrs, 5);
+        }
+    }
+
+    #[test]
+    fn test_decode_clean() {
+        // Error-free all-zero and all-ones data must decode immediately, without the
+        // decoders special-casing either
+        for code in &encodable(&CODES) {
+            for &fill in &[0x00, 0xFF] {
+                let txdata = vec![fill; code.k()/8];
+                let mut txcode = vec![0u8; code.n()/8];
+                code.copy_encode(&txdata, &mut txcode);
+                let iters = if code.punctured_bits() == 0 { 0 } else { 1 };
+
+                let mut output = vec![0u8; code.output_len()];
+                let mut working = vec![0u8; code.decode_bf_working_len()];
+                assert_eq!(code.decode_bf(&txcode, &mut output, &mut working, 20), (true, iters));
+                assert_eq!(&output[..txcode.len()], &txcode[..]);
+
+                let mut llrs = vec![0i8; code.n()];
+                code.hard_to_llrs_scaled(&txcode, 16, &mut llrs);
+                let mut output = vec![0u8; code.output_len()];
+                let mut working = vec![0i8; code.decode_ms_working_len()];
+                let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+                let (success, ms_iters) = code.decode_ms(&llrs, &mut output, &mut working,
+                                                         &mut working_u8, 20);
+                assert!(success && ms_iters <= iters);
+                assert_eq!(&output[..txcode.len()], &txcode[..]);
+                assert_eq!(code.decode_ms_layered(&llrs, &mut output, &mut working,
+                                                  &mut working_u8, 20), (true, 0));
+                assert_eq!(&output[..txcode.len()], &txcode[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_ms_fast_path() {
+        for code in &encodable(&[LDPCCode::TC128, LDPCCode::TC256, LDPCCode::TC512]) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|i| (i * 13) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut llrs = vec![0i8; code.n()];
+            code.hard_to_llrs_scaled(&txcode, 100, &mut llrs);
+
+            // A clean input stops at once, leaving the same APP LLRs as a first iteration
+            let mut output = vec![0u8; code.output_len()];
+            let mut app_llrs = vec![0i8; code.n()];
+            let mut working = vec![0i8; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            assert_eq!(code.decode_ms_soft(&llrs, &mut output, &mut app_llrs, &mut working,
+                                           &mut working_u8, 1), (true, 0));
+            assert_eq!(&output[..], &txcode[..]);
+            assert!(app_llrs.iter().all(|&x| x == 100 || x == -100));
+
+            // A codeword rejected by the caller's own check still runs the full decoder
+            let result = code.decode_ms_crc(&llrs, &mut output, &mut working, &mut working_u8,
+                                            5, |_| false);
+            assert_eq!(result, (false, 5));
+        }
+    }
+
+    #[test]
+    fn test_check_node_update() {
+        use super::check_node_update;
+
+        // Each reply is the smallest other magnitude with the sign of the other messages
+        let mut out = [0i8; 4];
+        check_node_update(&[3i8, -1, 5, -2], &mut out, 1.0);
+        assert_eq!(out, [1, -2, 1, -1]);
+        check_node_update(&[3i8, -1, 5, 0], &mut out, 1.0);
+        assert_eq!(out, [0, 0, 0, -1]);
+
+        let mut out = [0f32; 3];
+        check_node_update(&[-2.0f32, 4.0, 8.0], &mut out, 0.75);
+        assert_eq!(out, [3.0, -1.5, -1.5]);
+
+        // A single message has nothing to draw on
+        let mut out = [0i16; 1];
+        check_node_update(&[7i16], &mut out, 1.0);
+        assert_eq!(out, [i16::MAX]);
+    }
+
+    #[test]
+    fn test_decode_k16384() {
+        // No generator is included for these codes, so decode the all-zero codeword.
+        for code in &[LDPCCode::TM20480, LDPCCode::TM24576, LDPCCode::TM32768] {
+            let mut rxcode = vec![0u8; code.n()/8];
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+            let mut llrs = vec![0i8; code.n()];
+            code.hard_to_llrs(&rxcode, &mut llrs);
+            let mut working = vec![0i8; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let (success, _) = code.decode_ms(&llrs, &mut output, &mut working,
+                                              &mut working_u8, 50);
+            assert!(success);
+            assert!(output.iter().all(|x| *x == 0));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tc128")]
+    fn test_llr_conversions() {
+        let code = LDPCCode::TC128;
+        let txdata: Vec<u8> = (0..8).collect();
+        let mut txcode = vec![0u8; 16];
+        code.copy_encode(&txdata, &mut txcode);
+
+        let mut llrs = vec![0i16; code.n()];
+        code.hard_to_llrs_scaled(&txcode, 100, &mut llrs);
+        assert!(llrs.iter().all(|x| *x == 100 || *x == -100));
+        let mut hard = vec![0u8; 16];
+        code.llrs_to_hard(&llrs, &mut hard);
+        assert_eq!(hard, txcode);
+
+        // Map to BPSK with a small offset on each sample and check the LLRs
+        let samples: Vec<f32> = llrs.iter().map(|x| f32::from(*x) / 100.0 * 0.75).collect();
+        let mut llrs = vec![0f32; code.n()];
+        code.bpsk_awgn_to_llrs(&samples, 0.5, &mut llrs);
+        assert!(llrs.iter().all(|x| *x == 3.0 || *x == -3.0));
+        code.llrs_to_hard(&llrs, &mut hard);
+        assert_eq!(hard, txcode);
+    }
+
+    #[test]
+    fn test_decode_ms_hard() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+
+            let mut working = vec![0i16; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let result = code.decode_ms_hard(&rxcode, 4, &mut output, &mut working,
+                                             &mut working_u8, 50);
+            assert!(result.0);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+
+            // Must match converting to LLRs first
+            let mut llrs = vec![0i16; code.n()];
+            code.hard_to_llrs_scaled(&rxcode, 4, &mut llrs);
+            let mut output_ms = vec![0u8; code.output_len()];
+            let result_ms = code.decode_ms(&llrs, &mut output_ms, &mut working,
+                                           &mut working_u8, 50);
+            assert_eq!(result, result_ms);
+            assert_eq!(output, output_ms);
+        }
+    }
+
+    #[test]
+    fn test_syndrome() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Decode to recover any punctured bits
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut output = vec![0u8; code.output_len()];
+            assert!(code.decode_bf(&txcode, &mut output, &mut working, 50).0);
+
+            let mut syndrome = vec![0xFFu8; code.syndrome_len()];
+            assert!(code.compute_syndrome(&output, &mut syndrome));
+            assert!(syndrome.iter().all(|s| *s == 0));
+            assert_eq!(code.count_unsatisfied_checks(&output, &mut syndrome), 0);
+
+            // Flipping a bit must fail exactly the checks that bit takes part in
+            output[0] ^= 1<<7;
+            assert!(!code.compute_syndrome(&output, &mut syndrome));
+            let mut expected = vec![0u8; code.syndrome_len()];
+            for (check, var) in code.iter_paritychecks() {
+                if var == 0 {
+                    expected[check/8] ^= 1 << (7-(check%8));
+                }
+            }
+            assert_eq!(syndrome, expected);
+            let degree = code.iter_paritychecks().filter(|&(_, var)| var == 0).count();
+            assert_eq!(code.count_unsatisfied_checks(&output, &mut syndrome), degree);
+        }
+    }
+
+    #[test]
+    fn test_is_codeword() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| (x * 5) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut output = vec![0u8; code.output_len()];
+            assert!(code.decode_bf(&txcode, &mut output, &mut working, 50).0);
+
+            let mut syndrome = vec![0u8; code.syndrome_len()];
+            assert!(code.is_codeword(&output, &mut syndrome));
+
+            // Any flipped bit must be caught, wherever its checks are
+            for &bit in &[0, code.k(), code.n() - 1] {
+                output[bit/8] ^= 1 << (7 - (bit%8));
+                assert!(!code.is_codeword(&output, &mut syndrome));
+                assert!(!code.compute_syndrome(&output, &mut syndrome));
+                output[bit/8] ^= 1 << (7 - (bit%8));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tm1280")]
+    fn test_decode_ms_apriori() {
+        let code = LDPCCode::TM1280;
+        let txdata: Vec<u8> = (0..code.k()/8).map(|x| (x * 3) as u8).collect();
+        let mut txcode = vec![0u8; code.n()/8];
+        code.copy_encode(&txdata, &mut txcode);
+        let mut working = vec![0f32; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        let mut output = vec![0u8; code.output_len()];
+
+        // With no a-priori information this is decode_ms, and the extrinsic LLRs are the
+        // a-posteriori LLRs less the channel LLRs
+        let mut rxcode = txcode.clone();
+        rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+        let mut llrs = vec![0f32; code.n()];
+        code.hard_to_llrs(&rxcode, &mut llrs);
+        let mut app_llrs = vec![0f32; code.n()];
+        let mut soft_output = vec![0u8; code.output_len()];
+        let mut priors = vec![0f32; code.n()];
+        let result = code.decode_ms_apriori(&llrs, &mut priors, &mut output, &mut working,
+                                            &mut working_u8, 50);
+        assert_eq!(result, code.decode_ms_soft(&llrs, &mut soft_output, &mut app_llrs,
+                                               &mut working, &mut working_u8, 50));
+        assert!(result.0);
+        assert_eq!(output, soft_output);
+        for ((extrinsic, app), llr) in priors.iter().zip(&app_llrs).zip(&llrs) {
+            assert_eq!(*extrinsic, app - llr);
+        }
+
+        // A-priori information on the data bits rescues a codeword the channel alone cannot
+        let mut rxcode = txcode.clone();
+        for byte in rxcode.iter_mut().step_by(5) { *byte ^= 0x81 }
+        code.hard_to_llrs(&rxcode, &mut llrs);
+        let (success, _) = code.decode_ms(&llrs, &mut output, &mut working, &mut working_u8, 50);
+        assert!(!success);
+        let mut priors = vec![0f32; code.n()];
+        for idx in 0..code.k() {
+            priors[idx] = if (txcode[idx/8] >> (7-(idx%8))) & 1 == 1 { -2.0 } else { 2.0 };
+        }
+        let (success, _) = code.decode_ms_apriori(&llrs, &mut priors, &mut output,
+                                                  &mut working, &mut working_u8, 50);
+        assert!(success);
+        assert_eq!(&output[..txcode.len()], &txcode[..]);
+    }
+
+    #[test]
+    fn test_decode_ms_apriori_saturated() {
+        // The channel and a-priori LLRs of most bits agree and together use most of T's range,
+        // so their a-posteriori LLRs saturate, but the extrinsic LLRs must still carry the
+        // full check messages rather than being limited to the remaining range
+        for code in &encodable(&[LDPCCode::TC256, LDPCCode::TM1280]) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| (x * 11) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[1] ^= 1<<6 | 1<<1;
+            let mut output = vec![0u8; code.output_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let agree = |idx: &usize| (txcode[idx/8] ^ rxcode[idx/8]) >> (7-(idx%8)) & 1 == 0;
+
+            let mut llrs = vec![0i8; code.n()];
+            let mut priors = vec![0i8; code.n()];
+            code.hard_to_llrs_scaled(&rxcode, 30, &mut llrs);
+            code.hard_to_llrs_scaled(&txcode, 30, &mut priors);
+            let mut working = vec![0i8; code.decode_ms_working_len()];
+            let (success, _) = code.decode_ms_apriori(&llrs, &mut priors, &mut output,
+                                                      &mut working, &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..txcode.len()], &txcode[..]);
+            assert!((0..code.n()).filter(agree).any(|idx| priors[idx].abs() > i8::MAX - 60));
+
+            let mut llrs = vec![0i16; code.n()];
+            let mut priors = vec![0i16; code.n()];
+            code.hard_to_llrs_scaled(&rxcode, 30 * 256, &mut llrs);
+            code.hard_to_llrs_scaled(&txcode, 30 * 256, &mut priors);
+            let mut working = vec![0i16; code.decode_ms_working_len()];
+            let (success, _) = code.decode_ms_apriori(&llrs, &mut priors, &mut output,
+                                                      &mut working, &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..txcode.len()], &txcode[..]);
+            assert!((0..code.n()).filter(agree).any(|idx| priors[idx].abs() > i16::MAX - 60*256));
+        }
+    }
+
+    #[test]
+    fn test_decode_ms_large_llrs() {
+        // LLRs at half of T's range, so that the accumulated marginals exceed it, still decode
+        // with both the flooding and layered schedules
+        for code in &encodable(&[LDPCCode::TM1280, LDPCCode::TM8192]) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| (x * 7) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            for byte in rxcode.iter_mut().step_by(23) { *byte ^= 0x10 }
+            let mut output = vec![0u8; code.output_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+
+            let mut llrs = vec![0i8; code.n()];
+            code.hard_to_llrs_scaled(&rxcode, i8::MAX / 2, &mut llrs);
+            let mut working = vec![0i8; code.decode_ms_working_len()];
+            let (success, _) = code.decode_ms(&llrs, &mut output, &mut working,
+                                              &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..txcode.len()], &txcode[..]);
+            let (success, _) = code.decode_ms_layered(&llrs, &mut output, &mut working,
+                                                      &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..txcode.len()], &txcode[..]);
+
+            let mut llrs = vec![0i16; code.n()];
+            code.hard_to_llrs_scaled(&rxcode, i16::MAX / 2, &mut llrs);
+            let mut working = vec![0i16; code.decode_ms_working_len()];
+            let (success, _) = code.decode_ms(&llrs, &mut output, &mut working,
+                                              &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..txcode.len()], &txcode[..]);
+            let (success, _) = code.decode_ms_layered(&llrs, &mut output, &mut working,
+                                                      &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..txcode.len()], &txcode[..]);
+
+            let mut llrs = vec![0f32; code.n()];
+            code.hard_to_llrs_scaled(&rxcode, f32::MAX / 2.0, &mut llrs);
+            let mut working = vec![0f32; code.decode_ms_working_len()];
+            let (success, _) = code.decode_ms(&llrs, &mut output, &mut working,
+                                              &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..txcode.len()], &txcode[..]);
+            let (success, _) = code.decode_ms_layered(&llrs, &mut output, &mut working,
+                                                      &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..txcode.len()], &txcode[..]);
         }
-        (false, maxiters)
     }
 
-    /// Convert hard information into LLRs.
-    ///
-    /// The min-sum decoding used in `decode_ms` is invariant to linear scaling
-    /// in LLR, so it doesn't matter which value is picked so long as the sign
-    /// is correct. This function just assigns -/+ 1 for 1/0 bits.
-    ///
-    /// `input` must be n/8 long, `llrs` must be n long.
-    pub fn hard_to_llrs<T: DecodeFrom>(self, input: &[u8], llrs: &mut [T]) {
-        assert_eq!(input.len(), self.n()/8, "input.len() != n/8");
-        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
-        let llr = -T::one();
-        for (idx, byte) in input.iter().enumerate() {
-            for i in 0..8 {
-                llrs[idx*8 + i] = if (byte >> (7-i)) & 1 == 1 { llr } else { -llr };
-            }
+    #[test]
+    fn test_default_max_iters() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| (x * 5) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+            let mut rxcode = txcode.clone();
+            rxcode[1] ^= 1<<6 | 1<<2;
+
+            let mut llrs = vec![0i8; code.n()];
+            code.hard_to_llrs(&rxcode, &mut llrs);
+            let mut working = vec![0i8; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let (success, iters) = code.decode_ms(&llrs, &mut output, &mut working,
+                                                  &mut working_u8, LDPCCode::DEFAULT_MAX_ITERS);
+            assert!(success);
+            assert!(iters <= LDPCCode::DEFAULT_MAX_ITERS);
+            assert_eq!(&output[..txcode.len()], &txcode[..]);
         }
     }
 
-    /// Convert LLRs into hard information.
-    ///
-    /// `llrs` must be n long, `output` must be n/8 long.
-    pub fn llrs_to_hard<T: DecodeFrom>(self, llrs: &[T], output: &mut [u8]) {
-        assert_eq!(llrs.len(), self.n(), "llrs.len() != n");
-        assert_eq!(output.len(), self.n()/8, "output.len() != n/8");
+    #[test]
+    fn test_decode_ms_soft() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
 
-        for o in &mut output[..] { *o = 0 }
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+            let mut llrs = vec![0f32; code.n()];
+            code.hard_to_llrs(&rxcode, &mut llrs);
 
-        for (i, llr) in llrs.iter().enumerate() {
-            if *llr < T::zero() {
-                output[i/8] |= 1 << (7 - (i%8));
+            let mut working = vec![0f32; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let mut app_llrs = vec![0f32; code.n()];
+            let (success, _) = code.decode_ms_soft(&llrs, &mut output, &mut app_llrs,
+                                                   &mut working, &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+
+            // Hard decisions on the APP LLRs must match the output
+            for (idx, llr) in app_llrs.iter().enumerate() {
+                let bit = (output[idx/8] >> (7-(idx%8))) & 1;
+                assert_eq!(bit == 1, *llr <= 0.0);
+            }
+
+            // Including the punctured bits, which are never transmitted
+            let mut all_llrs = vec![0f32; code.n() + code.punctured_bits()];
+            code.decode_ms_soft(&llrs, &mut output, &mut all_llrs, &mut working,
+                                &mut working_u8, 50);
+            assert_eq!(&all_llrs[..code.n()], &app_llrs[..]);
+            for (idx, llr) in all_llrs.iter().enumerate().skip(code.n()) {
+                let bit = (output[idx/8] >> (7-(idx%8))) & 1;
+                assert_eq!(bit == 1, *llr <= 0.0);
             }
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::prelude::v1::*;
+    #[test]
+    fn test_decoder_buffers() {
+        use super::{DecoderBuffers, DecoderKind};
+        for code in &CODES {
+            let bf = code.decoder_buffers(DecoderKind::BitFlipping);
+            assert_eq!(bf, DecoderBuffers { output: code.output_len(),
+                                            working_u8: code.decode_bf_working_len(),
+                                            working_soft: 0 });
+            let ms = code.decoder_buffers(DecoderKind::MinSum);
+            assert_eq!(ms.working_u8, code.decode_ms_working_u8_len());
+            assert_eq!(ms.working_soft, code.decode_ms_working_len());
+            assert_eq!(code.decoder_buffers(DecoderKind::SumProduct), ms);
+            let gb = code.decoder_buffers(DecoderKind::GallagerB);
+            assert_eq!(gb.working_u8, code.decode_gallager_b_working_len());
+            let wbf = code.decoder_buffers(DecoderKind::WeightedBitFlipping);
+            assert_eq!(wbf.working_soft, code.decode_wbf_working_i16_len());
+            let gdbf = code.decoder_buffers(DecoderKind::GradientDescentBitFlipping);
+            assert_eq!(gdbf.working_soft, code.decode_gdbf_working_f32_len());
+        }
+    }
 
-    use crate::codes::{LDPCCode, CodeParams,
-                       TC128_PARAMS,  TC256_PARAMS,  TC512_PARAMS,
-                       TM1280_PARAMS, TM1536_PARAMS, TM2048_PARAMS,
-                       TM5120_PARAMS, TM6144_PARAMS, TM8192_PARAMS};
+    #[test]
+    fn test_buffer_plan() {
+        use super::DecoderKind;
+        let code = LDPCCode::TM1280;
+        let plan = code.buffer_plan(DecoderKind::WeightedBitFlipping);
+        let mut input = vec![0u8; plan.input_u8];
+        let reliability = vec![4u8; plan.input_soft];
+        let mut output = vec![0u8; plan.buffers.output];
+        let mut working = vec![0u8; plan.buffers.working_u8];
+        let mut working_i16 = vec![0i16; plan.buffers.working_soft];
+        let txcode = vec![0u8; code.n()/8];
+        input.copy_from_slice(&txcode);
+        input[0] ^= 0x80;
+        let (success, _) = code.decode_wbf(&input, &reliability, &mut output, &mut working,
+                                           &mut working_i16, 20);
+        assert!(success);
+        assert_eq!(&output[..code.n()/8], &txcode[..]);
 
-    const CODES: [LDPCCode;  9] = [LDPCCode::TC128,   LDPCCode::TC256,   LDPCCode::TC512,
-                                   LDPCCode::TM1280,  LDPCCode::TM1536,  LDPCCode::TM2048,
-                                   LDPCCode::TM5120,  LDPCCode::TM6144,  LDPCCode::TM8192,
-    ];
+        let plan = code.buffer_plan(DecoderKind::MinSum);
+        assert_eq!((plan.input_u8, plan.input_soft), (0, code.n()));
+        let plan = code.buffer_plan(DecoderKind::BitFlipping);
+        assert_eq!((plan.input_u8, plan.input_soft), (code.n()/8, 0));
+    }
 
-    const PARAMS: [CodeParams; 9] = [TC128_PARAMS,  TC256_PARAMS,  TC512_PARAMS,
-                                     TM1280_PARAMS, TM1536_PARAMS, TM2048_PARAMS,
-                                     TM5120_PARAMS, TM6144_PARAMS, TM8192_PARAMS,
-    ];
+    #[test]
+    fn test_non_finite_input() {
+        use super::{DecodeError, MsDecoder};
+        let code = LDPCCode::TC128;
+        let mut llrs = vec![1.0f32; code.n()];
+        let mut output = vec![0u8; code.output_len()];
+        let mut working = vec![0f32; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        llrs[5] = f32::NAN;
+        llrs[9] = f32::INFINITY;
+        assert_eq!(code.try_decode_ms(&llrs, &mut output, &mut working, &mut working_u8, 20),
+                   Err(DecodeError::NonFiniteInput { index: 5 }));
+        llrs[5] = 1.0;
+        let mut decoder = MsDecoder::new(code, &mut working, &mut working_u8).unwrap();
+        assert_eq!(decoder.decode(&llrs, &mut output, 20),
+                   Err(DecodeError::NonFiniteInput { index: 9 }));
+        llrs[9] = 1.0;
+        assert!(decoder.decode(&llrs, &mut output, 20).unwrap().converged);
+    }
 
     #[test]
-    fn test_decode_ms_working_len() {
-        for (code, param) in CODES.iter().zip(PARAMS.iter()) {
-            assert_eq!(code.decode_ms_working_len(), param.decode_ms_working_len);
-            assert_eq!(code.decode_ms_working_u8_len(), param.decode_ms_working_u8_len);
+    #[cfg(feature = "tm1280")]
+    fn test_decode_dispatch() {
+        use super::{DecodeError, DecodeInput, DecoderKind};
+        let code = LDPCCode::TM1280;
+        let txdata: Vec<u8> = (0..code.k()/8).map(|i| i as u8).collect();
+        let mut txcode = vec![0u8; code.n()/8];
+        code.copy_encode(&txdata, &mut txcode);
+        let mut rxcode = txcode.clone();
+        rxcode[3] ^= 0x21;
+        let llrs: Vec<f32> = (0..code.n())
+            .map(|i| if (rxcode[i/8] >> (7-(i%8))) & 1 == 1 { -1.0 } else { 1.0 }).collect();
+
+        for &kind in &[DecoderKind::BitFlipping, DecoderKind::MinSum,
+                       DecoderKind::GradientDescentBitFlipping]
+        {
+            let plan = code.buffer_plan(kind);
+            let input = if plan.input_u8 > 0 { DecodeInput::Hard(&rxcode) }
+                        else { DecodeInput::Soft(&llrs) };
+            let mut output = vec![0u8; plan.buffers.output];
+            let mut working = vec![0u8; plan.buffers.working_u8];
+            let mut working_soft = vec![0f32; plan.buffers.working_soft];
+            let stats = code.decode(kind, input, &mut output, &mut working, &mut working_soft, 50)
+                            .unwrap();
+            assert!(stats.converged);
+            assert_eq!(&output[..code.n()/8], &txcode[..]);
         }
+
+        let mut output = vec![0u8; code.output_len()];
+        let mut working = vec![0u8; code.decode_ms_working_u8_len()];
+        let mut working_soft = vec![0f32; code.decode_ms_working_len()];
+        assert_eq!(code.decode(DecoderKind::MinSum, DecodeInput::Hard(&rxcode), &mut output,
+                               &mut working, &mut working_soft, 50),
+                   Err(DecodeError::Unsupported { kind: DecoderKind::MinSum }));
     }
 
     #[test]
-    fn test_decode_bf_working_len() {
-        for (code, param) in CODES.iter().zip(PARAMS.iter()) {
-            assert_eq!(code.decode_bf_working_len(), param.decode_bf_working_len);
-        }
+    fn test_decode_ms_confidence() {
+        use super::{Confidence, ConfidenceThresholds};
+        let code = LDPCCode::TM1280;
+        let normal = ConfidenceThresholds { min_llr: 2.0, max_iters: 3 };
+        let strict = ConfidenceThresholds { min_llr: 5.0, max_iters: 3 };
+        let txcode = vec![0u8; code.n()/8];
+        let mut llrs = vec![4.0f32; code.n()];
+        let mut output = vec![0u8; code.output_len()];
+        let mut working = vec![0f32; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+
+        // A clean codeword is decoded with high confidence, unless every bit must be more
+        // reliable than it was received
+        let (confidence, _) = code.decode_ms_confidence(&llrs, &mut output, &mut working,
+                                                        &mut working_u8, 50, normal);
+        assert_eq!(confidence, Confidence::High);
+        assert_eq!(&output[..code.n()/8], &txcode[..]);
+        let (confidence, _) = code.decode_ms_confidence(&llrs, &mut output, &mut working,
+                                                        &mut working_u8, 50, strict);
+        assert_eq!(confidence, Confidence::Marginal);
+
+        // Some wrong LLRs still decode, but only after several iterations
+        for llr in llrs.iter_mut().step_by(40) { *llr = -0.5 }
+        let (confidence, iters) = code.decode_ms_confidence(&llrs, &mut output, &mut working,
+                                                            &mut working_u8, 50, normal);
+        assert_eq!(confidence, Confidence::Marginal);
+        assert!(iters > 3);
+        assert_eq!(&output[..code.n()/8], &txcode[..]);
+
+        // Too many errors to correct
+        for llr in llrs.iter_mut().step_by(2) { *llr = -4.0 }
+        let (confidence, _) = code.decode_ms_confidence(&llrs, &mut output, &mut working,
+                                                        &mut working_u8, 20, normal);
+        assert_eq!(confidence, Confidence::Failed);
     }
 
     #[test]
-    fn test_output_len() {
-        for (code, param) in CODES.iter().zip(PARAMS.iter()) {
-            assert_eq!(code.output_len(), param.output_len);
-        }
+    fn test_decode_ms_debug() {
+        let code = LDPCCode::TM1280;
+        let mut llrs = vec![1.0f32; code.n()];
+        for llr in llrs.iter_mut().step_by(40) { *llr = -0.5 }
+        let mut output = vec![0u8; code.output_len()];
+        let mut working = vec![0f32; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+
+        let mut trajectory = Vec::new();
+        let (success, iters) = code.decode_ms_debug(&llrs, &mut output, &mut working,
+                                                    &mut working_u8, 50,
+                                                    |iter, unsatisfied| {
+                                                        trajectory.push((iter, unsatisfied))
+                                                    });
+        assert!(success);
+        assert_eq!(trajectory.len(), iters + 1);
+        assert!(trajectory.iter().enumerate().all(|(idx, &(iter, _))| idx == iter));
+        assert!(trajectory[0].1 > 0);
+        assert_eq!(trajectory[iters].1, 0);
+
+        // The same decode as decode_ms
+        let mut output_ms = vec![0u8; code.output_len()];
+        assert_eq!(code.decode_ms(&llrs, &mut output_ms, &mut working, &mut working_u8, 50),
+                   (success, iters));
+        assert_eq!(output, output_ms);
     }
 
     #[test]
-    fn test_hard_to_llrs() {
-        let code = LDPCCode::TC128;
-        let hard = vec![255, 254, 253, 252, 251, 250, 249, 248,
-                        203, 102, 103, 120, 107,  30, 157, 169];
-        let mut llrs = vec![0f32; code.n()];
-        let llr = -1.0;
-        code.hard_to_llrs(&hard, &mut llrs);
-        assert_eq!(llrs, vec![
-             llr,  llr,  llr,  llr,  llr,  llr,  llr,  llr,
-             llr,  llr,  llr,  llr,  llr,  llr,  llr, -llr,
-             llr,  llr,  llr,  llr,  llr,  llr, -llr,  llr,
-             llr,  llr,  llr,  llr,  llr,  llr, -llr, -llr,
-             llr,  llr,  llr,  llr,  llr, -llr,  llr,  llr,
-             llr,  llr,  llr,  llr,  llr, -llr,  llr, -llr,
-             llr,  llr,  llr,  llr,  llr, -llr, -llr,  llr,
-             llr,  llr,  llr,  llr,  llr, -llr, -llr, -llr,
-             llr,  llr, -llr, -llr,  llr, -llr,  llr,  llr,
-            -llr,  llr,  llr, -llr, -llr,  llr,  llr, -llr,
-            -llr,  llr,  llr, -llr, -llr,  llr,  llr,  llr,
-            -llr,  llr,  llr,  llr,  llr, -llr, -llr, -llr,
-            -llr,  llr,  llr, -llr,  llr, -llr,  llr,  llr,
-            -llr, -llr, -llr,  llr,  llr,  llr,  llr, -llr,
-             llr, -llr, -llr,  llr,  llr,  llr, -llr,  llr,
-             llr, -llr,  llr, -llr,  llr, -llr, -llr,  llr]);
+    fn test_total_decoder_bytes() {
+        use super::DecoderKind;
+        for code in &CODES {
+            assert_eq!(code.total_decoder_bytes::<f32>(DecoderKind::BitFlipping),
+                       code.output_len() + code.decode_bf_working_len());
+            assert_eq!(code.total_decoder_bytes::<i8>(DecoderKind::MinSum),
+                       code.output_len() + code.decode_ms_working_u8_len()
+                       + code.decode_ms_working_len());
+            assert_eq!(code.total_decoder_bytes::<f32>(DecoderKind::MinSum),
+                       code.output_len() + code.decode_ms_working_u8_len()
+                       + 4 * code.decode_ms_working_len());
+            assert_eq!(code.total_decoder_bytes::<u8>(DecoderKind::WeightedBitFlipping),
+                       code.output_len() + code.decode_wbf_working_len()
+                       + 2 * code.decode_wbf_working_i16_len());
+        }
     }
 
     #[test]
-    fn test_llrs_to_hard() {
+    fn test_try_decode() {
         let code = LDPCCode::TC128;
-        let llr = -1.0;
-        let llrs = vec![
-             llr,  llr,  llr,  llr,  llr,  llr,  llr,  llr,
-             llr,  llr,  llr,  llr,  llr,  llr,  llr, -llr,
-             llr,  llr,  llr,  llr,  llr,  llr, -llr,  llr,
-             llr,  llr,  llr,  llr,  llr,  llr, -llr, -llr,
-             llr,  llr,  llr,  llr,  llr, -llr,  llr,  llr,
-             llr,  llr,  llr,  llr,  llr, -llr,  llr, -llr,
-             llr,  llr,  llr,  llr,  llr, -llr, -llr,  llr,
-             llr,  llr,  llr,  llr,  llr, -llr, -llr, -llr,
-             llr,  llr, -llr, -llr,  llr, -llr,  llr,  llr,
-            -llr,  llr,  llr, -llr, -llr,  llr,  llr, -llr,
-            -llr,  llr,  llr, -llr, -llr,  llr,  llr,  llr,
-            -llr,  llr,  llr,  llr,  llr, -llr, -llr, -llr,
-            -llr,  llr,  llr, -llr,  llr, -llr,  llr,  llr,
-            -llr, -llr, -llr,  llr,  llr,  llr,  llr, -llr,
-             llr, -llr, -llr,  llr,  llr,  llr, -llr,  llr,
-             llr, -llr,  llr, -llr,  llr, -llr, -llr,  llr];
-        let mut hard = vec![0u8; code.n()/8];
-        code.llrs_to_hard(&llrs, &mut hard);
-        assert_eq!(hard, vec![255, 254, 253, 252, 251, 250, 249, 248,
-                              203, 102, 103, 120, 107,  30, 157, 169]);
+        let input = vec![0u8; code.n()/8];
+        let llrs = vec![1i8; code.n()];
+        let mut output = vec![0u8; code.output_len()];
+        let mut working = vec![0u8; code.decode_bf_working_len()];
+        let mut working_ms = vec![0i8; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+
+        let stats = code.try_decode_bf(&input, &mut output, &mut working, 10).unwrap();
+        assert!(stats.converged);
+        let stats = code.try_decode_ms(&llrs, &mut output, &mut working_ms,
+                                       &mut working_u8, 10).unwrap();
+        assert!(stats.converged);
+
+        assert_eq!(code.try_decode_bf(&input[1..], &mut output, &mut working, 10).err(),
+                   Some(DecodeError::BadBufferLen { which: "input", expected: 16, got: 15 }));
+        assert_eq!(code.try_decode_bf(&input, &mut output, &mut working[1..], 10).err(),
+                   Some(DecodeError::BadBufferLen { which: "working", expected: 128, got: 127 }));
+        assert_eq!(code.try_decode_ms(&llrs, &mut output[1..], &mut working_ms,
+                                      &mut working_u8, 10).err(),
+                   Some(DecodeError::BadBufferLen { which: "output", expected: 16, got: 15 }));
+        assert_eq!(code.try_decode_ms(&llrs, &mut output, &mut working_ms,
+                                      &mut working_u8[1..], 10).err(),
+                   Some(DecodeError::BadBufferLen { which: "working_u8", expected: 8, got: 7 }));
     }
 
     #[test]
-    fn test_decode_erasures() {
-        for code in &CODES {
-            // Only bother testing codes that actually have punctured bits
-            if code.punctured_bits() == 0 {
-                continue;
-            }
+    #[cfg(feature = "tm1280")]
+    fn test_try_decode_ms_bytes() {
+        use core::slice;
+        let code = LDPCCode::TM1280;
+        let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+        let mut txcode = vec![0u8; code.n()/8];
+        code.copy_encode(&txdata, &mut txcode);
+        txcode[3] ^= 1<<6;
+        let mut llrs = vec![0f32; code.n()];
+        code.hard_to_llrs(&txcode, &mut llrs);
+        let mut output = vec![0u8; code.output_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
 
-            // Encode a codeword
+        // Take an f32-aligned byte arena with one element to spare
+        let mut arena = vec![0u32; code.decode_ms_working_len() + 1];
+        let arena = unsafe {
+            slice::from_raw_parts_mut(arena.as_mut_ptr() as *mut u8, arena.len() * 4)
+        };
+        let len = code.decode_ms_working_len_bytes();
+
+        let stats = code.try_decode_ms_bytes(&llrs, &mut output, &mut arena[..len],
+                                             &mut working_u8, 50).unwrap();
+        assert!(stats.converged);
+        txcode[3] ^= 1<<6;
+        assert_eq!(&output[..code.n()/8], &txcode[..]);
+
+        assert_eq!(code.try_decode_ms_bytes(&llrs, &mut output, &mut arena[1..len+1],
+                                            &mut working_u8, 50).err(),
+                   Some(DecodeError::BadAlignment { which: "working", required: 4 }));
+        assert_eq!(code.try_decode_ms_bytes(&llrs, &mut output, &mut arena[..len-4],
+                                            &mut working_u8, 50).err(),
+                   Some(DecodeError::BadBufferLen { which: "working", expected: len,
+                                                    got: len - 4 }));
+    }
+
+    #[test]
+    fn test_decode_wbf() {
+        for code in &encodable(&CODES) {
             let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
             let mut txcode = vec![0u8; code.n()/8];
             code.copy_encode(&txdata, &mut txcode);
 
-            // Allocate working area
-            let mut working = vec![0u8; code.decode_bf_working_len()];
-            let mut output = vec![0u8; code.output_len()];
-
-            // Copy TX codeword into output manually (normally done by `decode_bf()`).
-            output[..txcode.len()].copy_from_slice(&txcode);
-
-            // Run erasure decoder
-            let (success, _) = code.decode_erasures(&mut output, &mut working, 50);
+            // Corrupt some bits and mark them as unreliable
+            let mut rxcode = txcode.clone();
+            rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+            let mut reliability = vec![8u8; code.n()];
+            reliability[0] = 1;
+            reliability[2] = 2;
+            reliability[4] = 1;
+            reliability[9] = 2;
 
+            let mut working = vec![0u8; code.decode_wbf_working_len()];
+            let mut working_i16 = vec![0i16; code.decode_wbf_working_i16_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let (success, _) = code.decode_wbf(&rxcode, &reliability, &mut output,
+                                               &mut working, &mut working_i16, 50);
             assert!(success);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
+        }
+    }
 
-            // Now compare the result against the min-sum decoder which should
-            // also correctly decode the punctured parity bits
-            let mut llrs = vec![0i8; code.n()];
-            let mut working_ms = vec![0i8; code.decode_ms_working_len()];
-            let mut working_u8_ms = vec![0u8; code.decode_ms_working_u8_len()];
-            let mut output_ms = vec![0u8; code.output_len()];
-            code.hard_to_llrs(&txcode, &mut llrs);
-            let (success, _) = code.decode_ms(&llrs, &mut output_ms, &mut working_ms,
-                                              &mut working_u8_ms, 50);
+    #[test]
+    fn test_decode_gdbf() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Corrupt some bits with low confidence
+            let mut llrs = vec![0f32; code.n()];
+            code.hard_to_llrs_scaled(&txcode, 2.0, &mut llrs);
+            for idx in &[0, 2, 4] {
+                llrs[*idx] *= -0.1;
+            }
 
+            let mut working = vec![0u8; code.decode_gdbf_working_len()];
+            let mut working_f32 = vec![0f32; code.decode_gdbf_working_f32_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let (success, _) = code.decode_gdbf(&llrs, &mut output, &mut working,
+                                                &mut working_f32, 50);
             assert!(success);
-            assert_eq!(output, output_ms);
+            assert_eq!(&txcode[..], &output[..txcode.len()]);
         }
     }
 
     #[test]
-    fn test_decode_bf() {
-        for code in &CODES {
-            // Make up some TX data
+    fn test_decode_ms_layered() {
+        for code in &encodable(&CODES) {
             let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
             let mut txcode = vec![0u8; code.n()/8];
             code.copy_encode(&txdata, &mut txcode);
 
-            // Copy it and corrupt some bits
             let mut rxcode = txcode.clone();
             rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
+            let mut llrs = vec![0i16; code.n()];
+            code.hard_to_llrs_scaled(&rxcode, 16, &mut llrs);
 
-            // Allocate working area and output area
-            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut working = vec![0i16; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
             let mut output = vec![0u8; code.output_len()];
-
-            // Run decoder
-            let (success, _) = code.decode_bf(&rxcode, &mut output, &mut working, 50);
-
+            let (success, iters) = code.decode_ms_layered(&llrs, &mut output, &mut working,
+                                                          &mut working_u8, 50);
             assert!(success);
             assert_eq!(&txcode[..], &output[..txcode.len()]);
+
+            // Should need no more iterations than the flooding schedule
+            let (_, ms_iters) = code.decode_ms(&llrs, &mut output, &mut working,
+                                               &mut working_u8, 50);
+            assert!(iters <= ms_iters);
         }
+    }
+
+    #[test]
+    fn test_decode_ms_layered_noisy() {
+        // On a noisy channel the layered schedule should do at least as well as flooding
+        for code in &encodable(&[LDPCCode::TM1280]) {
+            let mut state = 0x2545_F491u32;
+            let mut uniform = || {
+                state ^= state << 13; state ^= state >> 17; state ^= state << 5;
+                (f64::from(state) + 1.0) / 4_294_967_297.0
+            };
+            // BPSK at Eb/N0 = 3dB
+            let rate = code.k() as f64 / code.n() as f64;
+            let sigma2 = 1.0 / (2.0 * rate * 10f64.powf(0.3));
+
+            let mut working = vec![0f32; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            let (mut errors_ms, mut errors_layered) = (0, 0);
+            for frame in 0..100 {
+                let txdata: Vec<u8> = (0..code.k()/8).map(|x| (x * 7 + frame) as u8).collect();
+                let mut txcode = vec![0u8; code.n()/8];
+                code.copy_encode(&txdata, &mut txcode);
+                let llrs: Vec<f32> = (0..code.n()).map(|i| {
+                    let x = if (txcode[i/8] >> (7 - (i%8))) & 1 == 0 { 1.0 } else { -1.0 };
+                    let (u1, u2) = (uniform(), uniform());
+                    let g = (-2.0 * u1.ln()).sqrt() * (2.0 * core::f64::consts::PI * u2).cos();
+                    (2.0 * (x + sigma2.sqrt() * g) / sigma2) as f32
+                }).collect();
 
+                code.decode_ms(&llrs, &mut output, &mut working, &mut working_u8, 50);
+                if output[..txcode.len()] != txcode[..] { errors_ms += 1 }
+                code.decode_ms_layered(&llrs, &mut output, &mut working, &mut working_u8, 50);
+                if output[..txcode.len()] != txcode[..] { errors_layered += 1 }
+            }
+            assert!(errors_ms > 0);
+            assert!(errors_layered <= errors_ms);
+        }
     }
+
     #[test]
-    fn test_decode_ms() {
-        for code in &CODES {
-            // Make up a TX codeword
+    fn test_decode_ms_layered_saturated() {
+        // i8 LLRs large enough that the marginals saturate must still decode, as for decode_ms
+        for code in &encodable(&CODES) {
             let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
             let mut txcode = vec![0u8; code.n()/8];
             code.copy_encode(&txdata, &mut txcode);
 
-            // Copy it and corrupt some bits
             let mut rxcode = txcode.clone();
             rxcode[0] ^= 1<<7 | 1<<5 | 1<<3;
-
-            // Convert the hard data to LLRs
-            let mut llrs = vec![0i8; code.n()];
-            code.hard_to_llrs(&rxcode, &mut llrs);
-
-            // Allocate working area and output area
             let mut working = vec![0i8; code.decode_ms_working_len()];
-            let mut working_u8 = vec![0u8; code.output_len() - code.k()/8];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
             let mut output = vec![0u8; code.output_len()];
+            for &magnitude in &[16, 32, 48, i8::MAX / 2] {
+                let mut llrs = vec![0i8; code.n()];
+                code.hard_to_llrs_scaled(&rxcode, magnitude, &mut llrs);
+                let (success, _) = code.decode_ms_layered(&llrs, &mut output, &mut working,
+                                                          &mut working_u8, 50);
+                assert!(success);
+                assert_eq!(&txcode[..], &output[..txcode.len()]);
+            }
+        }
+    }
 
-            // Run decoder
-            let (success, _) = code.decode_ms(&llrs, &mut output, &mut working,
-                                              &mut working_u8, 50);
+    #[test]
+    fn test_decode_erasures_received() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|x| x as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Find the full codeword including punctured bits
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let mut full = vec![0u8; code.output_len()];
+            assert!(code.decode_bf(&txcode, &mut full, &mut working, 50).0);
+
+            // Erase some received bits, and overwrite them with garbage
+            let mut known = vec![true; code.n()];
+            let mut codeword = vec![0u8; code.output_len()];
+            codeword[..code.n()/8].copy_from_slice(&txcode);
+            for var in (0..code.n()).step_by(37) {
+                known[var] = false;
+                codeword[var/8] ^= 1<<(7-(var%8));
+            }
 
+            let (success, _) = code.decode_erasures(&known, &mut codeword, &mut working, 50);
             assert!(success);
-            assert_eq!(&txcode[..], &output[..txcode.len()]);
+            assert_eq!(codeword, full);
         }
     }
 }
@@ -9,8 +9,11 @@
 // We have a couple of expressions with +0 for clarity of where the 0 comes from
 #![allow(clippy::identity_op)]
 
+use core::fmt;
+use core::mem;
 use core::slice;
 
+use crate::bits::{pack_bools, BitOrder};
 use crate::codes::LDPCCode;
 
 /// Trait for the types of codeword we can encode into.
@@ -30,14 +33,43 @@ pub trait EncodeInto {
 
     /// Returns the bit length for this type
     fn bitlength() -> usize;
+
+    /// Given `codeword` which has the first k bits set to the data to transmit,
+    /// sets the remaining n-k parity bits using the compact generator `gc`.
+    ///
+    /// No lengths are checked, so a `gc` or `codeword` for a different code may panic or
+    /// silently produce an invalid codeword. Use `Encoder` to have them checked instead.
+    ///
+    /// The default implementation encodes the bytes of `codeword` in memory order using the
+    /// u8 encoder, so is only correct for plain integer types, as `encode` already requires.
+    ///
+    /// Returns a `&mut [u8]` view on `codeword`.
+    fn encode_with<'a>(code: &LDPCCode, gc: &[u64], codeword: &'a mut[Self]) -> &'a mut [u8]
+        where Self: Sized
+    {
+        u8::encode_with(code, gc, as_bytes(codeword))
+    }
+
+    /// First copies `data` into the first k bits of `codeword`, then calls `encode_with`.
+    fn copy_encode_with<'a>(code: &LDPCCode, gc: &[u64], data: &[u8], codeword: &'a mut[Self])
+        -> &'a mut [u8]
+        where Self: Sized
+    {
+        u8::copy_encode_with(code, gc, data, as_bytes(codeword))
+    }
+}
+
+/// Returns a `&mut [u8]` view on `codeword`.
+fn as_bytes<T>(codeword: &mut [T]) -> &mut [u8] {
+    let len = mem::size_of_val(codeword);
+    unsafe { slice::from_raw_parts_mut(codeword.as_mut_ptr() as *mut u8, len) }
 }
 
 impl EncodeInto for u8 {
-    fn encode<'a>(code: &LDPCCode, codeword: &'a mut[Self]) -> &'a mut [u8] {
+    fn encode_with<'a>(code: &LDPCCode, gc: &[u64], codeword: &'a mut[Self]) -> &'a mut [u8] {
         let k = code.k();
         let r = code.n() - code.k();
         let b = code.circulant_size();
-        let gc = code.compact_generator();
         let row_len = r/64;
 
         // Scope the split of codeword into (data, parity)
@@ -83,20 +115,29 @@ impl EncodeInto for u8 {
         codeword
     }
 
-    fn copy_encode<'a>(code: &LDPCCode, data: &[u8], codeword: &'a mut[Self]) -> &'a mut [u8] {
+    fn copy_encode_with<'a>(code: &LDPCCode, gc: &[u64], data: &[u8], codeword: &'a mut[Self])
+        -> &'a mut [u8]
+    {
         codeword[..data.len()].copy_from_slice(data);
-        Self::encode(code, codeword)
+        Self::encode_with(code, gc, codeword)
+    }
+
+    fn encode<'a>(code: &LDPCCode, codeword: &'a mut[Self]) -> &'a mut [u8] {
+        Self::encode_with(code, code.compact_generator(), codeword)
+    }
+
+    fn copy_encode<'a>(code: &LDPCCode, data: &[u8], codeword: &'a mut[Self]) -> &'a mut [u8] {
+        Self::copy_encode_with(code, code.compact_generator(), data, codeword)
     }
 
     fn bitlength() -> usize { 8 }
 }
 
 impl EncodeInto for u32 {
-    fn encode<'a>(code: &LDPCCode, codeword: &'a mut[Self]) -> &'a mut [u8] {
+    fn encode_with<'a>(code: &LDPCCode, gc: &[u64], codeword: &'a mut[Self]) -> &'a mut [u8] {
         let k = code.k();
         let r = code.n() - code.k();
         let b = code.circulant_size();
-        let gc = code.compact_generator();
         let row_len = r/64;
 
         // Scope the split of codeword into (data, parity)
@@ -156,23 +197,32 @@ impl EncodeInto for u32 {
         }
     }
 
-    fn copy_encode<'a>(code: &LDPCCode, data: &[u8], codeword: &'a mut[Self]) -> &'a mut [u8] {
+    fn copy_encode_with<'a>(code: &LDPCCode, gc: &[u64], data: &[u8], codeword: &'a mut[Self])
+        -> &'a mut [u8]
+    {
         let codeword_u8 = unsafe {
             slice::from_raw_parts_mut::<'a>(codeword.as_mut_ptr() as *mut u8, codeword.len() * 4)
         };
         codeword_u8[..data.len()].copy_from_slice(data);
-        Self::encode(code, codeword)
+        Self::encode_with(code, gc, codeword)
+    }
+
+    fn encode<'a>(code: &LDPCCode, codeword: &'a mut[Self]) -> &'a mut [u8] {
+        Self::encode_with(code, code.compact_generator(), codeword)
+    }
+
+    fn copy_encode<'a>(code: &LDPCCode, data: &[u8], codeword: &'a mut[Self]) -> &'a mut [u8] {
+        Self::copy_encode_with(code, code.compact_generator(), data, codeword)
     }
 
     fn bitlength() -> usize { 32 }
 }
 
 impl EncodeInto for u64 {
-    fn encode<'a>(code: &LDPCCode, codeword: &'a mut[Self]) -> &'a mut [u8] {
+    fn encode_with<'a>(code: &LDPCCode, gc: &[u64], codeword: &'a mut[Self]) -> &'a mut [u8] {
         let k = code.k();
         let r = code.n() - code.k();
         let b = code.circulant_size();
-        let gc = code.compact_generator();
         let row_len = r/64;
 
         // Scope the split of codeword into (data, parity)
@@ -192,9 +242,7 @@ impl EncodeInto for u64 {
                     let bit = crow*b + offset;
                     if data[bit/8] >> (7-(bit%8)) & 1 == 1 {
                         // If bit is set, XOR the generator constant in
-                        for (idx, circ) in gc[crow*row_len..(crow+1)*row_len].iter().enumerate() {
-                            parity[idx] ^= *circ;
-                        }
+                        xor_words(&mut parity[..row_len], &gc[crow*row_len..(crow+1)*row_len]);
                     }
                 }
                 // Now simulate the right-rotation of the generator by left-rotating the parity
@@ -242,24 +290,417 @@ impl EncodeInto for u64 {
         }
     }
 
-    fn copy_encode<'a>(code: &LDPCCode, data: &[u8], codeword: &'a mut[Self]) -> &'a mut [u8] {
+    fn copy_encode_with<'a>(code: &LDPCCode, gc: &[u64], data: &[u8], codeword: &'a mut[Self])
+        -> &'a mut [u8]
+    {
         let codeword_u8 = unsafe {
             slice::from_raw_parts_mut::<'a>(codeword.as_mut_ptr() as *mut u8, codeword.len() * 8)
         };
         codeword_u8[..data.len()].copy_from_slice(data);
-        Self::encode(code, codeword)
+        Self::encode_with(code, gc, codeword)
+    }
+
+    fn encode<'a>(code: &LDPCCode, codeword: &'a mut[Self]) -> &'a mut [u8] {
+        Self::encode_with(code, code.compact_generator(), codeword)
+    }
+
+    fn copy_encode<'a>(code: &LDPCCode, data: &[u8], codeword: &'a mut[Self]) -> &'a mut [u8] {
+        Self::copy_encode_with(code, code.compact_generator(), data, codeword)
     }
 
     fn bitlength() -> usize { 64 }
 }
 
+/// XOR each word of `src` into the corresponding word of `dst`.
+fn xor_words_scalar(dst: &mut [u64], src: &[u64]) {
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+        *d ^= *s;
+    }
+}
+
+/// XOR each word of `src` into the corresponding word of `dst`, two words at a time.
+///
+/// SSE2 is part of the x86_64 baseline so no runtime detection is required.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn xor_words(dst: &mut [u64], src: &[u64]) {
+    use core::arch::x86_64::{__m128i, _mm_loadu_si128, _mm_storeu_si128, _mm_xor_si128};
+    let pairs = dst.len().min(src.len()) / 2;
+    for i in 0..pairs {
+        unsafe {
+            let d = dst.as_mut_ptr().add(2*i) as *mut __m128i;
+            let s = src.as_ptr().add(2*i) as *const __m128i;
+            _mm_storeu_si128(d, _mm_xor_si128(_mm_loadu_si128(d), _mm_loadu_si128(s)));
+        }
+    }
+    xor_words_scalar(&mut dst[2*pairs..], &src[2*pairs..]);
+}
+
+/// XOR each word of `src` into the corresponding word of `dst`, two words at a time.
+///
+/// NEON is part of the aarch64 baseline so no runtime detection is required.
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+fn xor_words(dst: &mut [u64], src: &[u64]) {
+    use core::arch::aarch64::{veorq_u64, vld1q_u64, vst1q_u64};
+    let pairs = dst.len().min(src.len()) / 2;
+    for i in 0..pairs {
+        unsafe {
+            let d = dst.as_mut_ptr().add(2*i);
+            let s = src.as_ptr().add(2*i);
+            vst1q_u64(d, veorq_u64(vld1q_u64(d), vld1q_u64(s)));
+        }
+    }
+    xor_words_scalar(&mut dst[2*pairs..], &src[2*pairs..]);
+}
+
+/// XOR each word of `src` into the corresponding word of `dst`.
+#[cfg(not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+fn xor_words(dst: &mut [u64], src: &[u64]) {
+    xor_words_scalar(dst, src);
+}
+
+/// Errors which can be returned by the checked encoding interfaces.
+#[non_exhaustive]
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub enum EncodeError {
+    /// No generator matrix is included for this code, and none was provided.
+    NoGenerator,
+
+    /// The provided generator matrix is all zeros, so has probably not been initialised.
+    UninitialisedGenerator,
+
+    /// A buffer passed in was not the length required by the code.
+    BadBufferLen {
+        /// Name of the offending buffer, such as `"codeword"`.
+        which: &'static str,
+        /// Length required by the code.
+        expected: usize,
+        /// Length actually provided.
+        got: usize,
+    },
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncodeError::NoGenerator =>
+                write!(f, "no generator matrix is available for this code"),
+            EncodeError::UninitialisedGenerator =>
+                write!(f, "generator matrix is all zeros"),
+            EncodeError::BadBufferLen { which, expected, got } =>
+                write!(f, "{} has length {} but must be length {}", which, got, expected),
+        }
+    }
+}
+
+/// Return `EncodeError::BadBufferLen` if `got` is not equal to `expected`.
+fn check_len(which: &'static str, expected: usize, got: usize) -> Result<(), EncodeError> {
+    if expected == got {
+        Ok(())
+    } else {
+        Err(EncodeError::BadBufferLen { which, expected, got })
+    }
+}
+
+/// Reusable encoder context.
+///
+/// Holds a code and the compact generator matrix used to encode it, validating the generator
+/// once at construction. Usually the generator included for the code is used, but for codes
+/// without one (the k=16384 codes) a compact generator may be provided instead, for example
+/// from `LDPCCode::init_generator_from_parity`.
+///
+/// ```
+/// # #[cfg(feature = "tc128")] {
+/// # use labrador_ldpc::LDPCCode;
+/// # use labrador_ldpc::encoder::Encoder;
+/// let code = LDPCCode::TC128;
+/// let encoder = Encoder::new(code).unwrap();
+///
+/// let data = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+/// let mut codeword = [0u8; 16];
+/// encoder.copy_encode(&data, &mut codeword).unwrap();
+/// assert_eq!(&codeword[8..], [0x34, 0x99, 0x98, 0x87, 0x94, 0xE1, 0x62, 0x56]);
+/// # }
+/// ```
+pub struct Encoder<'a> {
+    code: LDPCCode,
+    generator: &'a [u64],
+}
+
+impl Encoder<'static> {
+    /// Create a new encoder for `code` using its included generator matrix.
+    ///
+    /// Returns `EncodeError::NoGenerator` if no generator matrix is included for `code`.
+    pub fn new(code: LDPCCode) -> Result<Encoder<'static>, EncodeError> {
+        if code.compact_generator().is_empty() {
+            return Err(EncodeError::NoGenerator);
+        }
+        Ok(Encoder { code, generator: code.compact_generator() })
+    }
+}
+
+impl<'a> Encoder<'a> {
+    /// Create a new encoder for `code` using the provided compact generator matrix.
+    ///
+    /// `generator` must be in the same form as `LDPCCode::compact_generator()` and have
+    /// `code.compact_generator_len()` elements. Generators are specific to a single code and
+    /// cannot be shared between codes of different rates, even with the same k. Some such
+    /// pairs have compact generators of the same length, so use `LDPCCode::verify_generator`
+    /// to be certain a generator matches `code`.
+    ///
+    /// Returns `EncodeError::BadBufferLen` if `generator` is the wrong length, or
+    /// `EncodeError::UninitialisedGenerator` if it is all zeros. Use
+    /// `LDPCCode::verify_generator` to check its contents more thoroughly.
+    pub fn with_generator(code: LDPCCode, generator: &'a [u64])
+        -> Result<Encoder<'a>, EncodeError>
+    {
+        check_len("generator", code.compact_generator_len(), generator.len())?;
+        if generator.iter().all(|x| *x == 0) {
+            return Err(EncodeError::UninitialisedGenerator);
+        }
+        Ok(Encoder { code, generator })
+    }
+
+    /// Get the code this encoder was created for.
+    pub fn code(&self) -> LDPCCode {
+        self.code
+    }
+
+    /// Encode `codeword`, which must be n bits long and have the data in its first k bits.
+    ///
+    /// See `LDPCCode::encode` for full details.
+    ///
+    /// Returns `EncodeError::BadBufferLen` if `codeword` is the wrong length,
+    /// otherwise returns a `&mut [u8]` view on `codeword`.
+    pub fn encode<'b, T>(&self, codeword: &'b mut [T]) -> Result<&'b mut [u8], EncodeError>
+        where T: EncodeInto
+    {
+        check_len("codeword", self.code.n(), codeword.len() * T::bitlength())?;
+        Ok(T::encode_with(&self.code, self.generator, codeword))
+    }
+
+    /// Copy `data`, which must be k bits long, into `codeword`, which must be n bits long,
+    /// and then encode it.
+    ///
+    /// See `LDPCCode::copy_encode` for full details.
+    ///
+    /// Returns `EncodeError::BadBufferLen` if `data` or `codeword` are the wrong length,
+    /// otherwise returns a `&mut [u8]` view on `codeword`.
+    pub fn copy_encode<'b, T>(&self, data: &[u8], codeword: &'b mut [T])
+        -> Result<&'b mut [u8], EncodeError>
+        where T: EncodeInto
+    {
+        check_len("data", self.code.k(), data.len() * 8)?;
+        check_len("codeword", self.code.n(), codeword.len() * T::bitlength())?;
+        Ok(T::copy_encode_with(&self.code, self.generator, data, codeword))
+    }
+}
+
+/// Encoder for a stream of data longer than a single codeword.
+///
+/// Data written with `write` is split into k-bit blocks, each of which is encoded into a
+/// codeword as soon as it is complete. Once all data is written, `finish` pads any final
+/// partial block with zeros and encodes it. No allocation is required: the caller provides a
+/// single n/8 byte codeword buffer at construction, and each codeword is passed to a callback
+/// before the buffer is reused.
+///
+/// ```
+/// # #[cfg(feature = "tc128")] {
+/// # use labrador_ldpc::LDPCCode;
+/// # use labrador_ldpc::encoder::{Encoder, StreamEncoder};
+/// let code = LDPCCode::TC128;
+/// let mut buf = [0u8; 16];
+/// let mut stream = StreamEncoder::new(Encoder::new(code).unwrap(), &mut buf).unwrap();
+///
+/// let mut codewords = Vec::new();
+/// stream.write(b"Hello, ", |c| codewords.push(c.to_vec()));
+/// stream.write(b"world!", |c| codewords.push(c.to_vec()));
+/// let padding = stream.finish(|c| codewords.push(c.to_vec()));
+///
+/// assert_eq!(codewords.len(), 2);
+/// assert_eq!(&codewords[0][..8], b"Hello, w");
+/// assert_eq!(&codewords[1][..8], b"orld!\0\0\0");
+/// assert_eq!(padding, 3);
+/// # }
+/// ```
+pub struct StreamEncoder<'a> {
+    encoder: Encoder<'a>,
+    codeword: &'a mut [u8],
+    fill: usize,
+}
+
+impl<'a> StreamEncoder<'a> {
+    /// Create a new stream encoder using `encoder`, with `codeword` as its working buffer.
+    ///
+    /// Returns `EncodeError::BadBufferLen` if `codeword` is not n/8 bytes long.
+    pub fn new(encoder: Encoder<'a>, codeword: &'a mut [u8])
+        -> Result<StreamEncoder<'a>, EncodeError>
+    {
+        check_len("codeword", encoder.code().n(), codeword.len() * 8)?;
+        Ok(StreamEncoder { encoder, codeword, fill: 0 })
+    }
+
+    /// Get the code this encoder was created for.
+    pub fn code(&self) -> LDPCCode {
+        self.encoder.code()
+    }
+
+    /// Get the number of bytes written but not yet encoded, always less than k/8.
+    pub fn buffered(&self) -> usize {
+        self.fill
+    }
+
+    /// Write `data` to the stream, calling `emit` with each codeword completed.
+    ///
+    /// `data` may be any length. Each codeword is n/8 bytes long, with its data in the
+    /// first k/8 bytes. Any incomplete final block is buffered until the next call to
+    /// `write` or `finish`.
+    ///
+    /// Returns the number of codewords emitted.
+    pub fn write<F: FnMut(&[u8])>(&mut self, data: &[u8], mut emit: F) -> usize {
+        let kb = self.code().k() / 8;
+        let mut emitted = 0;
+        let mut data = data;
+        while !data.is_empty() {
+            let len = data.len().min(kb - self.fill);
+            self.codeword[self.fill..self.fill + len].copy_from_slice(&data[..len]);
+            self.fill += len;
+            data = &data[len..];
+
+            if self.fill == kb {
+                self.emit(&mut emit);
+                emitted += 1;
+            }
+        }
+        emitted
+    }
+
+    /// Encode any buffered data, padded with zeros to k/8 bytes, and pass it to `emit`.
+    ///
+    /// Returns the number of zero bytes of padding added, which the receiver needs to
+    /// discard them. If no data was buffered, `emit` is not called and 0 is returned.
+    /// The stream may be written to again afterwards, starting a new block.
+    pub fn finish<F: FnMut(&[u8])>(&mut self, mut emit: F) -> usize {
+        if self.fill == 0 {
+            return 0;
+        }
+        let kb = self.code().k() / 8;
+        let padding = kb - self.fill;
+        for x in &mut self.codeword[self.fill..kb] { *x = 0 }
+        self.emit(&mut emit);
+        padding
+    }
+
+    /// Encode the full block in the buffer, pass it to `emit`, and reset the buffer.
+    fn emit<F: FnMut(&[u8])>(&mut self, emit: &mut F) {
+        // The buffer length was checked at construction so encoding cannot fail
+        let _ = self.encoder.encode(&mut self.codeword[..]);
+        emit(self.codeword);
+        self.fill = 0;
+    }
+}
+
 impl LDPCCode {
 
+    /// Get the length of [u64] of the compact generator matrix for this code.
+    ///
+    /// Equal to (k/circulant_size) * (n-k)/64. This is the length of `compact_generator()`,
+    /// except for codes with no included generator, where that is empty.
+    pub const fn compact_generator_len(self) -> usize {
+        (self.k() / self.circulant_size()) * (self.n() - self.k()) / 64
+    }
+
+    /// Check that a compact generator matrix is correct for this code.
+    ///
+    /// This is intended for generators loaded at runtime, for example copied from flash for
+    /// use with `Encoder::with_generator`, to catch corruption or a buffer reused by mistake.
+    /// The length of `generator` is checked, then the first row of each circulant block is
+    /// encoded and tested against every parity check. As each block is determined by its first
+    /// row, this detects any error in `generator`, while only encoding k/circulant_size rows.
+    /// The parity checks are always generated from constants in flash (see
+    /// `iter_paritychecks`), so no equivalent check is needed for them.
+    ///
+    /// * `codeword` must be `output_len()` long, and is used as a working area.
+    /// * `working` must be `decode_bf_working_len()` long.
+    ///
+    /// Returns true if `generator` is exactly the generator matrix for this code.
+    pub fn verify_generator(self, generator: &[u64], codeword: &mut [u8], working: &mut [u8])
+        -> bool
+    {
+        assert_eq!(codeword.len(), self.output_len(), "codeword.len() != (n+p)/8");
+        assert_eq!(working.len(), self.decode_bf_working_len(), "working.len() incorrect");
+
+        if generator.len() != self.compact_generator_len() {
+            return false;
+        }
+
+        let n = self.n();
+        let p = self.punctured_bits();
+        for row in (0..self.k()).step_by(self.circulant_size()) {
+            for x in &mut codeword[..] { *x = 0 }
+            codeword[row/8] = 1 << (7-(row%8));
+            u8::encode_with(&self, generator, &mut codeword[..n/8]);
+
+            // Fill in any punctured bits so every parity check can be tested
+            if p > 0 && !self.decode_erasures_inner(|_| true, codeword, working, p).0 {
+                return false;
+            }
+
+            if !self.compute_syndrome(codeword, &mut working[..self.syndrome_len()]) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Check the constants for this code in flash are consistent, as a built-in self-test.
+    ///
+    /// Every parity check edge generated from the compact parity check constants is checked
+    /// to be in range and their number to equal `paritycheck_sum()`, and then the included
+    /// generator matrix, if any, is checked against the parity checks with
+    /// `verify_generator`, so that G·Hᵀ = 0. This catches corruption of either set of
+    /// constants before it can cause silent encode or decode failures.
+    ///
+    /// * `codeword` must be `output_len()` long, and is used as a working area.
+    /// * `working` must be `decode_bf_working_len()` long.
+    ///
+    /// Returns true if every check passes.
+    pub fn self_test(self, codeword: &mut [u8], working: &mut [u8]) -> bool {
+        let checks = self.n() + self.punctured_bits() - self.k();
+        let vars = self.n() + self.punctured_bits();
+        let mut edges = 0;
+        for (check, var) in self.iter_paritychecks() {
+            if check >= checks || var >= vars {
+                return false;
+            }
+            edges += 1;
+        }
+        if edges != self.paritycheck_sum() as usize {
+            return false;
+        }
+
+        let generator = self.compact_generator();
+        if generator.is_empty() {
+            assert_eq!(codeword.len(), self.output_len(), "codeword.len() != (n+p)/8");
+            assert_eq!(working.len(), self.decode_bf_working_len(), "working.len() incorrect");
+            true
+        } else {
+            self.verify_generator(generator, codeword, working)
+        }
+    }
+
     /// Encode a codeword. This function assumes the first k bits of `codeword` have already
     /// been set to your data, and will set the remaining n-k bits appropriately.
     ///
+    /// Encoding happens entirely in place: the first k bits are never modified and any
+    /// existing contents of the final n-k bits are overwritten, so no separate buffer is
+    /// needed for the data.
+    ///
     /// `codeword` must be exactly n bits long.
     ///
+    /// Panics for codes without a generator matrix (the k=16384 codes, or any code whose cargo
+    /// feature is disabled), which must be encoded using `encode_from_parity` instead.
+    ///
     /// You can give `codeword` in `u8`, `u32`, or `u64`.
     /// The larger types are faster and are interpreted as packed bytes in little endian.
     ///
@@ -270,6 +711,7 @@ impl LDPCCode {
         where T: EncodeInto
     {
         assert_eq!(codeword.len() * T::bitlength(), self.n(), "codeword must be n bits long");
+        assert!(!self.compact_generator().is_empty(), "no generator matrix for this code");
         EncodeInto::encode(self, codeword)
     }
 
@@ -286,53 +728,525 @@ impl LDPCCode {
     {
         assert_eq!(data.len() * 8, self.k(), "data must be k bits long");
         assert_eq!(codeword.len() * T::bitlength(), self.n(), "codeword must be n bits long");
+        assert!(!self.compact_generator().is_empty(), "no generator matrix for this code");
         EncodeInto::copy_encode(self, data, codeword)
     }
+
+    /// Encode a codeword, with checked buffer lengths.
+    ///
+    /// Identical to `encode`, but instead of panicking returns `EncodeError::BadBufferLen` if
+    /// `codeword` is not n bits long, or `EncodeError::NoGenerator` if no generator matrix is
+    /// included for this code.
+    pub fn try_encode<'a, T>(&self, codeword: &'a mut [T]) -> Result<&'a mut [u8], EncodeError>
+        where T: EncodeInto
+    {
+        Encoder::new(*self)?.encode(codeword)
+    }
+
+    /// Encode a codeword, first copying in the data, with checked buffer lengths.
+    ///
+    /// Identical to `copy_encode`, but instead of panicking returns `EncodeError::BadBufferLen`
+    /// if `data` is not k bits long or `codeword` is not n bits long, or
+    /// `EncodeError::NoGenerator` if no generator matrix is included for this code.
+    pub fn try_copy_encode<'a, T>(&self, data: &[u8], codeword: &'a mut [T])
+        -> Result<&'a mut [u8], EncodeError>
+        where T: EncodeInto
+    {
+        Encoder::new(*self)?.copy_encode(data, codeword)
+    }
+
+    /// Encode a codeword, first copying in data given as words.
+    ///
+    /// This is the same as `copy_encode` except `data` is given in the same type as
+    /// `codeword`, so buffers of `u32` or `u64` never need repacking into bytes. `data` must
+    /// be k bits long and `codeword` n bits long.
+    ///
+    /// As for `encode`, each word holds consecutive codeword bytes in memory order, so on a
+    /// little endian target the first byte of a word is its least significant byte. Use
+    /// `from_be()` on a word to get its bits with the first codeword bit as the most
+    /// significant bit.
+    ///
+    /// Returns a view of `codeword` in &mut [u8], as for `copy_encode`.
+    pub fn copy_encode_words<'a, T>(&self, data: &[T], codeword: &'a mut [T]) -> &'a mut [u8]
+        where T: EncodeInto + Copy
+    {
+        assert_eq!(data.len() * T::bitlength(), self.k(), "data must be k bits long");
+        assert_eq!(codeword.len() * T::bitlength(), self.n(), "codeword must be n bits long");
+        codeword[..data.len()].copy_from_slice(data);
+        self.encode(codeword)
+    }
+
+    /// Encode a codeword from data given as one `bool` per bit.
+    ///
+    /// This is the same as `copy_encode`, except `data` must be k long, with `true` for each
+    /// 1 bit, and `codeword` must be n/8 bytes long. Use `bits::unpack_bools` if you need the
+    /// codeword as `bool`s.
+    pub fn copy_encode_bools<'a>(&self, data: &[bool], codeword: &'a mut [u8]) -> &'a mut [u8] {
+        assert_eq!(data.len(), self.k(), "data must be k bits long");
+        assert_eq!(codeword.len() * 8, self.n(), "codeword must be n bits long");
+        pack_bools(data, &mut codeword[..self.k()/8]);
+        self.encode(codeword)
+    }
+
+    /// Encode a codeword with the given bit packing.
+    ///
+    /// This is the same as `copy_encode`, except `data` is packed in `order`, and `codeword`,
+    /// which must be n/8 bytes long, is written in `order` too. `BitOrder::MsbFirst` gives
+    /// exactly the output of `copy_encode`.
+    pub fn copy_encode_ordered<'a>(&self, data: &[u8], codeword: &'a mut [u8], order: BitOrder)
+        -> &'a mut [u8]
+    {
+        assert_eq!(data.len() * 8, self.k(), "data must be k bits long");
+        assert_eq!(codeword.len() * 8, self.n(), "codeword must be n bits long");
+        codeword[..self.k()/8].copy_from_slice(data);
+        order.reorder(&mut codeword[..self.k()/8]);
+        self.encode(codeword);
+        order.reorder(codeword);
+        codeword
+    }
+
+    /// Encode a codeword from data given as an iterator of bits.
+    ///
+    /// This is the same as `copy_encode_bools`, except the data bits are taken from `bits`,
+    /// `true` for each 1 bit, so bits arriving from a bitstream need not first be collected
+    /// into a buffer. `codeword` must be n/8 bytes long.
+    ///
+    /// Returns `EncodeError::BadBufferLen` with `which` of `"bits"` if `bits` does not yield
+    /// exactly k bits, in which case `got` is the number yielded, or k+1 if there were too
+    /// many. Returns `EncodeError::BadBufferLen` if `codeword` is the wrong length, or
+    /// `EncodeError::NoGenerator` if no generator matrix is included for this code.
+    pub fn copy_encode_iter<'a, I>(&self, bits: I, codeword: &'a mut [u8])
+        -> Result<&'a mut [u8], EncodeError>
+        where I: IntoIterator<Item=bool>
+    {
+        let encoder = Encoder::new(*self)?;
+        check_len("codeword", self.n()/8, codeword.len())?;
+        let k = self.k();
+        for x in &mut codeword[..k/8] { *x = 0 }
+        let mut count = 0;
+        for bit in bits.into_iter().take(k + 1) {
+            if count < k && bit {
+                codeword[count/8] |= 1 << (7 - (count%8));
+            }
+            count += 1;
+        }
+        check_len("bits", k, count)?;
+        encoder.encode(codeword)
+    }
+
+    /// Get the length of [u8] required for a codeword with `extra` additional bits punctured.
+    ///
+    /// Equal to ceil((n - extra)/8).
+    pub const fn punctured_len(self, extra: usize) -> usize {
+        (self.n() - extra).div_ceil(8)
+    }
+
+    /// Compute only some of the parity bits of the codeword for `data`.
+    ///
+    /// Each parity bit is the sum of the generator matrix entries in its column for every set
+    /// data bit, so a few parity bits can be found without encoding the whole codeword, for
+    /// example to retransmit only the parity bits a receiver reports lost. Each bit takes time
+    /// proportional to k, so for more than a small fraction of the n-k parity bits, encoding
+    /// the whole codeword is quicker.
+    ///
+    /// * `data` must be k/8 bytes long
+    /// * `which_parity` lists the positions in the codeword of the parity bits to compute, in
+    ///   any order, each in `parity_bit_range()`, that is at least k and less than n
+    /// * `out` must be ceil(which_parity.len()/8) long, and is set to the parity bits in the
+    ///   order listed, packed most significant bit first, with any bits after the last set to 0
+    ///
+    /// Each bit is identical to the same bit of the codeword from `copy_encode`. Panics for
+    /// codes without a generator matrix; see `compact_generator`.
+    pub fn encode_parity_subset(&self, data: &[u8], which_parity: &[usize], out: &mut [u8]) {
+        assert_eq!(data.len() * 8, self.k(), "data must be k bits long");
+        assert_eq!(out.len(), which_parity.len().div_ceil(8), "out.len() incorrect");
+        assert!(!self.compact_generator().is_empty(), "no generator matrix for this code");
+
+        let k = self.k();
+        let r = self.n() - k;
+        let b = self.circulant_size();
+        let gc = self.compact_generator();
+        for x in &mut out[..] { *x = 0 }
+        for (idx, &pos) in which_parity.iter().enumerate() {
+            assert!(pos >= k && pos < self.n(), "parity position out of range");
+            let (block, col) = ((pos - k) / b, (pos - k) % b);
+            let mut parity = 0;
+            for bit in (0..k).filter(|&bit| data[bit/8] >> (7 - (bit%8)) & 1 == 1) {
+                // As in `iter_generator`, each generator row is its circulant's first row
+                // rotated right by the row's offset, within each block of b parity columns
+                let (crow, offset) = (bit / b, bit % b);
+                let src = block * b + (col + b - offset) % b;
+                parity ^= (gc[crow * (r / 64) + src / 64] >> (63 - (src % 64))) & 1;
+            }
+            out[idx/8] |= (parity as u8) << (7 - (idx%8));
+        }
+    }
+
+    /// Get the length of [u8] required for a shortened codeword carrying `info_bits` of data.
+    ///
+    /// Equal to ceil((n - k + info_bits)/8).
+    pub const fn shortened_len(self, info_bits: usize) -> usize {
+        (self.n() - self.k() + info_bits).div_ceil(8)
+    }
+
+    /// Encode a message shorter than k bits, without transmitting the padding.
+    ///
+    /// The first `info_bits` bits of `data` are taken as the message and the rest of the k data
+    /// bits are set to zero. As the receiver knows those bits are zero they need not be sent,
+    /// so `output` contains only the message bits followed by the n-k parity bits. This is
+    /// known as shortening the code, and lowers its rate to match a smaller payload. Use
+    /// `decode_ms_shortened` to decode.
+    ///
+    /// * `data` must be ceil(info_bits/8) long, and any bits after `info_bits` are ignored
+    /// * `info_bits` must be at most k
+    /// * `codeword` must be n bits long, and is used to hold the full codeword
+    /// * `output` must be `shortened_len(info_bits)` long, and is set to the message then
+    ///   parity bits, with any bits after the last set to 0
+    pub fn encode_shortened(&self, data: &[u8], info_bits: usize, codeword: &mut [u8],
+                            output: &mut [u8])
+    {
+        assert!(info_bits <= self.k(), "info_bits > k");
+        assert_eq!(data.len(), info_bits.div_ceil(8), "data.len() != ceil(info_bits/8)");
+        assert_eq!(codeword.len() * 8, self.n(), "codeword must be n bits long");
+        assert_eq!(output.len(), self.shortened_len(info_bits), "output.len() incorrect");
+
+        for x in &mut codeword[..] { *x = 0 }
+        codeword[..data.len()].copy_from_slice(data);
+        for bit in info_bits..data.len()*8 {
+            codeword[bit/8] &= !(1 << (7-(bit%8)));
+        }
+        self.encode(codeword);
+
+        for o in &mut output[..] { *o = 0 }
+        let bits = (0..info_bits).chain(self.k()..self.n());
+        for (out, bit) in bits.enumerate() {
+            if (codeword[bit/8] >> (7-(bit%8))) & 1 == 1 {
+                output[out/8] |= 1 << (7-(out%8));
+            }
+        }
+    }
+
+    /// Remove additional bits from a codeword before transmission.
+    ///
+    /// This punctures the codeword further than the code already does, raising its rate,
+    /// which is useful for rate matching. The receiver must know the same `extra_puncture`
+    /// positions and use `depuncture_llrs` to restore them as erasures before decoding.
+    ///
+    /// * `codeword` must be the n bit codeword, as produced by `encode`
+    /// * `extra_puncture` lists the positions in 0..n to remove, in strictly increasing order
+    /// * `output` must be `punctured_len(extra_puncture.len())` long, and is set to the
+    ///   remaining bits in order, with any bits after the last set to 0
+    ///
+    /// Puncturing data bits as well as parity bits is allowed, though generally performs worse.
+    pub fn puncture(&self, codeword: &[u8], extra_puncture: &[usize], output: &mut [u8]) {
+        assert_eq!(codeword.len() * 8, self.n(), "codeword must be n bits long");
+        assert_eq!(output.len(), self.punctured_len(extra_puncture.len()),
+                   "output.len() incorrect");
+        assert!(extra_puncture.windows(2).all(|w| w[0] < w[1]), "extra_puncture must be sorted");
+        assert!(extra_puncture.iter().all(|&x| x < self.n()), "extra_puncture >= n");
+
+        for o in &mut output[..] { *o = 0 }
+        let mut punctured = extra_puncture.iter().peekable();
+        let mut out = 0;
+        for bit in 0..self.n() {
+            if punctured.peek() == Some(&&bit) {
+                punctured.next();
+                continue;
+            }
+            if (codeword[bit/8] >> (7-(bit%8))) & 1 == 1 {
+                output[out/8] |= 1 << (7-(out%8));
+            }
+            out += 1;
+        }
+    }
+
+    /// Encode `data` and then remove additional bits, see `puncture`.
+    ///
+    /// * `data` must be k bits long
+    /// * `codeword` must be n bits long, and is used to hold the full codeword
+    /// * `output` must be `punctured_len(extra_puncture.len())` long, and receives the
+    ///   punctured codeword
+    pub fn encode_punctured(&self, data: &[u8], codeword: &mut [u8], extra_puncture: &[usize],
+                            output: &mut [u8])
+    {
+        self.copy_encode(data, codeword);
+        self.puncture(codeword, extra_puncture, output);
+    }
+
+    /// Get the length of [u64] required for the working area of `encode_from_parity`.
+    ///
+    /// Equal to r * ceil((r+1)/64), where r = n + punctured_bits - k.
+    pub const fn encode_from_parity_working_len(self) -> usize {
+        let r = self.n() + self.punctured_bits() - self.k();
+        r * (r + 1).div_ceil(64)
+    }
+
+    /// Encode a codeword using only the parity check matrix, without a generator matrix.
+    ///
+    /// This function assumes the first k bits of `codeword` have already been set to your
+    /// data, and will set the remaining n-k bits appropriately, exactly as `encode` does.
+    /// The result is bit-identical to `encode`.
+    ///
+    /// Instead of the generator matrix, the parity bits are found by directly solving the
+    /// parity check equations with Gauss-Jordan elimination. This needs a large working area
+    /// and takes time roughly proportional to the cube of the number of parity bits, so it is
+    /// much slower than `encode` and mainly useful as a reference, or for codes where a
+    /// generator matrix is not available.
+    ///
+    /// * `codeword` must be exactly n/8 bytes long.
+    /// * `working` must be `encode_from_parity_working_len()` long.
+    pub fn encode_from_parity<'a>(&self, codeword: &'a mut [u8], working: &mut [u64])
+        -> &'a mut [u8]
+    {
+        assert_eq!(codeword.len() * 8, self.n(), "codeword must be n bits long");
+        assert_eq!(working.len(), self.encode_from_parity_working_len(), "working.len() incorrect");
+
+        let n = self.n();
+        let k = self.k();
+        let r = n + self.punctured_bits() - k;
+
+        self.solve_parity(1, |var| u64::from(codeword[var/8] >> (7-(var%8)) & 1), working);
+
+        // Copy the solved (non-punctured) parity bits into the codeword
+        let w = (r + 1).div_ceil(64);
+        for x in &mut codeword[k/8..] { *x = 0 }
+        for (col, row) in working.chunks(w).enumerate().take(n - k) {
+            if row[r/64] >> (r%64) & 1 == 1 {
+                let var = k + col;
+                codeword[var/8] |= 1 << (7-(var%8));
+            }
+        }
+
+        codeword
+    }
+
+    /// Get the length of [u64] required for the working area of `init_generator_from_parity`.
+    ///
+    /// Equal to r * ceil((r + k/circulant_size)/64), where r = n + punctured_bits - k.
+    pub const fn init_generator_from_parity_working_len(self) -> usize {
+        let r = self.n() + self.punctured_bits() - self.k();
+        r * (r + self.k() / self.circulant_size()).div_ceil(64)
+    }
+
+    /// Compute the compact generator matrix for this code from its parity check matrix.
+    ///
+    /// The result is in the same form as `compact_generator()`, and is identical to it for
+    /// codes where a generator is included. It can be used with `Encoder::with_generator`,
+    /// which enables fast encoding of the k=16384 codes, or of any code whose cargo feature
+    /// has been disabled to avoid storing its generator in flash.
+    ///
+    /// The first row of each circulant block of the generator is the parity of a single data
+    /// bit, so every block is found at once by solving the parity check equations with
+    /// Gauss-Jordan elimination, as for `encode_from_parity`, with one right hand side per
+    /// block. This takes time roughly proportional to the cube of the number of parity bits,
+    /// up to around ten seconds for the largest codes in a release build, so it is meant to be
+    /// run once at startup on a hosted system, or ahead of time with the result stored.
+    ///
+    /// * `generator` must be `compact_generator_len()` long.
+    /// * `working` must be `init_generator_from_parity_working_len()` long.
+    pub fn init_generator_from_parity(&self, generator: &mut [u64], working: &mut [u64]) {
+        assert_eq!(generator.len(), self.compact_generator_len(), "generator.len() incorrect");
+        assert_eq!(working.len(), self.init_generator_from_parity_working_len(),
+                   "working.len() incorrect");
+
+        let k = self.k();
+        let b = self.circulant_size();
+        let r = self.n() + self.punctured_bits() - k;
+        let row_len = (self.n() - k) / 64;
+
+        // Right hand side `crow` is the data vector with only bit crow*b set, packed into one
+        // u64 per data bit, so there can be at most 64 of them
+        let rhs = k / b;
+        assert!(rhs <= 64, "too many circulant rows to solve at once");
+        self.solve_parity(rhs, |var| if var % b == 0 { 1 << (var / b) } else { 0 }, working);
+
+        // Pack each solution's (non-punctured) parity bits into its generator row
+        let w = (r + rhs).div_ceil(64);
+        for x in &mut generator[..] { *x = 0 }
+        for (crow, gc) in generator.chunks_mut(row_len).enumerate() {
+            let col = r + crow;
+            for (idx, row) in working.chunks(w).enumerate().take(self.n() - k) {
+                if row[col/64] >> (col%64) & 1 == 1 {
+                    gc[idx/64] |= 1 << (63 - (idx%64));
+                }
+            }
+        }
+    }
+
+    /// Solve the parity check equations for all n+p-k parity bits given `rhs` sets of the k
+    /// data bits at once, where `rhs` is at most 64.
+    ///
+    /// Bit j of `data(i)` is data bit i of set j. On return, row j of `working` (each row
+    /// being ceil((r+rhs)/64) words long) has bit r+s set to the value of parity bit k+j for
+    /// set s.
+    fn solve_parity<F: Fn(usize) -> u64>(&self, rhs: usize, data: F, working: &mut [u64]) {
+        let k = self.k();
+        let r = self.n() + self.punctured_bits() - k;
+        let w = (r + rhs).div_ceil(64);
+
+        // Build the dense system H_p x = H_d d, one row per check, with the right hand sides
+        // stored in columns r onwards.
+        for x in &mut working[..] { *x = 0 }
+        for (check, var) in self.iter_paritychecks() {
+            if var >= k {
+                let col = var - k;
+                working[check*w + col/64] ^= 1 << (col%64);
+            } else {
+                let mut sets = data(var);
+                while sets != 0 {
+                    let col = r + sets.trailing_zeros() as usize;
+                    working[check*w + col/64] ^= 1 << (col%64);
+                    sets &= sets - 1;
+                }
+            }
+        }
+
+        // Gauss-Jordan elimination to reduce H_p to the identity
+        for col in 0..r {
+            let word = col/64;
+            let bit = 1 << (col%64);
+            let pivot = (col..r).find(|&row| working[row*w + word] & bit != 0)
+                                .expect("parity check matrix is not full rank");
+            if pivot != col {
+                for i in word..w {
+                    working.swap(pivot*w + i, col*w + i);
+                }
+            }
+            for row in 0..r {
+                if row != col && working[row*w + word] & bit != 0 {
+                    for i in word..w {
+                        working[row*w + i] ^= working[col*w + i];
+                    }
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::prelude::v1::*;
 
-    use crate::codes::LDPCCode;
+    use crate::codes::{encodable, LDPCCode};
+
+    const CODES: [LDPCCode;  9] = [LDPCCode::TC128,   LDPCCode::TC256,   LDPCCode::TC512,
+                                   LDPCCode::TM1280,  LDPCCode::TM1536,  LDPCCode::TM2048,
+                                   LDPCCode::TM5120,  LDPCCode::TM6144,  LDPCCode::TM8192,
+    ];
 
     macro_rules! test_encode {
         ($code:path, $parity:expr) => {
-            let code = $code;
-            let parity = $parity;
-            let txdata: Vec<u8> = (0..code.k()/8).map(|i| i as u8).collect();
+            // Only codes whose generator is included in this build can be encoded
+            for code in encodable(&[$code]) {
+                let parity = $parity;
+                let txdata: Vec<u8> = (0..code.k()/8).map(|i| i as u8).collect();
 
-            // First check we can encode OK in the totally normal way
-            let mut txcode = vec![0u8; code.n()/8];
-            txcode[..code.k()/8].copy_from_slice(&txdata);
-            let rxcode = code.encode(&mut txcode);
-            let (rxdata, rxparity) = rxcode.split_at(code.k()/8);
-            assert_eq!(rxdata, &txdata[..]);
-            assert_eq!(rxparity, &parity[..]);
+                // First check we can encode OK in the totally normal way
+                let mut txcode = vec![0u8; code.n()/8];
+                txcode[..code.k()/8].copy_from_slice(&txdata);
+                let rxcode = code.encode(&mut txcode);
+                let (rxdata, rxparity) = rxcode.split_at(code.k()/8);
+                assert_eq!(rxdata, &txdata[..]);
+                assert_eq!(rxparity, &parity[..]);
 
-            // Now check copy_encode works
-            let mut txcode = vec![0u8; code.n()/8];
-            let rxcode = code.copy_encode(&txdata, &mut txcode);
-            let (rxdata, rxparity) = rxcode.split_at(code.k()/8);
-            assert_eq!(rxdata, &txdata[..]);
-            assert_eq!(rxparity, &parity[..]);
-
-            // Now check for u32 version
-            let mut txcode = vec![0u32; code.n()/32];
-            let rxcode = code.copy_encode(&txdata, &mut txcode);
-            let (rxdata, rxparity) = rxcode.split_at(code.k()/8);
-            assert_eq!(rxdata, &txdata[..]);
-            assert_eq!(rxparity, &parity[..]);
-
-            // Now check for u64 version
-            let mut txcode = vec![0u64; code.n()/64];
-            let rxcode = code.copy_encode(&txdata, &mut txcode);
-            let (rxdata, rxparity) = rxcode.split_at(code.k()/8);
-            assert_eq!(rxdata, &txdata[..]);
-            assert_eq!(rxparity, &parity[..]);
+                // Check encoding in place overwrites any existing parity bits, for every type
+                let mut txcode = vec![0xFFu8; code.n()/8];
+                txcode[..code.k()/8].copy_from_slice(&txdata);
+                assert_eq!(code.encode(&mut txcode)[code.k()/8..], parity[..]);
+                let mut txcode = vec![0u32; code.n()/32];
+                code.copy_encode(&txdata, &mut txcode);
+                let expected = txcode.clone();
+                for x in &mut txcode[code.k()/32..] { *x = !0 }
+                code.encode(&mut txcode);
+                assert_eq!(txcode, expected);
+                let mut txcode = vec![0u64; code.n()/64];
+                code.copy_encode(&txdata, &mut txcode);
+                let expected = txcode.clone();
+                for x in &mut txcode[code.k()/64..] { *x = !0 }
+                code.encode(&mut txcode);
+                assert_eq!(txcode, expected);
+
+                // Now check copy_encode works
+                let mut txcode = vec![0u8; code.n()/8];
+                let rxcode = code.copy_encode(&txdata, &mut txcode);
+                let (rxdata, rxparity) = rxcode.split_at(code.k()/8);
+                assert_eq!(rxdata, &txdata[..]);
+                assert_eq!(rxparity, &parity[..]);
+
+                // Now check for u32 version
+                let mut txcode = vec![0u32; code.n()/32];
+                let rxcode = code.copy_encode(&txdata, &mut txcode);
+                let (rxdata, rxparity) = rxcode.split_at(code.k()/8);
+                assert_eq!(rxdata, &txdata[..]);
+                assert_eq!(rxparity, &parity[..]);
+
+                // Now check for u64 version
+                let mut txcode = vec![0u64; code.n()/64];
+                let rxcode = code.copy_encode(&txdata, &mut txcode);
+                let (rxdata, rxparity) = rxcode.split_at(code.k()/8);
+                assert_eq!(rxdata, &txdata[..]);
+                assert_eq!(rxparity, &parity[..]);
+            }
         };
     }
 
+    #[test]
+    fn test_encode_from_parity() {
+        // The larger codes are too slow to solve in a debug build, so we stop at TM5120.
+        let codes = [LDPCCode::TC128, LDPCCode::TC256, LDPCCode::TC512,
+                     LDPCCode::TM1280, LDPCCode::TM1536, LDPCCode::TM2048, LDPCCode::TM5120];
+        for code in &encodable(&codes) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|i| (i * 7) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            let mut codeword = vec![0xFFu8; code.n()/8];
+            codeword[..code.k()/8].copy_from_slice(&txdata);
+            let mut working = vec![0u64; code.encode_from_parity_working_len()];
+            code.encode_from_parity(&mut codeword, &mut working);
+            assert_eq!(codeword, txcode);
+        }
+    }
+
+    #[test]
+    fn test_init_generator_from_parity() {
+        // As for encode_from_parity, the larger codes are too slow to solve in a debug build
+        let codes = [LDPCCode::TC128, LDPCCode::TC256, LDPCCode::TC512,
+                     LDPCCode::TM1280, LDPCCode::TM1536, LDPCCode::TM2048];
+        for code in &encodable(&codes) {
+            let mut generator = vec![0u64; code.compact_generator_len()];
+            let mut working = vec![0u64; code.init_generator_from_parity_working_len()];
+            code.init_generator_from_parity(&mut generator, &mut working);
+            assert_eq!(&generator[..], code.compact_generator());
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn test_init_generator_from_parity_tm20480() {
+        // Too slow for a debug build: run with `cargo test --release -- --ignored`
+        use super::Encoder;
+        let code = LDPCCode::TM20480;
+        let mut generator = vec![0u64; code.compact_generator_len()];
+        let mut working = vec![0u64; code.init_generator_from_parity_working_len()];
+        code.init_generator_from_parity(&mut generator, &mut working);
+
+        let encoder = Encoder::with_generator(code, &generator).unwrap();
+        let data: Vec<u8> = (0..code.k()/8).map(|i| (i * 7) as u8).collect();
+        let mut codeword = vec![0u8; code.output_len()];
+        encoder.copy_encode(&data, &mut codeword[..code.n()/8]).unwrap();
+
+        // Fill in the punctured bits so every parity check can be tested
+        let mut working_bf = vec![0u8; code.decode_bf_working_len()];
+        let mut output = vec![0u8; code.output_len()];
+        assert!(code.decode_bf(&codeword[..code.n()/8], &mut output, &mut working_bf, 50).0);
+        assert_eq!(&output[..code.n()/8], &codeword[..code.n()/8]);
+
+        let mut syndrome = vec![0xFFu8; code.syndrome_len()];
+        assert!(code.compute_syndrome(&output, &mut syndrome));
+        assert!(syndrome.iter().all(|s| *s == 0));
+    }
+
     #[test]
     fn test_encode() {
         test_encode!(LDPCCode::TC128,
@@ -500,4 +1414,332 @@ mod tests {
                       0x58, 0x77, 0x8C, 0x46, 0x31, 0x2B, 0x7E, 0xC1,
                       0xCB, 0x4A, 0x59, 0xFC, 0x44, 0xFA, 0xF8, 0x70]);
     }
+
+    #[test]
+    fn test_encode_zero() {
+        // The codes are linear, so all-zero data must encode to the all-zero codeword
+        for code in &encodable(&CODES) {
+            let mut codeword = vec![0xFFu8; code.n()/8];
+            code.copy_encode(&vec![0u8; code.k()/8], &mut codeword);
+            assert!(codeword.iter().all(|&x| x == 0));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tm1280")]
+    fn test_copy_encode_words() {
+        let code = LDPCCode::TM1280;
+        let data: Vec<u8> = (0..code.k()/8).map(|i| (i * 7) as u8).collect();
+        let mut expected = vec![0u8; code.n()/8];
+        code.copy_encode(&data, &mut expected);
+
+        // Pack the data into words in memory order, as a caller's buffers would be
+        let data_u32: Vec<u32> = data.chunks(4)
+            .map(|x| u32::from_ne_bytes([x[0], x[1], x[2], x[3]])).collect();
+        let mut codeword_u32 = vec![0u32; code.n()/32];
+        assert_eq!(code.copy_encode_words(&data_u32, &mut codeword_u32), &expected[..]);
+
+        let data_u64: Vec<u64> = data.chunks(8)
+            .map(|x| { let mut b = [0u8; 8]; b.copy_from_slice(x); u64::from_ne_bytes(b) })
+            .collect();
+        let mut codeword_u64 = vec![0u64; code.n()/64];
+        code.copy_encode_words(&data_u64, &mut codeword_u64);
+        for (word, bytes) in codeword_u64.iter().zip(expected.chunks(8)) {
+            assert_eq!(&word.to_ne_bytes()[..], bytes);
+        }
+    }
+
+    #[test]
+    fn test_encode_with_default() {
+        use super::EncodeInto;
+
+        // A type implementing only the required methods, as a downstream impl written against
+        // the original trait would, falls back to the provided `encode_with` bodies.
+        struct Word(u16);
+        impl EncodeInto for Word {
+            fn encode<'a>(code: &LDPCCode, codeword: &'a mut[Self]) -> &'a mut [u8] {
+                Self::encode_with(code, code.compact_generator(), codeword)
+            }
+            fn copy_encode<'a>(code: &LDPCCode, data: &[u8], codeword: &'a mut[Self])
+                -> &'a mut [u8]
+            {
+                Self::copy_encode_with(code, code.compact_generator(), data, codeword)
+            }
+            fn bitlength() -> usize { 16 }
+        }
+
+        for code in &encodable(&CODES) {
+            let data: Vec<u8> = (0..code.k()/8).map(|i| (i * 13) as u8).collect();
+            let mut expected = vec![0u8; code.n()/8];
+            code.copy_encode(&data, &mut expected);
+            let mut codeword: Vec<Word> = (0..code.n()/16).map(|_| Word(0xFFFF)).collect();
+            assert_eq!(Word::copy_encode(code, &data, &mut codeword), &expected[..]);
+            assert_eq!(Word::encode(code, &mut codeword), &expected[..]);
+            assert_eq!(codeword[0].0, u16::from_ne_bytes([expected[0], expected[1]]));
+        }
+    }
+
+    #[test]
+    fn test_try_encode_no_generator() {
+        use super::EncodeError;
+        let mut codeword = vec![0u8; LDPCCode::TM20480.n()/8];
+        assert_eq!(LDPCCode::TM20480.try_encode(&mut codeword), Err(EncodeError::NoGenerator));
+    }
+
+    #[test]
+    #[cfg(feature = "tc128")]
+    fn test_try_encode() {
+        use super::EncodeError;
+        let code = LDPCCode::TC128;
+        let data = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+        let mut codeword = [0u8; 16];
+        assert_eq!(code.try_copy_encode(&data, &mut codeword).unwrap()[8..],
+                   [0x34, 0x99, 0x98, 0x87, 0x94, 0xE1, 0x62, 0x56]);
+        let mut codeword_u32 = [0u32; 4];
+        assert!(code.try_encode(&mut codeword_u32).is_ok());
+
+        // A buffer sized for another code is rejected rather than panicking
+        let mut wrong = vec![0u8; LDPCCode::TM2048.n()/8];
+        assert_eq!(code.try_copy_encode(&data, &mut wrong),
+                   Err(EncodeError::BadBufferLen { which: "codeword", expected: 128,
+                                                   got: 2048 }));
+        assert_eq!(code.try_copy_encode(&data[..4], &mut codeword),
+                   Err(EncodeError::BadBufferLen { which: "data", expected: 64, got: 32 }));
+    }
+
+    #[test]
+    #[cfg(feature = "tc128")]
+    fn test_copy_encode_iter() {
+        use super::EncodeError;
+        let code = LDPCCode::TC128;
+        let data = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+        let bits = (0..64).map(|i| (data[i/8] >> (7 - (i%8))) & 1 == 1);
+        let mut codeword = [0xFFu8; 16];
+        assert_eq!(code.copy_encode_iter(bits, &mut codeword).unwrap()[8..],
+                   [0x34, 0x99, 0x98, 0x87, 0x94, 0xE1, 0x62, 0x56]);
+        assert_eq!(&codeword[..8], &data[..]);
+
+        assert_eq!(code.copy_encode_iter((0..63).map(|_| true), &mut codeword),
+                   Err(EncodeError::BadBufferLen { which: "bits", expected: 64, got: 63 }));
+        assert_eq!(code.copy_encode_iter(core::iter::repeat(false), &mut codeword),
+                   Err(EncodeError::BadBufferLen { which: "bits", expected: 64, got: 65 }));
+    }
+
+    #[test]
+    fn test_encoder() {
+        use super::{Encoder, EncodeError};
+
+        for code in &encodable(&CODES) {
+            assert_eq!(code.compact_generator().len(), code.compact_generator_len());
+            let encoder = Encoder::new(*code).unwrap();
+            let txdata: Vec<u8> = (0..code.k()/8).map(|i| i as u8).collect();
+            let mut expected = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut expected);
+
+            let mut codeword = vec![0u64; code.n()/64];
+            assert_eq!(encoder.copy_encode(&txdata, &mut codeword).unwrap(), &expected[..]);
+            let mut codeword = vec![0u8; code.n()/8 - 1];
+            assert_eq!(encoder.encode(&mut codeword).err(),
+                       Some(EncodeError::BadBufferLen { which: "codeword", expected: code.n(),
+                                                        got: code.n() - 8 }));
+
+            // An encoder with an explicit generator must behave identically
+            let encoder = Encoder::with_generator(*code, code.compact_generator()).unwrap();
+            let mut codeword = vec![0u8; code.n()/8];
+            assert_eq!(encoder.copy_encode(&txdata, &mut codeword).unwrap(), &expected[..]);
+        }
+
+        let code = LDPCCode::TM20480;
+        assert_eq!(Encoder::new(code).err(), Some(EncodeError::NoGenerator));
+        let g = vec![0u64; code.compact_generator_len()];
+        assert_eq!(Encoder::with_generator(code, &g).err(),
+                   Some(EncodeError::UninitialisedGenerator));
+        assert_eq!(Encoder::with_generator(code, &g[1..]).err(),
+                   Some(EncodeError::BadBufferLen { which: "generator", expected: g.len(),
+                                                    got: g.len() - 1 }));
+    }
+
+    #[test]
+    fn test_stream_encoder() {
+        use super::{Encoder, EncodeError, StreamEncoder};
+
+        for code in &encodable(&CODES) {
+            let kb = code.k() / 8;
+            let data: Vec<u8> = (0..5*kb/2).map(|i| (i * 7) as u8).collect();
+            let mut buf = vec![0u8; code.n()/8];
+            let mut stream = StreamEncoder::new(Encoder::new(*code).unwrap(), &mut buf).unwrap();
+
+            // Write in uneven pieces which straddle block boundaries
+            let mut codewords = Vec::new();
+            let mut emitted = 0;
+            for chunk in data.chunks(kb/3 + 1) {
+                emitted += stream.write(chunk, |c| codewords.push(c.to_vec()));
+            }
+            assert_eq!(emitted, 2);
+            assert_eq!(stream.buffered(), kb/2);
+            assert_eq!(stream.finish(|c| codewords.push(c.to_vec())), kb - kb/2);
+            assert_eq!(stream.finish(|c| codewords.push(c.to_vec())), 0);
+            assert_eq!(codewords.len(), 3);
+
+            let mut padded = data.clone();
+            padded.resize(3 * kb, 0);
+            for (block, codeword) in padded.chunks(kb).zip(codewords.iter()) {
+                let mut expected = vec![0u8; code.n()/8];
+                code.copy_encode(block, &mut expected);
+                assert_eq!(codeword, &expected);
+            }
+        }
+
+        let mut buf = [0u8; 15];
+        for code in encodable(&[LDPCCode::TC128]) {
+            assert_eq!(StreamEncoder::new(Encoder::new(code).unwrap(), &mut buf).err(),
+                       Some(EncodeError::BadBufferLen { which: "codeword", expected: 128,
+                                                        got: 120 }));
+        }
+    }
+
+    #[test]
+    fn test_verify_generator() {
+        for code in &encodable(&CODES) {
+            let mut codeword = vec![0u8; code.output_len()];
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            let g = code.compact_generator();
+            assert!(code.verify_generator(g, &mut codeword, &mut working));
+            assert!(!code.verify_generator(&g[1..], &mut codeword, &mut working));
+
+            // Any single corrupted bit must be caught
+            let mut bad = g.to_vec();
+            let idx = bad.len() - 1;
+            bad[idx] ^= 1 << 17;
+            assert!(!code.verify_generator(&bad, &mut codeword, &mut working));
+            let zeros = vec![0u64; g.len()];
+            assert!(!code.verify_generator(&zeros, &mut codeword, &mut working));
+        }
+
+        // TM1280 and TM1536 have compact generators of the same length, but they differ
+        #[cfg(all(feature = "tm1280", feature = "tm1536"))]
+        {
+            let code = LDPCCode::TM1536;
+            let g = LDPCCode::TM1280.compact_generator();
+            assert_eq!(g.len(), code.compact_generator_len());
+            let mut codeword = vec![0u8; code.output_len()];
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            assert!(!code.verify_generator(g, &mut codeword, &mut working));
+        }
+    }
+
+    #[test]
+    fn test_self_test() {
+        let large = [LDPCCode::TM20480, LDPCCode::TM24576, LDPCCode::TM32768];
+        for code in CODES.iter().chain(large.iter()) {
+            let mut codeword = vec![0u8; code.output_len()];
+            let mut working = vec![0u8; code.decode_bf_working_len()];
+            assert!(code.self_test(&mut codeword, &mut working));
+        }
+    }
+
+    #[test]
+    fn test_simd_encode() {
+        // The u8 encoder is always scalar, so check the u64 encoder (which uses SIMD with
+        // the `simd` feature) produces bit-identical codewords for every code.
+        for code in &encodable(&CODES) {
+            for seed in 0..4u32 {
+                let txdata: Vec<u8> = (0..code.k()/8)
+                    .map(|i| ((i as u32).wrapping_mul(2654435761).wrapping_add(seed) >> 7) as u8)
+                    .collect();
+                let mut scalar = vec![0u8; code.n()/8];
+                code.copy_encode(&txdata, &mut scalar);
+                let mut simd = vec![0u64; code.n()/64];
+                assert_eq!(code.copy_encode(&txdata, &mut simd), &scalar[..]);
+            }
+        }
+
+        // Also check the XOR helper directly, including odd lengths
+        for len in 0..9 {
+            let src: Vec<u64> = (0..len as u64).map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+                                               .collect();
+            let mut a: Vec<u64> = (0..len as u64).map(|i| !i << 3).collect();
+            let mut b = a.clone();
+            super::xor_words(&mut a, &src);
+            super::xor_words_scalar(&mut b, &src);
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn test_encode_parity_subset() {
+        for code in &encodable(&CODES) {
+            let txdata: Vec<u8> = (0..code.k()/8).map(|i| (i * 41) as u8).collect();
+            let mut txcode = vec![0u8; code.n()/8];
+            code.copy_encode(&txdata, &mut txcode);
+
+            // Every 7th parity bit, in reverse order, plus the first and last
+            let mut which: Vec<usize> = code.parity_bit_range().rev().step_by(7).collect();
+            which.push(code.k());
+            which.push(code.n() - 1);
+            let mut out = vec![0xFFu8; which.len().div_ceil(8)];
+            code.encode_parity_subset(&txdata, &which, &mut out);
+            for (idx, &pos) in which.iter().enumerate() {
+                assert_eq!(out[idx/8] >> (7 - (idx%8)) & 1, txcode[pos/8] >> (7 - (pos%8)) & 1);
+            }
+            for idx in which.len()..out.len()*8 {
+                assert_eq!(out[idx/8] >> (7 - (idx%8)) & 1, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_shortened() {
+        for code in &encodable(&CODES) {
+            let info_bits = code.k() / 2 + 3;
+            let mut data: Vec<u8> = (0..info_bits.div_ceil(8)).map(|i| i as u8 | 0x81).collect();
+            let mut codeword = vec![0u8; code.n()/8];
+            let mut txcode = vec![0u8; code.shortened_len(info_bits)];
+            code.encode_shortened(&data, info_bits, &mut codeword, &mut txcode);
+
+            // Must match the full codeword of the zero padded data, with the padding removed
+            let last = info_bits / 8;
+            data[last] &= 0xE0;
+            data.resize(code.k()/8, 0);
+            let mut expected = vec![0u8; code.n()/8];
+            code.copy_encode(&data, &mut expected);
+            assert_eq!(codeword, expected);
+            assert_eq!(&txcode[..last], &data[..last]);
+            for idx in 0..code.n() - code.k() {
+                let out = info_bits + idx;
+                let bit = code.k() + idx;
+                assert_eq!((txcode[out/8] >> (7-(out%8))) & 1,
+                           (expected[bit/8] >> (7-(bit%8))) & 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_punctured() {
+        for code in &encodable(&CODES) {
+            assert_eq!(code.puncture_pattern().len(), code.punctured_bits());
+
+            // Puncture every 16th parity bit
+            let extra: Vec<usize> = (code.k()..code.n()).step_by(16).collect();
+            let txdata: Vec<u8> = (0..code.k()/8).map(|i| i as u8).collect();
+            let mut codeword = vec![0u8; code.n()/8];
+            let mut txcode = vec![0u8; code.punctured_len(extra.len())];
+            code.encode_punctured(&txdata, &mut codeword, &extra, &mut txcode);
+
+            // Convert to LLRs, restore the punctured bits, and decode
+            let mut received = vec![0i16; code.n() - extra.len()];
+            for (idx, llr) in received.iter_mut().enumerate() {
+                *llr = if (txcode[idx/8] >> (7-(idx%8))) & 1 == 1 { -1 } else { 1 };
+            }
+            let mut llrs = vec![0i16; code.n()];
+            code.depuncture_llrs(&received, &extra, &mut llrs);
+            assert!(extra.iter().all(|&x| llrs[x] == 0));
+
+            let mut working = vec![0i16; code.decode_ms_working_len()];
+            let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+            let mut output = vec![0u8; code.output_len()];
+            assert!(code.decode_ms(&llrs, &mut output, &mut working, &mut working_u8, 50).0);
+            assert_eq!(&output[..code.n()/8], &codeword[..]);
+        }
+    }
 }
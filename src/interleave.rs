@@ -0,0 +1,186 @@
+// Copyright 2017 Adam Greig
+// Licensed under the MIT license, see LICENSE for details.
+
+//! This module provides a block interleaver for spreading burst errors across codewords.
+//!
+//! The codes are good at correcting errors scattered randomly through a codeword, but a burst
+//! of consecutive errors, as caused by a fade, can overwhelm a single codeword. A row-column
+//! interleaver writes several codewords into the rows of a matrix and transmits it column by
+//! column, so consecutive transmitted bits come from different codewords. After
+//! deinterleaving at the receiver, a burst of `b` bits becomes at most `ceil(b/depth)` errors
+//! in each codeword.
+//!
+//! ```
+//! # #[cfg(feature = "tc128")] {
+//! # use labrador_ldpc::LDPCCode;
+//! # use labrador_ldpc::interleave::RowColumnInterleaver;
+//! let code = LDPCCode::TC128;
+//! let interleaver = RowColumnInterleaver::for_code(code, 4);
+//!
+//! // Encode four codewords back to back
+//! let mut codewords = vec![0u8; interleaver.buffer_len()];
+//! for (idx, codeword) in codewords.chunks_mut(code.n()/8).enumerate() {
+//!     code.copy_encode(&[idx as u8; 8], codeword);
+//! }
+//!
+//! // Interleave for transmission, then deinterleave on reception
+//! let mut tx = vec![0u8; interleaver.buffer_len()];
+//! interleaver.interleave(&codewords, &mut tx);
+//! let mut rx = vec![0u8; interleaver.buffer_len()];
+//! interleaver.deinterleave(&tx, &mut rx);
+//! assert_eq!(rx, codewords);
+//! # }
+//! ```
+
+use crate::codes::LDPCCode;
+
+/// Row-column block interleaver.
+///
+/// Bits are written into a matrix of `rows` rows and `cols` columns row by row, and read out
+/// column by column. Usually each row is one codeword, so `rows` is the interleaving depth
+/// and `cols` is the codeword length n.
+///
+/// All methods operate on the packed representation used by the encoders, most significant
+/// bit first, with buffers `buffer_len()` bytes long. If `rows * cols` is not a multiple of 8,
+/// the unused bits at the end of each output buffer are set to 0.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub struct RowColumnInterleaver {
+    rows: usize,
+    cols: usize,
+}
+
+impl RowColumnInterleaver {
+    /// Create an interleaver with `rows` rows of `cols` bits each.
+    ///
+    /// Both `rows` and `cols` must be non-zero.
+    pub fn new(rows: usize, cols: usize) -> RowColumnInterleaver {
+        assert!(rows > 0 && cols > 0, "rows and cols must be non-zero");
+        RowColumnInterleaver { rows, cols }
+    }
+
+    /// Create an interleaver for `depth` codewords of `code`, one codeword per row.
+    pub fn for_code(code: LDPCCode, depth: usize) -> RowColumnInterleaver {
+        RowColumnInterleaver::new(depth, code.n())
+    }
+
+    /// Get the number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Get the number of columns.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Get the length of [u8] of the buffers used by this interleaver.
+    ///
+    /// Equal to ceil(rows * cols / 8).
+    pub fn buffer_len(&self) -> usize {
+        (self.rows * self.cols).div_ceil(8)
+    }
+
+    /// Interleave `input`, written row by row, into `output`, read column by column.
+    ///
+    /// Both `input` and `output` must be `buffer_len()` long.
+    pub fn interleave(&self, input: &[u8], output: &mut [u8]) {
+        assert_eq!(input.len(), self.buffer_len(), "input.len() incorrect");
+        assert_eq!(output.len(), self.buffer_len(), "output.len() incorrect");
+        for o in &mut output[..] { *o = 0 }
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let from = row * self.cols + col;
+                let to = col * self.rows + row;
+                if (input[from/8] >> (7-(from%8))) & 1 == 1 {
+                    output[to/8] |= 1 << (7-(to%8));
+                }
+            }
+        }
+    }
+
+    /// Reverse `interleave`, restoring the original row by row order.
+    ///
+    /// Both `input` and `output` must be `buffer_len()` long.
+    pub fn deinterleave(&self, input: &[u8], output: &mut [u8]) {
+        assert_eq!(input.len(), self.buffer_len(), "input.len() incorrect");
+        assert_eq!(output.len(), self.buffer_len(), "output.len() incorrect");
+        for o in &mut output[..] { *o = 0 }
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let from = col * self.rows + row;
+                let to = row * self.cols + col;
+                if (input[from/8] >> (7-(from%8))) & 1 == 1 {
+                    output[to/8] |= 1 << (7-(to%8));
+                }
+            }
+        }
+    }
+
+    /// Reverse `interleave` on soft information, such as received LLRs.
+    ///
+    /// Each entry of `input` and `output` is one bit, so both must be `rows * cols` long.
+    pub fn deinterleave_soft<T: Copy>(&self, input: &[T], output: &mut [T]) {
+        assert_eq!(input.len(), self.rows * self.cols, "input.len() != rows * cols");
+        assert_eq!(output.len(), self.rows * self.cols, "output.len() != rows * cols");
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                output[row * self.cols + col] = input[col * self.rows + row];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::v1::*;
+
+    use super::RowColumnInterleaver;
+
+    #[test]
+    fn test_interleaver() {
+        // A small example which is easy to check by hand
+        let interleaver = RowColumnInterleaver::new(2, 8);
+        let mut output = [0u8; 2];
+        interleaver.interleave(&[0xFF, 0x00], &mut output);
+        assert_eq!(output, [0xAA, 0xAA]);
+        let mut restored = [0u8; 2];
+        interleaver.deinterleave(&output, &mut restored);
+        assert_eq!(restored, [0xFF, 0x00]);
+    }
+
+    #[test]
+    #[cfg(feature = "tm1280")]
+    fn test_interleaver_burst() {
+        use crate::codes::LDPCCode;
+
+        // A burst of errors is spread across every codeword, so each can be corrected
+        let code = LDPCCode::TM1280;
+        let depth = 8;
+        let interleaver = RowColumnInterleaver::for_code(code, depth);
+        let mut codewords = vec![0u8; interleaver.buffer_len()];
+        for (idx, codeword) in codewords.chunks_mut(code.n()/8).enumerate() {
+            let data: Vec<u8> = (0..code.k()/8).map(|x| (x * idx) as u8).collect();
+            code.copy_encode(&data, codeword);
+        }
+        let mut tx = vec![0u8; interleaver.buffer_len()];
+        interleaver.interleave(&codewords, &mut tx);
+        for x in &mut tx[100..104] { *x ^= 0xFF }
+
+        let mut llrs = vec![0f32; depth * code.n()];
+        for (idx, llr) in llrs.iter_mut().enumerate() {
+            *llr = if (tx[idx/8] >> (7-(idx%8))) & 1 == 1 { -1.0 } else { 1.0 };
+        }
+        let mut rx = vec![0f32; depth * code.n()];
+        interleaver.deinterleave_soft(&llrs, &mut rx);
+
+        let mut working = vec![0f32; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        let mut output = vec![0u8; code.output_len()];
+        for (llrs, codeword) in rx.chunks(code.n()).zip(codewords.chunks(code.n()/8)) {
+            let (success, _) = code.decode_ms(llrs, &mut output, &mut working,
+                                              &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..code.n()/8], codeword);
+        }
+    }
+}
@@ -14,11 +14,12 @@
 //!
 //! No memory allocations are made inside this crate so most methods require you to pass in an
 //! allocated block of memory for them to use. Check individual method documentation for further
-//! details.
+//! details, or use `LDPCCode::buffer_plan()` to get every length a particular decoder needs.
 //!
 //! ## Example
 //!
 //! ```
+//! # #[cfg(feature = "tc128")] {
 //! use labrador_ldpc::LDPCCode;
 //!
 //! // Pick the TC128 code, n=128 k=64
@@ -47,6 +48,7 @@
 //!
 //! // Check the errors got corrected
 //! assert_eq!(&rxdata[..8], &txdata[..8]);
+//! # }
 //! ```
 //!
 //! ## Codes
@@ -65,8 +67,8 @@
 //! They are the same codes defined in CCSDS document 231.1-O-1 and subsequent revisions (although
 //! the n=256 code is eventually removed, it lives on here as it's quite useful).
 //!
-//! The TM codes are available in r=1/2, r=2/3, and r=4/5, for dimensions k=1024 and k=4096.
-//! They are the same codes defined in CCSDS document 131.0-B-2 and subsequent revisions.
+//! The TM codes are available in r=1/2, r=2/3, and r=4/5, for dimensions k=1024, k=4096, and
+//! k=16384. They are the same codes defined in CCSDS document 131.0-B-2 and subsequent revisions.
 //!
 //! For more information on the codes themselves please see the CCSDS publications:
 //! https://public.ccsds.org/
@@ -78,9 +80,12 @@
 //! especially if they need to be decoded on a constrained system such as an embedded platform.
 //! For most other data transfer, the TM codes are more flexible and generally better suited.
 //!
-//! The very large k=16384 TM codes have not been included due to the complexity in generating
-//! their generator matrices and the very long constants involved, but it would be theoretically
-//! possible to include them. The relevant parity check constants are already included.
+//! The very large k=16384 TM codes (TM20480, TM24576, and TM32768) can be decoded as normal,
+//! but their generator matrices are not included due to the very long constants involved, so
+//! `encode` and `copy_encode` will panic for them. They can be encoded with `encode_from_parity`,
+//! though this needs several megabytes of working area and is very slow. Alternatively,
+//! `init_generator_from_parity` computes their generator once at startup, after which an
+//! `Encoder::with_generator` encodes them as quickly as the other codes.
 //!
 //! ### Generator Matrices
 //!
@@ -94,8 +99,19 @@
 //! number of the final rows are stored, and the rest can be inferred from those at runtime. Our
 //! encoder methods just use this compact form directly, so it doesn't ever need to be expanded.
 //!
+//! Using the compact form is also the fastest approach on desktop machines: it is at most 4KB
+//! for any code, so it stays in the L1 cache for the whole encode. An encoder which instead
+//! streamed a fully expanded generator, laid out column by column for sequential access, was
+//! measured at several times slower on the larger codes, as it must read the entire matrix
+//! (1MB for TM6144) from memory for every codeword.
+//!
 //! The relevant constants are in the `codes.compact_generators` module, with names like `TC128_G`.
 //!
+//! Each generator is only built in when the cargo feature of the same name as its code, such
+//! as `tc128` or `tm8192`, is enabled. All are enabled by default; to save flash, disable
+//! default features and enable only the codes you need to encode. Every code can always be
+//! decoded, as the parity check constants are small and shared between codes.
+//!
 //! ### Parity Check Matrices
 //!
 //! These are the counterpart to the generator matrices of the previous section. They are used by
@@ -107,6 +123,13 @@
 //! The constants are in `codes.compact_parity_checks` and reflect the construction defined
 //! in the CCSDS documents.
 //!
+//! All the decoders read these constants directly through `LDPCCode::iter_paritychecks()`,
+//! computing each edge of the parity check matrix on the fly, so no RAM is ever used to hold
+//! an expanded parity check matrix. In particular the message passing decoders keep no
+//! check-to-variable or variable-to-check adjacency tables: each iteration walks the edges in
+//! check order, and the per-variable sums are accumulated into one marginal per bit as it
+//! goes, so their working area holds only the messages and marginals themselves.
+//!
 //! ## Encoders
 //!
 //! There are two encoder methods implemented on `LDPCCode`: `encode` and `copy_encode`.
@@ -121,13 +144,19 @@
 //! general the larger types will encode up to three times faster, so it's usually worth using
 //! them. They are interpreted as containing your data in little-endian, so you can directly
 //! cast between the `&[u8]` and larger interpretations on all little-endian systems (which is to
-//! say, most systems).
+//! say, most systems). If your data is also held in words, `copy_encode_words` takes it in the
+//! same type as the codeword.
 //!
 //! The encode methods always return an `&mut [u8]` view on the codeword memory, which you
 //! can use if you need this type for further use (such as transmission out of a radio), or if you
 //! ignore the return value you can continue using your original slice of codeword memory.
 //!
+//! Bits are packed into bytes most significant bit first, so the first bit of a codeword is the
+//! top bit of its first byte. The `bits` module has `pack_bits` and `unpack_bits` to convert
+//! between this packed form and one bit per byte.
+//!
 //! ```
+//! # #[cfg(feature = "tc128")] {
 //! # use labrador_ldpc::LDPCCode;
 //! let code = LDPCCode::TC128;
 //!
@@ -141,8 +170,20 @@
 //! let mut codeword: [u64; 2] = [0x0706050403020100, 0x0000000000000000];
 //! code.encode(&mut codeword);
 //! assert_eq!(codeword, [0x0706050403020100, 0x5662E19487989934]);
+//! # }
 //! ```
 //!
+//! `encoder::Encoder` wraps a code and its generator matrix, returning an `EncodeError` rather
+//! than panicking on bad buffer lengths, and can encode using a generator matrix you provide.
+//! `encoder::StreamEncoder` uses an `Encoder` to protect a stream of data of any length,
+//! splitting it into k-bit blocks and zero padding the last one. For a single message shorter
+//! than k bits, `encode_shortened` leaves the zero padding out of the transmitted codeword
+//! entirely, and `decode_ms_shortened` restores it as known bits when decoding.
+//!
+//! A third method, `encode_from_parity`, computes the same codeword using only the parity check
+//! matrix, by solving the parity check equations directly. It is far slower and needs a large
+//! working area, so is mostly useful as a cross-check, or for codes without a generator matrix.
+//!
 //! The required memory (in bytes) to encode with each code is:
 //!
 //! Code   | Input (RAM) | Output (RAM)    | Generator const (text)
@@ -157,6 +198,23 @@
 //! TM5120 |         512 |             640 |            4096
 //! TM6144 |         512 |             768 |            4096
 //! TM8192 |         512 |            1024 |            4096
+//! TM20480|        2048 |            2560 |               -
+//! TM24576|        2048 |            3072 |               -
+//! TM32768|        2048 |            4096 |               -
+//!
+//! On channels with burst errors, such as those with fading, the `interleave` module's
+//! `RowColumnInterleaver` spreads each burst across several codewords so that each sees only
+//! a few errors.
+//!
+//! When transmitting with QPSK, 8PSK or 16-QAM rather than BPSK, `modulation::llrs_from_qam`
+//! turns the received symbols into LLRs in codeword bit order, ready for the soft decoders.
+//! Other soft metrics, such as the correlations of differential BPSK, can be scaled into LLRs
+//! with `modulation::llrs_from_metric`, whose documentation describes how to choose the scale.
+//! The `modulation` module documentation also explains how its `llrs_from_*` functions
+//! relate to the `LDPCCode::*_to_llrs` methods.
+//!
+//! To offer several rates from one code, `puncture::RateCompatible` punctures a chosen set of
+//! bits from each codeword and routes encoding and decoding through the base code.
 //!
 //! ## Decoders
 //!
@@ -186,6 +244,9 @@
 //! TM5120 |         640 |       5120T |      704 |          366 |          5632 | 48640T   + 192
 //! TM6144 |         768 |       6144T |      896 |          366 |          7168 | 60416T   + 384
 //! TM8192 |        1024 |       8192T |     1280 |          366 |         10240 | 83968T   + 768
+//! TM20480|        2560 |      20480T |     2816 |          366 |         22528 | 194560T  + 768
+//! TM24576|        3072 |      24576T |     3584 |          366 |         28672 | 241664T  +1536
+//! TM32768|        4096 |      32768T |     5120 |          366 |         40960 | 335872T  +3072
 //!
 //! `T` reflects the size of the type for your soft information: for `i8` this is 1, for `i16` 2,
 //! for `i32` and `f32` it's 4, and for `f64` it is 8. You should use a type commensurate with
@@ -196,11 +257,25 @@
 //! soft inputs and uses soft information internally, requiring a larger working area.
 //!
 //! The required sizes are available both at compile-time in the `CodeParams` consts, and at
-//! runtime with methods on `LDPCCode` such as `decode_ms_working_len()`. You can therefore
-//! allocate the required memory either statically or dynamically at runtime.
+//! runtime with methods on `LDPCCode` such as `decode_ms_working_len()`. These methods are
+//! `const fn`, so they can also size static arrays directly, for example
+//! `static mut OUTPUT: [u8; LDPCCode::TC128.output_len()]`. You can therefore allocate the
+//! required memory either statically or dynamically at runtime.
+//!
+//! Every decoder writes the complete corrected codeword to `output`, not just the data: the
+//! first k bits are the decoded data, followed by the n-k parity bits, and then for the TM
+//! codes the p punctured bits, which are never transmitted but are estimated by the decoder
+//! along with everything else. The first n/8 bytes of `output` are therefore a corrected copy
+//! of the transmitted codeword, ready to be relayed or combined with later receptions, and the
+//! whole of `output` satisfies every parity check whenever decoding succeeds.
 //!
 //! Please see the individual decoder methods for more details on their requirements.
 //!
+//! If you decode many codewords with the same code, `decoder::MsDecoder` bundles the code and
+//! the `decode_ms` working areas together, checking their lengths once when it is created.
+//! `decoder::StreamDecoder` uses an `MsDecoder` to decode a stream of LLRs written in pieces of
+//! any length, recovering the data written to an `encoder::StreamEncoder`.
+//!
 //! ### Bit Flipping Decoder
 //! This decoder is based on the original Gallagher decoder. It is not very optimal but is fast.
 //! The idea is to see which bits are connected to the highest number of parity checks that are not
@@ -208,7 +283,18 @@
 //! However, this routine cannot correct erasures (it only knows about bit flips). All of the TM
 //! codes are *punctured*, which means some parity bits are not transmitted and so are unknown at
 //! the receiver. We use a separate algorithm to decode the erasures first, based on a paper by
-//! Archonta, Kanistras and Paliouras, doi:10.1109/MOCAST.2016.7495161.
+//! Archonta, Kanistras and Paliouras, doi:10.1109/MOCAST.2016.7495161. This erasure decoder is
+//! also available directly as `decode_erasures`, for channels which mark bits as erased.
+//!
+//! If a coarse reliability is available for each received bit, `decode_wbf` implements the
+//! weighted bit flipping algorithm of Kou, Lin and Fossorier, doi:10.1109/18.959255, which
+//! sits between `decode_bf` and `decode_ms` in both performance and cost. With full soft
+//! information, `decode_gdbf` implements gradient descent bit flipping (Wadayama et al.,
+//! arXiv:0711.0261), which usually performs better again while still passing no messages.
+//!
+//! `decode_gallager_b` implements Gallager's algorithm B, passing hard messages along each
+//! parity check edge. It needs a little more working area than `decode_bf`, and typically
+//! corrects more errors on the TM codes.
 //!
 //! ### Message Passing Decoder
 //! This is a modified min-sum decoder that computes the probability of each bit being set given
@@ -216,12 +302,103 @@
 //! so inherently covers the punctured codes as well. This implementation is based on one described
 //! by Savin, arXiv:0803.1090. It is both reasonably efficient (no `atahn` required), and
 //! performs very close to optimal sum-product decoding.
+//!
+//! Positive LLRs indicate a 0 bit and negative LLRs a 1 bit. `hard_to_llrs` and
+//! `hard_to_llrs_scaled` convert hard bits into LLRs, and `bpsk_awgn_to_llrs` converts received
+//! BPSK samples (0 sent as +1, 1 sent as -1) into LLRs for an AWGN channel of known variance.
+//! With the `std` feature, `bsc_to_llrs` converts hard bits from a binary symmetric channel with
+//! known crossover probability. If your demodulator outputs fixed-point `i16` soft decisions,
+//! `decode_ms_q` converts them to `f32` as it decodes, given their number of fractional bits.
+//!
+//! Two common corrections to min-sum are also available: `decode_ms_normalized` scales every
+//! check-to-variable message by a constant factor, and `decode_ms_offset` subtracts a constant
+//! offset from them. Either can recover a little more of the gap to sum-product decoding.
+//! `decode_ms_clamped` instead limits the magnitude of every LLR and message, which keeps the
+//! decoder well behaved with badly scaled inputs. `decode_ms_damped` blends each message with
+//! the previous one on the same edge, which can help when the decoder oscillates.
+//!
+//! `decode_ms_layered` runs the same algorithm with a layered schedule, processing one block
+//! row of parity checks at a time and updating the marginals immediately, which usually
+//! converges in noticeably fewer iterations. It uses the same working areas as `decode_ms`.
+//!
+//! If your data carries its own check such as a CRC, `decode_ms_crc` only stops once that
+//! check also passes, catching the rare convergence to a valid but incorrect codeword.
+//!
+//! If you can spare `4*paritycheck_sum` bytes of RAM, `init_ms_lookup` precomputes the parity
+//! check edges into a table, and `decode_ms_lookup` then decodes using that table, which is
+//! typically somewhat faster than recomputing the edges from the compact constants.
+//! `init_ms_lookup_step` fills the same table one block row at a time, for systems that need
+//! to spread that startup work across several short calls.
+//!
+//! `decode_ms_reference` is a deliberately unoptimised implementation of the same min-sum
+//! algorithm, far too slow for normal use, which gives bit-for-bit identical results to
+//! `decode_ms` and so serves as a trusted baseline when checking the faster decoders.
+//!
+//! The min-sum check node update itself is available as `decoder::check_node_update`, for
+//! assembling decoders with other schedules from the same core.
+//!
+//! With the `alloc` feature enabled, `ms_decoder_alloc` creates an `OwnedMsDecoder` which
+//! allocates its own correctly sized working areas, for applications where convenience matters
+//! more than avoiding allocation.
+//!
+//! ### Decoding in Parallel
+//! Codewords are independent, so many can be decoded at once on a multi-core host. All the
+//! code constants are immutable statics and `LDPCCode` is `Copy + Send + Sync`, so the only
+//! mutable state is the working areas passed to each call. Give each thread its own working
+//! areas (or its own `MsDecoder`) and share everything else:
+//!
+//! ```
+//! # use labrador_ldpc::LDPCCode;
+//! let code = LDPCCode::TM1280;
+//! let llrs = vec![1.0f32; 16 * code.n()];
+//! let mut outputs = vec![0u8; 16 * code.output_len()];
+//!
+//! std::thread::scope(|s| {
+//!     // Split the batch into four parts, one per thread
+//!     let parts = llrs.chunks(4 * code.n()).zip(outputs.chunks_mut(4 * code.output_len()));
+//!     for (llrs, outputs) in parts {
+//!         s.spawn(move || {
+//!             let mut working = vec![0f32; code.decode_ms_working_len()];
+//!             let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+//!             let blocks = llrs.chunks(code.n()).zip(outputs.chunks_mut(code.output_len()));
+//!             for (llrs, output) in blocks {
+//!                 code.decode_ms(llrs, output, &mut working, &mut working_u8,
+//!                                LDPCCode::DEFAULT_MAX_ITERS);
+//!             }
+//!         });
+//!     }
+//! });
+//! assert!(outputs.iter().all(|x| *x == 0));
+//! ```
+//!
+//! The crate itself never starts threads, so this works equally well with a thread pool.
+//!
+//! ### Simulation
+//! With the `std` feature enabled, the `sim` module provides `simulate_fer`, which measures the
+//! frame and bit error rates of a code and decoder over a simulated AWGN channel. Random
+//! numbers come from the `sim::Rng` trait, which you implement for your own generator.
+//!
+//! ### Sum-Product Decoder
+//! With the `std` feature enabled, the full sum-product decoder `decode_sp` is also available.
+//! It uses the same working areas as the min-sum decoder but is much slower, so it is mostly
+//! useful as a reference when analysing performance.
 
 #[cfg(test)]
 #[macro_use]
 extern crate std;
 
+#[cfg(all(feature = "std", not(test)))]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod bits;
 pub mod codes;
 pub mod encoder;
 pub mod decoder;
+pub mod interleave;
+pub mod modulation;
+pub mod puncture;
+pub mod sim;
 pub use codes::{LDPCCode};
@@ -0,0 +1,282 @@
+// Copyright 2017 Adam Greig
+// Licensed under the MIT license, see LICENSE for details.
+
+//! This module provides soft demapping of higher order modulations into LLRs for the decoders.
+//!
+//! With BPSK each received sample is directly the soft information for one bit, but with QPSK,
+//! 8PSK or 16-QAM each complex symbol carries several bits. `llrs_from_qam` computes the LLR of
+//! every bit of every symbol using the max-log approximation, writing them in transmission
+//! order so that they can be passed straight to `decode_ms`.
+//!
+//! For receivers producing some other symmetric soft metric per bit, such as the correlation
+//! used to detect differential BPSK, `llrs_from_metric` scales the metrics into LLRs.
+//!
+//! The LLR helpers follow one naming convention. Methods on `LDPCCode` named `*_to_llrs`
+//! (`hard_to_llrs`, `bpsk_awgn_to_llrs`, `bsc_to_llrs`) convert exactly one codeword and check
+//! their lengths against n. The free functions here, named `llrs_from_*`, do not depend on the
+//! code and accept any length, since a stream of symbols need not align with codewords.
+//!
+//! ```
+//! # #[cfg(feature = "tc128")] {
+//! # use labrador_ldpc::LDPCCode;
+//! # use labrador_ldpc::modulation::{llrs_from_qam, Modulation};
+//! let code = LDPCCode::TC128;
+//! let mut codeword = vec![0u8; code.n()/8];
+//! code.copy_encode(&[0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0], &mut codeword);
+//!
+//! // Modulate each group of four bits onto a 16-QAM symbol
+//! let modulation = Modulation::Qam16;
+//! let bits = modulation.bits_per_symbol();
+//! let symbols: Vec<(f32, f32)> = (0..code.n()/bits).map(|sym| {
+//!     let label = (0..bits).fold(0, |label, j| {
+//!         let idx = sym * bits + j;
+//!         (label << 1) | ((codeword[idx/8] >> (7 - (idx%8))) & 1) as usize
+//!     });
+//!     modulation.point(label)
+//! }).collect();
+//!
+//! // Demap the received symbols and decode
+//! let mut llrs = vec![0f32; code.n()];
+//! llrs_from_qam(&symbols, modulation, 0.05, &mut llrs);
+//! let mut working = vec![0f32; code.decode_ms_working_len()];
+//! let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+//! let mut output = vec![0u8; code.output_len()];
+//! let (success, _) = code.decode_ms(&llrs, &mut output, &mut working, &mut working_u8,
+//!                                   LDPCCode::DEFAULT_MAX_ITERS);
+//! assert!(success);
+//! assert_eq!(&output[..code.n()/8], &codeword[..]);
+//! # }
+//! ```
+
+use core::f32::consts::FRAC_1_SQRT_2;
+
+/// Gray mapped modulations supported by `llrs_from_qam`.
+///
+/// Every constellation is normalised to unit average symbol energy. Each symbol carries the
+/// bits of its label, most significant bit first, so the first bit transmitted is the most
+/// significant bit of the label.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub enum Modulation {
+    /// QPSK, two bits per symbol. The first bit sets the sign of I and the second the sign
+    /// of Q, with a 0 bit mapping to the positive value.
+    Qpsk,
+
+    /// 8PSK, three bits per symbol. The point at angle `m*π/4` carries the Gray code
+    /// `m ^ (m >> 1)`, so neighbouring points differ in exactly one bit.
+    Psk8,
+
+    /// 16-QAM, four bits per symbol. The first two bits select the I level and the last two
+    /// the Q level, each Gray mapped with 00, 01, 11 and 10 giving the levels 3, 1, -1 and -3
+    /// before normalisation.
+    Qam16,
+}
+
+/// 8PSK constellation points indexed by label.
+const PSK8_POINTS: [(f32, f32); 8] = [
+    (1.0, 0.0), (FRAC_1_SQRT_2, FRAC_1_SQRT_2), (-FRAC_1_SQRT_2, FRAC_1_SQRT_2), (0.0, 1.0),
+    (FRAC_1_SQRT_2, -FRAC_1_SQRT_2), (0.0, -1.0), (-1.0, 0.0), (-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+];
+
+/// Gray mapped 16-QAM amplitude levels indexed by a two bit label, before normalisation.
+const QAM16_LEVELS: [f32; 4] = [3.0, 1.0, -3.0, -1.0];
+
+/// 1/sqrt(10), which normalises 16-QAM to unit average symbol energy.
+const QAM16_SCALE: f32 = 0.316_227_77;
+
+impl Modulation {
+    /// Get the number of bits carried by each symbol.
+    pub const fn bits_per_symbol(self) -> usize {
+        match self {
+            Modulation::Qpsk => 2,
+            Modulation::Psk8 => 3,
+            Modulation::Qam16 => 4,
+        }
+    }
+
+    /// Get the constellation point `(I, Q)` transmitted for `label`.
+    ///
+    /// `label` must be less than `1 << bits_per_symbol()`.
+    pub fn point(self, label: usize) -> (f32, f32) {
+        assert!(label < 1 << self.bits_per_symbol(), "label out of range");
+        match self {
+            Modulation::Qpsk => {
+                let i = if label & 0b10 == 0 { FRAC_1_SQRT_2 } else { -FRAC_1_SQRT_2 };
+                let q = if label & 0b01 == 0 { FRAC_1_SQRT_2 } else { -FRAC_1_SQRT_2 };
+                (i, q)
+            },
+            Modulation::Psk8 => PSK8_POINTS[label],
+            Modulation::Qam16 => (QAM16_LEVELS[label >> 2] * QAM16_SCALE,
+                                  QAM16_LEVELS[label & 0b11] * QAM16_SCALE),
+        }
+    }
+}
+
+/// Compute the LLR of every bit carried by `symbols`, received with `modulation`.
+///
+/// `sigma2` is the noise variance in each of I and Q, relative to the unit average symbol
+/// energy of the constellation. As the floating point min-sum decoder is invariant to scaling
+/// all its LLRs, any positive `sigma2` gives the same result there, but an accurate value is
+/// needed for the offset min-sum and sum-product decoders. See `llrs_from_metric` for how the
+/// scale affects each decoder.
+///
+/// The max-log approximation is used: the LLR of each bit is the difference between the
+/// squared distances to the nearest constellation point with that bit set and the nearest
+/// with it clear, divided by `2*sigma2`. As for the decoders, a positive LLR means the bit is
+/// more likely to be 0.
+///
+/// `llrs` must be `symbols.len() * modulation.bits_per_symbol()` long, and is written with the
+/// bits of each symbol in turn in transmission order, most significant bit of the label first.
+/// If n is not a multiple of the bits per symbol, the final symbol of each codeword will have
+/// been padded by the transmitter, and those LLRs should be discarded.
+pub fn llrs_from_qam(symbols: &[(f32, f32)], modulation: Modulation, sigma2: f32,
+                     llrs: &mut [f32])
+{
+    let bits = modulation.bits_per_symbol();
+    assert_eq!(llrs.len(), symbols.len() * bits, "llrs.len() != symbols.len() * bits");
+    assert!(sigma2 > 0.0, "sigma2 must be positive");
+
+    for (&(i, q), llrs) in symbols.iter().zip(llrs.chunks_mut(bits)) {
+        // Smallest squared distance to a point with each bit clear and set
+        let mut min0 = [f32::MAX; 4];
+        let mut min1 = [f32::MAX; 4];
+        for label in 0..1 << bits {
+            let (pi, pq) = modulation.point(label);
+            let d = (i - pi) * (i - pi) + (q - pq) * (q - pq);
+            for j in 0..bits {
+                if (label >> (bits - 1 - j)) & 1 == 0 {
+                    min0[j] = f32::min(min0[j], d);
+                } else {
+                    min1[j] = f32::min(min1[j], d);
+                }
+            }
+        }
+        for (j, llr) in llrs.iter_mut().enumerate() {
+            *llr = (min1[j] - min0[j]) / (2.0 * sigma2);
+        }
+    }
+}
+
+/// Convert arbitrary symmetric soft metrics, one per bit, into LLRs by scaling.
+///
+/// Not every receiver produces a Gaussian sample per bit. With differential BPSK, for
+/// instance, the useful quantity is the correlation `Re(r[i] * conj(r[i-1]))` between
+/// consecutive received symbols. Any such metric may be used by the decoders provided it
+/// follows the same contract as an LLR:
+///
+/// * it is positive when the bit is more likely to be 0 and negative when it is more
+///   likely to be 1, so the metric for a 1 bit is distributed as the negation of that for a
+///   0 bit, and
+/// * larger magnitudes mean more confidence.
+///
+/// Each LLR is then `scale * metric`. How much `scale` matters depends on the decoder:
+///
+/// * With floating point LLRs, `decode_ms` and `decode_ms_normalized` are invariant to
+///   scaling, so any positive `scale` gives the same result.
+/// * Integer LLRs are rounded and saturate, so `scale` sets the fixed-point resolution of
+///   `decode_ms` and changes its result. Choose it so that typical LLRs use a good part of the
+///   integer range while keeping the headroom described in `decode_ms`.
+/// * `decode_ms_offset` and the sum-product decoder use the magnitudes, so for them `scale`
+///   should make each LLR close to the true `ln(P(bit=0)/P(bit=1))`.
+/// * The bit flipping decoders take hard bits rather than LLRs, so only the sign of each
+///   metric matters; `llrs_to_hard` gives the bits.
+///
+/// If the metric is approximately Gaussian with mean `mu` for a 0 bit and variance `var`, the
+/// true LLR is given by `scale = 2*mu/var`. Both may be estimated from the magnitudes of
+/// metrics received at a known operating point, or from frames that decoded correctly. For the
+/// differential BPSK metric above at moderate to high SNR, `mu` is the symbol energy and `var`
+/// is the symbol energy times the complex noise variance `N0`, giving `scale = 2/N0`.
+///
+/// `metrics` and `llrs` must be the same length, and `scale` must be positive.
+pub fn llrs_from_metric(metrics: &[f32], scale: f32, llrs: &mut [f32]) {
+    assert_eq!(llrs.len(), metrics.len(), "llrs.len() != metrics.len()");
+    assert!(scale > 0.0, "scale must be positive");
+    for (llr, metric) in llrs.iter_mut().zip(metrics.iter()) {
+        *llr = metric * scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::v1::*;
+
+    use super::{llrs_from_qam, Modulation};
+
+    #[test]
+    fn test_llrs_from_qam() {
+        for &modulation in &[Modulation::Qpsk, Modulation::Psk8, Modulation::Qam16] {
+            let bits = modulation.bits_per_symbol();
+            let labels = 1 << bits;
+
+            // Unit average energy
+            let energy: f32 = (0..labels).map(|l| modulation.point(l))
+                                         .map(|(i, q)| i*i + q*q).sum();
+            assert!((energy / labels as f32 - 1.0).abs() < 1e-5);
+
+            // Each noiseless symbol demaps to LLRs with the signs of its label's bits
+            let symbols: Vec<(f32, f32)> = (0..labels).map(|l| modulation.point(l)).collect();
+            let mut llrs = vec![0f32; labels * bits];
+            llrs_from_qam(&symbols, modulation, 0.1, &mut llrs);
+            for label in 0..labels {
+                for j in 0..bits {
+                    let llr = llrs[label * bits + j];
+                    if (label >> (bits - 1 - j)) & 1 == 0 {
+                        assert!(llr > 0.0);
+                    } else {
+                        assert!(llr < 0.0);
+                    }
+                }
+            }
+
+            // Gray mapping: the nearest neighbours of each point differ in exactly one bit
+            for a in 0..labels {
+                let (ai, aq) = modulation.point(a);
+                let dist = |b: usize| {
+                    let (bi, bq) = modulation.point(b);
+                    (ai - bi) * (ai - bi) + (aq - bq) * (aq - bq)
+                };
+                let nearest = (0..labels).filter(|&b| b != a).map(dist)
+                                         .fold(f32::MAX, f32::min);
+                for b in (0..labels).filter(|&b| b != a && dist(b) < nearest + 1e-5) {
+                    assert_eq!((a ^ b).count_ones(), 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tc256")]
+    fn test_llrs_from_metric() {
+        use crate::codes::LDPCCode;
+        use super::llrs_from_metric;
+        let code = LDPCCode::TC256;
+        let txdata: Vec<u8> = (0..code.k()/8).map(|i| (i * 23) as u8).collect();
+        let mut codeword = vec![0u8; code.n()/8];
+        code.copy_encode(&txdata, &mut codeword);
+
+        // Differential BPSK: each bit is carried by the phase change between two symbols,
+        // with no change for a 0 bit, and the metric is the correlation between them
+        let mut phase = 1.0f32;
+        let mut symbols = vec![phase];
+        for idx in 0..code.n() {
+            if (codeword[idx/8] >> (7 - (idx%8))) & 1 == 1 {
+                phase = -phase;
+            }
+            symbols.push(phase);
+        }
+        let received: Vec<f32> = symbols.iter().enumerate()
+                                        .map(|(i, s)| s * 0.5 + ((i * 37) % 11) as f32 * 0.04)
+                                        .collect();
+        let metrics: Vec<f32> = received.windows(2).map(|w| w[0] * w[1]).collect();
+
+        let mut llrs = vec![0f32; code.n()];
+        llrs_from_metric(&metrics, 4.0, &mut llrs);
+        assert!(llrs.iter().zip(metrics.iter()).all(|(l, m)| *l == m * 4.0));
+
+        let mut working = vec![0f32; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        let mut output = vec![0u8; code.output_len()];
+        let (success, _) = code.decode_ms(&llrs, &mut output, &mut working, &mut working_u8, 20);
+        assert!(success);
+        assert_eq!(&output[..], &codeword[..]);
+    }
+}
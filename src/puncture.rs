@@ -0,0 +1,170 @@
+// Copyright 2017 Adam Greig
+// Licensed under the MIT license, see LICENSE for details.
+
+//! This module provides rate-compatible codes, derived from a base code by puncturing.
+//!
+//! Removing some bits from each transmitted codeword raises the rate of a code, so one base
+//! code can offer several effective rates while only its constants are ever needed.
+//! `RateCompatible` wraps a base `LDPCCode` and a puncturing pattern, and routes encoding and
+//! decoding through the base code with the extra puncturing applied, so that higher rates can
+//! be selected at runtime, for example by an adaptive link.
+//!
+//! ```
+//! # #[cfg(feature = "tm1280")] {
+//! # use labrador_ldpc::LDPCCode;
+//! # use labrador_ldpc::puncture::RateCompatible;
+//! // Raise TM1280 from rate 4/5 to rate 8/9 by not sending every other parity bit
+//! let pattern: Vec<usize> = (1024..1280).step_by(2).collect();
+//! let rc = RateCompatible::new(LDPCCode::TM1280).with_puncture(&pattern);
+//! assert_eq!((rc.n(), rc.k()), (1152, 1024));
+//!
+//! let data = [0x5A; 128];
+//! let mut codeword = vec![0u8; rc.code().n()/8];
+//! let mut tx = vec![0u8; rc.output_len()];
+//! rc.encode(&data, &mut codeword, &mut tx);
+//!
+//! // Receive each bit as an LLR and decode
+//! let received: Vec<f32> = (0..rc.n())
+//!     .map(|i| if (tx[i/8] >> (7 - (i%8))) & 1 == 1 { -1.0 } else { 1.0 }).collect();
+//! let code = rc.code();
+//! let mut llrs = vec![0f32; code.n()];
+//! let mut working = vec![0f32; code.decode_ms_working_len()];
+//! let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+//! let mut output = vec![0u8; code.output_len()];
+//! let (success, _) = rc.decode_ms(&received, &mut llrs, &mut output, &mut working,
+//!                                 &mut working_u8, LDPCCode::DEFAULT_MAX_ITERS);
+//! assert!(success);
+//! assert_eq!(&output[..128], &data[..]);
+//! # }
+//! ```
+
+use crate::codes::LDPCCode;
+use crate::decoder::DecodeFrom;
+
+/// A code derived from a base `LDPCCode` by puncturing additional bits.
+///
+/// The puncturing pattern lists the positions in 0..n of the base codeword which are not
+/// transmitted, in strictly increasing order, exactly as for `LDPCCode::puncture`. Puncturing
+/// parity bits generally performs better than puncturing data bits.
+///
+/// The pattern is borrowed, so many rates may share one base code without any allocation.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub struct RateCompatible<'a> {
+    code: LDPCCode,
+    pattern: &'a [usize],
+}
+
+impl RateCompatible<'static> {
+    /// Create a view of `code` with no extra puncturing, at its own rate.
+    pub fn new(code: LDPCCode) -> RateCompatible<'static> {
+        RateCompatible { code, pattern: &[] }
+    }
+}
+
+impl<'a> RateCompatible<'a> {
+    /// Puncture the bits of the base code listed in `pattern` as well.
+    ///
+    /// `pattern` must be in strictly increasing order with every entry less than the base
+    /// code's n, and replaces any previous pattern.
+    pub fn with_puncture<'b>(self, pattern: &'b [usize]) -> RateCompatible<'b> {
+        assert!(pattern.windows(2).all(|w| w[0] < w[1]), "pattern must be sorted");
+        assert!(pattern.iter().all(|&x| x < self.code.n()), "pattern >= n");
+        RateCompatible { code: self.code, pattern }
+    }
+
+    /// Get the base code.
+    pub fn code(&self) -> LDPCCode {
+        self.code
+    }
+
+    /// Get the puncturing pattern.
+    pub fn pattern(&self) -> &'a [usize] {
+        self.pattern
+    }
+
+    /// Get the effective code length, the number of bits transmitted per codeword.
+    pub fn n(&self) -> usize {
+        self.code.n() - self.pattern.len()
+    }
+
+    /// Get the code dimension, the number of information bits, which is the base code's k.
+    pub fn k(&self) -> usize {
+        self.code.k()
+    }
+
+    /// Get the effective rate k/n.
+    pub fn rate(&self) -> f64 {
+        self.k() as f64 / self.n() as f64
+    }
+
+    /// Get the length of [u8] of a transmitted codeword, equal to ceil(n()/8).
+    pub fn output_len(&self) -> usize {
+        self.code.punctured_len(self.pattern.len())
+    }
+
+    /// Encode `data` and puncture it for transmission, see `LDPCCode::encode_punctured`.
+    ///
+    /// * `data` must be k/8 bytes long
+    /// * `codeword` must be the base code's n/8 bytes long, and receives the full codeword
+    /// * `output` must be `output_len()` long, and receives the bits to transmit
+    pub fn encode(&self, data: &[u8], codeword: &mut [u8], output: &mut [u8]) {
+        self.code.encode_punctured(data, codeword, self.pattern, output);
+    }
+
+    /// Decode received LLRs with the base code's min-sum decoder.
+    ///
+    /// The punctured bits are given an LLR of zero with `LDPCCode::depuncture_llrs`, and the
+    /// result is decoded with `LDPCCode::decode_ms`.
+    ///
+    /// * `received` must be `n()` long, the LLRs of the transmitted bits in order
+    /// * `llrs` must be the base code's n long, and receives the depunctured LLRs
+    ///
+    /// All other buffers and the return value are as for `decode_ms` on the base code.
+    pub fn decode_ms<T: DecodeFrom>(&self, received: &[T], llrs: &mut [T], output: &mut [u8],
+                                    working: &mut [T], working_u8: &mut [u8], maxiters: usize)
+        -> (bool, usize)
+    {
+        self.code.depuncture_llrs(received, self.pattern, llrs);
+        self.code.decode_ms(llrs, output, working, working_u8, maxiters)
+    }
+}
+
+#[cfg(all(test, feature = "tm2048"))]
+mod tests {
+    use std::prelude::v1::*;
+
+    use super::RateCompatible;
+    use crate::codes::LDPCCode;
+
+    #[test]
+    fn test_rate_compatible() {
+        let code = LDPCCode::TM2048;
+        let data: Vec<u8> = (0..code.k()/8).map(|x| (x * 11) as u8).collect();
+        let mut codeword = vec![0u8; code.n()/8];
+        let mut llrs = vec![0i16; code.n()];
+        let mut working = vec![0i16; code.decode_ms_working_len()];
+        let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+        let mut output = vec![0u8; code.output_len()];
+
+        // The base rate and two higher rates from the same code, puncturing parity bits
+        let light: Vec<usize> = (code.k()..code.n()).step_by(8).collect();
+        let heavy: Vec<usize> = (code.k()..code.n()).step_by(4).collect();
+        let base = RateCompatible::new(code);
+        let mut last_rate = 0.0;
+        for rc in &[base, base.with_puncture(&light), base.with_puncture(&heavy)] {
+            assert_eq!(rc.n(), code.n() - rc.pattern().len());
+            assert!(rc.rate() > last_rate);
+            last_rate = rc.rate();
+
+            let mut tx = vec![0u8; rc.output_len()];
+            rc.encode(&data, &mut codeword, &mut tx);
+            let received: Vec<i16> = (0..rc.n())
+                .map(|i| if (tx[i/8] >> (7 - (i%8))) & 1 == 1 { -8 } else { 8 }).collect();
+            let (success, _) = rc.decode_ms(&received, &mut llrs, &mut output, &mut working,
+                                            &mut working_u8, 50);
+            assert!(success);
+            assert_eq!(&output[..code.n()/8], &codeword[..]);
+        }
+        assert_eq!(base.rate(), 0.5);
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright 2017 Adam Greig
+// Licensed under the MIT license, see LICENSE for details.
+
+//! This module provides a simple simulation harness for measuring decoder performance.
+//!
+//! `simulate_fer` encodes random data, transmits it as BPSK over an AWGN channel at a given
+//! Eb/N0, decodes it, and counts the frame and bit errors. This makes it easy to check a code
+//! and decoder choice against the published waterfall curves, or to sanity check the scaling
+//! of your own channel model.
+//!
+//! The random number generator is supplied by the caller through the `Rng` trait, which is
+//! implemented for any closure returning uniformly distributed `u32`s, so results are
+//! reproducible and no particular RNG crate is required. The trait itself needs no `std`, so
+//! it can also be implemented for an embedded target's own generator.
+//!
+//! `simulate_fer` is only available with the `std` feature, which it uses for the floating
+//! point functions required to generate Gaussian noise.
+//!
+//! ```
+//! # #[cfg(all(feature = "std", feature = "tc128"))] {
+//! # use labrador_ldpc::LDPCCode;
+//! # use labrador_ldpc::sim::{simulate_fer, SimDecoder};
+//! // A simple xorshift generator; use a better RNG for serious measurements.
+//! let mut state = 0x2545_F491u32;
+//! let mut rng = || { state ^= state << 13; state ^= state >> 17; state ^= state << 5; state };
+//!
+//! let result = simulate_fer(LDPCCode::TC128, SimDecoder::MinSum, 5.0, 20, 20, &mut rng);
+//! assert_eq!(result.frames, 20);
+//! assert!(result.fer() < 0.5);
+//! # }
+//! ```
+
+#[cfg(feature = "std")]
+use alloc::vec;
+
+#[cfg(feature = "std")]
+use crate::codes::LDPCCode;
+
+/// A source of uniformly distributed random numbers.
+///
+/// Implement this for your own generator, or pass any closure returning uniformly
+/// distributed `u32`s, which implements it automatically.
+pub trait Rng {
+    /// Return the next uniformly distributed `u32`.
+    fn next_u32(&mut self) -> u32;
+
+    /// Fill `dest` with uniformly distributed bytes.
+    ///
+    /// By default each byte is the low byte of a separate call to `next_u32`.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest {
+            *byte = self.next_u32() as u8;
+        }
+    }
+}
+
+impl<F: FnMut() -> u32> Rng for F {
+    fn next_u32(&mut self) -> u32 {
+        self()
+    }
+}
+
+/// Which decoder to use in a simulation.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub enum SimDecoder {
+    /// The bit flipping decoder `decode_bf`, using hard decisions on each received sample.
+    BitFlipping,
+
+    /// The min-sum decoder `decode_ms`, using `f32` LLRs.
+    MinSum,
+}
+
+/// Results of a simulation run.
+#[derive(Copy,Clone,Debug,Eq,PartialEq,Hash)]
+pub struct FerResult {
+    /// Number of frames simulated.
+    pub frames: usize,
+
+    /// Number of frames with at least one data bit in error after decoding.
+    pub frame_errors: usize,
+
+    /// Number of data bits simulated.
+    pub bits: usize,
+
+    /// Number of data bits in error after decoding.
+    pub bit_errors: usize,
+}
+
+impl FerResult {
+    /// Frame error rate, the fraction of frames with any data bit in error.
+    pub fn fer(&self) -> f64 {
+        self.frame_errors as f64 / self.frames as f64
+    }
+
+    /// Bit error rate, the fraction of data bits in error.
+    pub fn ber(&self) -> f64 {
+        self.bit_errors as f64 / self.bits as f64
+    }
+}
+
+/// Generate a uniformly distributed number in (0, 1] from `rng`.
+#[cfg(feature = "std")]
+fn uniform<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    (f64::from(rng.next_u32()) + 1.0) / 4_294_967_296.0
+}
+
+/// Simulate `num_frames` frames of `code` over a BPSK AWGN channel at `ebn0_db` dB Eb/N0.
+///
+/// For each frame, random data is drawn from `rng`, encoded, modulated as +1 for each 0 bit
+/// and -1 for each 1 bit, corrupted with Gaussian noise of the variance corresponding to
+/// `ebn0_db` at this code's rate, and then decoded by `decoder` with at most `maxiters`
+/// iterations. Errors are counted over the decoded data bits only.
+///
+/// `rng` supplies all the random data and noise; see `Rng`.
+///
+/// Panics for codes without a generator matrix (the k=16384 codes).
+#[cfg(feature = "std")]
+pub fn simulate_fer<R>(code: LDPCCode, decoder: SimDecoder, ebn0_db: f64, num_frames: usize,
+                       maxiters: usize, rng: &mut R)
+    -> FerResult
+    where R: Rng + ?Sized
+{
+    let n = code.n();
+    let k = code.k();
+    let rate = k as f64 / n as f64;
+    let sigma2 = 1.0 / (2.0 * rate * 10f64.powf(ebn0_db / 10.0));
+    let sigma = sigma2.sqrt();
+
+    let mut data = vec![0u8; k/8];
+    let mut codeword = vec![0u8; n/8];
+    let mut samples = vec![0f32; n];
+    let mut llrs = vec![0f32; n];
+    let mut hard = vec![0u8; n/8];
+    let mut output = vec![0u8; code.output_len()];
+    let mut working_bf = vec![0u8; code.decode_bf_working_len()];
+    let mut working_ms = vec![0f32; code.decode_ms_working_len()];
+    let mut working_u8 = vec![0u8; code.decode_ms_working_u8_len()];
+
+    let mut result = FerResult { frames: num_frames, frame_errors: 0, bits: num_frames * k,
+                                 bit_errors: 0 };
+
+    for _ in 0..num_frames {
+        rng.fill_bytes(&mut data);
+        code.copy_encode(&data, &mut codeword);
+
+        // Modulate and add noise, using the Box-Muller transform for Gaussian samples
+        for (idx, sample) in samples.iter_mut().enumerate() {
+            let bit = (codeword[idx/8] >> (7 - (idx%8))) & 1;
+            let symbol = if bit == 1 { -1.0 } else { 1.0 };
+            let (u1, u2) = (uniform(rng), uniform(rng));
+            let noise = (-2.0 * u1.ln()).sqrt() * (2.0 * core::f64::consts::PI * u2).cos();
+            *sample = (symbol + sigma * noise) as f32;
+        }
+
+        match decoder {
+            SimDecoder::BitFlipping => {
+                code.bpsk_awgn_to_llrs(&samples, sigma2 as f32, &mut llrs);
+                code.llrs_to_hard(&llrs, &mut hard);
+                code.decode_bf(&hard, &mut output, &mut working_bf, maxiters);
+            },
+            SimDecoder::MinSum => {
+                code.bpsk_awgn_to_llrs(&samples, sigma2 as f32, &mut llrs);
+                code.decode_ms(&llrs, &mut output, &mut working_ms, &mut working_u8, maxiters);
+            },
+        }
+
+        let errors: usize = data.iter().zip(output.iter())
+                                .map(|(a, b)| (a ^ b).count_ones() as usize).sum();
+        if errors > 0 {
+            result.frame_errors += 1;
+            result.bit_errors += errors;
+        }
+    }
+
+    result
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{simulate_fer, Rng, SimDecoder};
+    use crate::codes::{encodable, LDPCCode};
+
+    /// A seeded xorshift generator, as a user might implement `Rng` for their own PRNG.
+    struct XorShift(u32);
+
+    impl Rng for XorShift {
+        fn next_u32(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_rng() {
+        // A closure and the equivalent trait implementation give identical simulations
+        let mut state = 0x2545_F491u32;
+        let mut closure = || { state ^= state << 13; state ^= state >> 17; state ^= state << 5;
+                               state };
+        let mut xorshift = XorShift(0x2545_F491);
+        for code in encodable(&[LDPCCode::TC128]) {
+            let a = simulate_fer(code, SimDecoder::MinSum, 2.0, 20, 20, &mut closure);
+            let b = simulate_fer(code, SimDecoder::MinSum, 2.0, 20, 20, &mut xorshift);
+            assert_eq!(a, b);
+        }
+
+        let mut bytes = [0u8; 4];
+        XorShift(1).fill_bytes(&mut bytes);
+        let mut rng = XorShift(1);
+        let expected: [u8; 4] = core::array::from_fn(|_| rng.next_u32() as u8);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_simulate_fer() {
+        let mut state = 0x2545_F491u32;
+        let mut rng = || { state ^= state << 13; state ^= state >> 17; state ^= state << 5; state };
+
+        // At high Eb/N0 everything decodes, at very low Eb/N0 almost nothing does
+        for code in encodable(&[LDPCCode::TC256]) {
+            for &decoder in &[SimDecoder::BitFlipping, SimDecoder::MinSum] {
+                let good = simulate_fer(code, decoder, 8.0, 20, 20, &mut rng);
+                assert_eq!(good.frames, 20);
+                assert_eq!(good.bits, 20 * 128);
+                assert_eq!(good.frame_errors, 0);
+                assert_eq!(good.ber(), 0.0);
+
+                let bad = simulate_fer(code, decoder, -3.0, 20, 20, &mut rng);
+                assert!(bad.fer() > 0.5);
+                assert!(bad.bit_errors >= bad.frame_errors);
+            }
+        }
+
+        // The min-sum decoder beats bit flipping in the waterfall region
+        for code in encodable(&[LDPCCode::TM1280]) {
+            let bf = simulate_fer(code, SimDecoder::BitFlipping, 3.0, 50, 20, &mut rng);
+            let ms = simulate_fer(code, SimDecoder::MinSum, 3.0, 50, 20, &mut rng);
+            assert!(ms.frame_errors < bf.frame_errors);
+        }
+    }
+}